@@ -20,7 +20,7 @@ namespace ControlCommand
 
     inline std::optional<char> parseActive(const QByteArray &data)
     {
-        if (!data.startsWith(ControlCommand::HEADER))
+        if (!data.startsWith(ControlCommand::HEADER) || data.size() < 8)
             return std::nullopt;
 
         return static_cast<quint8>(data.at(7));
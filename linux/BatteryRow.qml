@@ -0,0 +1,42 @@
+import QtQuick 2.15
+
+// Levels of both buds, the case and single-unit headsets, shared by the main page and the device window
+Row {
+    spacing: 8
+
+    PodColumn {
+        visible: airPodsTrayApp.deviceInfo.battery.leftPodAvailable
+        inEar: airPodsTrayApp.deviceInfo.leftPodInEar
+        inCase: airPodsTrayApp.deviceInfo.leftPodInCase && !airPodsTrayApp.deviceInfo.rightPodInCase
+        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
+        batteryLevel: airPodsTrayApp.deviceInfo.battery.leftPodLevel
+        isCharging: airPodsTrayApp.deviceInfo.battery.leftPodCharging
+        indicator: "L"
+    }
+
+    PodColumn {
+        visible: airPodsTrayApp.deviceInfo.battery.rightPodAvailable
+        inEar: airPodsTrayApp.deviceInfo.rightPodInEar
+        inCase: airPodsTrayApp.deviceInfo.rightPodInCase && !airPodsTrayApp.deviceInfo.leftPodInCase
+        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
+        batteryLevel: airPodsTrayApp.deviceInfo.battery.rightPodLevel
+        isCharging: airPodsTrayApp.deviceInfo.battery.rightPodCharging
+        indicator: "R"
+    }
+
+    PodColumn {
+        visible: airPodsTrayApp.deviceInfo.battery.caseAvailable && !airPodsTrayApp.deviceInfo.ignoreCase
+        inEar: true
+        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.caseIcon
+        batteryLevel: airPodsTrayApp.deviceInfo.battery.caseLevel
+        isCharging: airPodsTrayApp.deviceInfo.battery.caseCharging
+    }
+
+    PodColumn {
+        visible: airPodsTrayApp.deviceInfo.battery.headsetAvailable
+        inEar: true
+        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
+        batteryLevel: airPodsTrayApp.deviceInfo.battery.headsetLevel
+        isCharging: airPodsTrayApp.deviceInfo.battery.headsetCharging
+    }
+}
@@ -5,6 +5,8 @@
 #include <QDBusObjectPath>
 #include <QDBusMetaType>
 
+#include <algorithm>
+
 BluetoothMonitor::BluetoothMonitor(QObject *parent)
     : QObject(parent), m_dbus(QDBusConnection::systemBus())
 {
@@ -117,15 +119,304 @@ bool BluetoothMonitor::checkAlreadyConnectedDevices()
     return deviceFound;
 }
 
+QVariantMap BluetoothMonitor::deviceProperties(const QString &macAddress)
+{
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        LOG_WARN("Failed to get managed objects: " << reply.errorMessage());
+        return QVariantMap();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    for (const QMap<QString, QVariantMap> &interfaces : std::as_const(managedObjects))
+    {
+        const QVariantMap deviceProps = interfaces.value("org.bluez.Device1");
+        if (deviceProps.value("Address").toString().compare(macAddress, Qt::CaseInsensitive) == 0)
+        {
+            return deviceProps;
+        }
+    }
+    return QVariantMap();
+}
+
+QString BluetoothMonitor::findDevicePath(const QString &macAddress)
+{
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        return QString();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    for (auto it = managedObjects.constBegin(); it != managedObjects.constEnd(); ++it)
+    {
+        if (it.value().value("org.bluez.Device1").value("Address").toString().compare(macAddress, Qt::CaseInsensitive) == 0)
+        {
+            return it.key().path();
+        }
+    }
+    return QString();
+}
+
+bool BluetoothMonitor::setDeviceConnected(const QString &macAddress, bool connected, std::function<void(bool)> done)
+{
+    QString path = findDevicePath(macAddress);
+    if (path.isEmpty())
+    {
+        LOG_WARN("Device " << macAddress << " is not known to BlueZ");
+        if (done)
+            done(false);
+        return false;
+    }
+
+    // Connecting can take several seconds, don't block the UI waiting for it
+    QDBusInterface device("org.bluez", path, "org.bluez.Device1", m_dbus);
+    QDBusPendingCallWatcher *watcher = new QDBusPendingCallWatcher(device.asyncCall(connected ? "Connect" : "Disconnect"), this);
+    connect(watcher, &QDBusPendingCallWatcher::finished, this, [macAddress, connected, done](QDBusPendingCallWatcher *call)
+            {
+                QDBusPendingReply<> reply = *call;
+                if (reply.isError())
+                    LOG_WARN("Failed to " << (connected ? "connect " : "disconnect ") << macAddress << ": " << reply.error().message());
+                if (done)
+                    done(!reply.isError());
+                call->deleteLater(); });
+    return true;
+}
+
+QVariantList BluetoothMonitor::pairedAirPods()
+{
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        LOG_WARN("Failed to get managed objects: " << reply.errorMessage());
+        return QVariantList();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    QVariantList devices;
+    for (const QMap<QString, QVariantMap> &interfaces : std::as_const(managedObjects))
+    {
+        const QVariantMap deviceProps = interfaces.value("org.bluez.Device1");
+        if (deviceProps.value("Paired").toBool() &&
+            deviceProps.value("UUIDs").toStringList().contains("74ec2172-0bad-4d01-8f77-997b2be0722a"))
+        {
+            devices << QVariantMap{
+                {"address", deviceProps.value("Address")},
+                {"name", deviceProps.value("Alias", deviceProps.value("Name"))},
+                {"connected", deviceProps.value("Connected")},
+            };
+        }
+    }
+    return devices;
+}
+
+QVariantList BluetoothMonitor::audioDevices()
+{
+    static const QStringList audioUuids = {
+        "0000110b-0000-1000-8000-00805f9b34fb", // A2DP sink
+        "0000111e-0000-1000-8000-00805f9b34fb", // Hands-Free
+        "00001108-0000-1000-8000-00805f9b34fb", // Headset
+    };
+
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        LOG_WARN("Failed to get managed objects: " << reply.errorMessage());
+        return QVariantList();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    QVariantList devices;
+    for (const QMap<QString, QVariantMap> &interfaces : std::as_const(managedObjects))
+    {
+        const QVariantMap deviceProps = interfaces.value("org.bluez.Device1");
+        const QStringList uuids = deviceProps.value("UUIDs").toStringList();
+        if (!deviceProps.value("Paired").toBool() || uuids.contains("74ec2172-0bad-4d01-8f77-997b2be0722a"))
+        {
+            continue;
+        }
+        bool audio = deviceProps.value("Icon").toString().startsWith("audio-") ||
+                     std::any_of(audioUuids.begin(), audioUuids.end(), [&uuids](const QString &uuid) { return uuids.contains(uuid); });
+        if (!audio)
+        {
+            continue;
+        }
+
+        devices << QVariantMap{
+            {"address", deviceProps.value("Address")},
+            {"name", deviceProps.value("Alias", deviceProps.value("Name"))},
+            {"icon", deviceProps.value("Icon")},
+            {"connected", deviceProps.value("Connected")},
+            {"battery", interfaces.contains("org.bluez.Battery1") ? interfaces.value("org.bluez.Battery1").value("Percentage").toInt() : -1},
+        };
+    }
+    return devices;
+}
+
+static QString macFromDevicePath(const QString &path)
+{
+    // Device paths look like /org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF, transports live below them
+    QString node = path.section('/', 4, 4);
+    return node.startsWith("dev_") ? node.mid(4).replace('_', ':') : QString();
+}
+
+QString BluetoothMonitor::findTransportPath(const QString &macAddress)
+{
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        return QString();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    for (auto it = managedObjects.constBegin(); it != managedObjects.constEnd(); ++it)
+    {
+        if (it.value().contains("org.bluez.MediaTransport1") &&
+            macFromDevicePath(it.key().path()).compare(macAddress, Qt::CaseInsensitive) == 0)
+        {
+            return it.key().path();
+        }
+    }
+    return QString();
+}
+
+int BluetoothMonitor::transportVolume(const QString &macAddress)
+{
+    QString path = findTransportPath(macAddress);
+    if (path.isEmpty())
+    {
+        return -1;
+    }
+
+    QDBusInterface transport("org.bluez", path, "org.freedesktop.DBus.Properties", m_dbus);
+    QDBusReply<QVariant> reply = transport.call("Get", "org.bluez.MediaTransport1", "Volume");
+    return reply.isValid() ? reply.value().toInt() : -1;
+}
+
+bool BluetoothMonitor::setTransportVolume(const QString &macAddress, int volume)
+{
+    QString path = findTransportPath(macAddress);
+    if (path.isEmpty())
+    {
+        return false;
+    }
+
+    QDBusInterface transport("org.bluez", path, "org.freedesktop.DBus.Properties", m_dbus);
+    QDBusReply<void> reply = transport.call("Set", "org.bluez.MediaTransport1", "Volume",
+                                            QVariant::fromValue(QDBusVariant(static_cast<quint16>(qBound(0, volume, 127)))));
+    if (!reply.isValid())
+    {
+        LOG_WARN("Failed to set transport volume: " << reply.error().message());
+        return false;
+    }
+    return true;
+}
+
+QString BluetoothMonitor::findAdapterPath()
+{
+    QDBusInterface objectManager("org.bluez", "/", "org.freedesktop.DBus.ObjectManager", m_dbus);
+    QDBusMessage reply = objectManager.call("GetManagedObjects");
+    if (reply.type() == QDBusMessage::ErrorMessage)
+    {
+        return QString();
+    }
+
+    ManagedObjectList managedObjects;
+    reply.arguments().constFirst().value<QDBusArgument>() >> managedObjects;
+
+    for (auto it = managedObjects.constBegin(); it != managedObjects.constEnd(); ++it)
+    {
+        if (it.value().contains("org.bluez.Adapter1"))
+        {
+            return it.key().path();
+        }
+    }
+    return QString();
+}
+
+QVariantMap BluetoothMonitor::adapterProperties()
+{
+    QString path = findAdapterPath();
+    if (path.isEmpty())
+    {
+        return QVariantMap();
+    }
+
+    QDBusInterface adapter("org.bluez", path, "org.freedesktop.DBus.Properties", m_dbus);
+    QDBusReply<QVariantMap> reply = adapter.call("GetAll", "org.bluez.Adapter1");
+    return reply.isValid() ? reply.value() : QVariantMap();
+}
+
+bool BluetoothMonitor::setAdapterProperty(const QString &name, bool value)
+{
+    QString path = findAdapterPath();
+    if (path.isEmpty())
+    {
+        LOG_WARN("No Bluetooth adapter found, cannot set " << name);
+        return false;
+    }
+
+    QDBusInterface adapter("org.bluez", path, "org.freedesktop.DBus.Properties", m_dbus);
+    QDBusReply<void> reply = adapter.call("Set", "org.bluez.Adapter1", name, QVariant::fromValue(QDBusVariant(value)));
+    if (!reply.isValid())
+    {
+        // Powering on fails with org.bluez.Error.Blocked while rfkill blocks the adapter
+        LOG_WARN("Failed to set adapter " << name << ": " << reply.error().message());
+        return false;
+    }
+    return true;
+}
+
 void BluetoothMonitor::onPropertiesChanged(const QString &interface, const QVariantMap &changedProps, const QStringList &invalidatedProps)
 {
     Q_UNUSED(invalidatedProps);
 
+    if (interface == "org.bluez.MediaTransport1" && changedProps.contains("Volume"))
+    {
+        emit transportVolumeChanged(macFromDevicePath(QDBusContext::message().path()), changedProps["Volume"].toInt());
+        return;
+    }
+
+    if (interface == "org.bluez.Battery1")
+    {
+        emit deviceBatteryChanged(macFromDevicePath(QDBusContext::message().path()));
+        return;
+    }
+
+    if (interface == "org.bluez.Adapter1")
+    {
+        emit adapterPropertiesChanged();
+        return;
+    }
+
     if (interface != "org.bluez.Device1")
     {
         return;
     }
 
+    QString changedDevice = macFromDevicePath(QDBusContext::message().path());
+    if (!changedDevice.isEmpty())
+    {
+        emit devicePropertiesChanged(changedDevice);
+    }
+
     if (changedProps.contains("Connected"))
     {
         bool connected = changedProps["Connected"].toBool();
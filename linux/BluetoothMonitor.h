@@ -4,6 +4,8 @@
 #include <QObject>
 #include <QtDBus/QtDBus>
 
+#include <functional>
+
 // Forward declarations for D-Bus types
 typedef QMap<QDBusObjectPath, QMap<QString, QVariantMap>> ManagedObjectList;
 Q_DECLARE_METATYPE(ManagedObjectList)
@@ -16,10 +18,34 @@ public:
     ~BluetoothMonitor();
 
     bool checkAlreadyConnectedDevices();
+    QVariantMap deviceProperties(const QString &macAddress);
+    // Asks BlueZ to connect or disconnect all profiles of the device, returns once the request is sent.
+    // done, if given, is called with the outcome once BlueZ answers
+    bool setDeviceConnected(const QString &macAddress, bool connected, std::function<void(bool)> done = nullptr);
+
+    // Paired AirPods as maps of address, name and connected
+    QVariantList pairedAirPods();
+
+    // Paired audio devices other than AirPods as maps of address, name, icon, connected and
+    // battery, the Battery1 percentage or -1 when BlueZ has none for the device
+    QVariantList audioDevices();
+
+    // AVRCP absolute volume of the A2DP transport, 0-127 or -1 without a transport
+    int transportVolume(const QString &macAddress);
+    bool setTransportVolume(const QString &macAddress, int volume);
+
+    // Powered, Discoverable and Pairable of the first Adapter1, empty without an adapter
+    QVariantMap adapterProperties();
+    bool setAdapterProperty(const QString &name, bool value);
+    QString findAdapterPath();
 
 signals:
     void deviceConnected(const QString &macAddress, const QString &deviceName);
     void deviceDisconnected(const QString &macAddress, const QString &deviceName);
+    void devicePropertiesChanged(const QString &macAddress);
+    void deviceBatteryChanged(const QString &macAddress);
+    void transportVolumeChanged(const QString &macAddress, int volume);
+    void adapterPropertiesChanged();
 
 private slots:
     void onPropertiesChanged(const QString &interface, const QVariantMap &changedProps, const QStringList &invalidatedProps);
@@ -29,6 +55,8 @@ private:
     void registerDBusService();
     bool isAirPodsDevice(const QString &devicePath);
     QString getDeviceName(const QString &devicePath);
+    QString findTransportPath(const QString &macAddress);
+    QString findDevicePath(const QString &macAddress);
 };
 
 #endif // BLUETOOTHMONITOR_H
\ No newline at end of file
@@ -0,0 +1,120 @@
+import QtQuick 2.15
+import QtQuick.Controls 2.15
+import QtQuick.Layouts
+
+// Ctrl+K palette, commands are objects with a name, an optional hint and a run() function
+Popup {
+    id: root
+    property var commands: []
+    property var matches: []
+
+    modal: true
+    focus: true
+    padding: 8
+    width: Math.min(parent.width - 40, 360)
+    height: Math.min(parent.height - 40, 320)
+    x: (parent.width - width) / 2
+    y: 20
+
+    // Characters of the query have to appear in order, runs and word starts score higher
+    function fuzzyScore(query, text) {
+        if (query === "")
+            return 0
+        query = query.toLowerCase()
+        text = text.toLowerCase()
+        let score = 0
+        let run = 0
+        let position = 0
+        for (let i = 0; i < query.length; i++) {
+            const found = text.indexOf(query[i], position)
+            if (found < 0)
+                return -1
+            run = found === position ? run + 1 : 1
+            score += run
+            if (found === 0 || text[found - 1] === " ")
+                score += 2
+            position = found + 1
+        }
+        return score - text.length * 0.01
+    }
+
+    function updateMatches() {
+        const results = []
+        for (const command of commands) {
+            const score = fuzzyScore(searchField.text, command.name)
+            if (score >= 0)
+                results.push({ command: command, score: score })
+        }
+        results.sort((a, b) => b.score - a.score)
+        matches = results.map(result => result.command)
+        resultList.currentIndex = matches.length > 0 ? 0 : -1
+    }
+
+    function runCurrent() {
+        if (resultList.currentIndex < 0)
+            return
+        const command = matches[resultList.currentIndex]
+        close()
+        command.run()
+    }
+
+    onAboutToShow: {
+        searchField.text = ""
+        updateMatches()
+        searchField.forceActiveFocus()
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        spacing: 6
+
+        TextField {
+            id: searchField
+            Layout.fillWidth: true
+            placeholderText: qsTr("Type a command…")
+            onTextChanged: root.updateMatches()
+            onAccepted: root.runCurrent()
+            Keys.onDownPressed: resultList.incrementCurrentIndex()
+            Keys.onUpPressed: resultList.decrementCurrentIndex()
+        }
+
+        ListView {
+            id: resultList
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            clip: true
+            model: root.matches
+
+            delegate: ItemDelegate {
+                required property var modelData
+                required property int index
+                width: ListView.view.width
+                highlighted: ListView.isCurrentItem
+                onClicked: {
+                    resultList.currentIndex = index
+                    root.runCurrent()
+                }
+
+                contentItem: RowLayout {
+                    Label {
+                        Layout.fillWidth: true
+                        text: modelData.name
+                        elide: Text.ElideRight
+                    }
+
+                    Label {
+                        text: modelData.hint || ""
+                        opacity: 0.6
+                    }
+                }
+            }
+
+            Label {
+                anchors.centerIn: parent
+                visible: root.matches.length === 0
+                text: qsTr("No matching commands")
+                opacity: 0.6
+            }
+        }
+    }
+}
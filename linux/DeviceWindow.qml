@@ -0,0 +1,47 @@
+import QtQuick 2.15
+import QtQuick.Controls 2.15
+import QtQuick.Window 2.15
+
+// Battery levels in a compact window of their own, e.g. on a second monitor while the main
+// window shows another page. Stays open when the main window is hidden to the tray.
+ApplicationWindow {
+    id: root
+    property bool stayOnTop: false
+
+    title: airPodsTrayApp.deviceInfo.deviceName !== "" ? airPodsTrayApp.deviceInfo.deviceName : "LibrePods"
+    flags: Qt.Window | (stayOnTop ? Qt.WindowStaysOnTopHint : 0)
+    width: Math.max(240, content.implicitWidth + 40)
+    height: content.implicitHeight + 40
+
+    Column {
+        id: content
+        anchors.centerIn: parent
+        spacing: 12
+
+        BatteryRow {
+            anchors.horizontalCenter: parent.horizontalCenter
+            visible: airPodsTrayApp.airpodsConnected
+        }
+
+        Label {
+            anchors.horizontalCenter: parent.horizontalCenter
+            visible: airPodsTrayApp.airpodsConnected && !airPodsTrayApp.deviceLoading
+            text: [qsTr("Off"), qsTr("Noise Cancellation"), qsTr("Transparency"), qsTr("Adaptive")][airPodsTrayApp.deviceInfo.noiseControlMode] ?? ""
+            opacity: 0.7
+        }
+
+        Label {
+            anchors.horizontalCenter: parent.horizontalCenter
+            visible: !airPodsTrayApp.airpodsConnected
+            text: qsTr("Disconnected")
+            opacity: 0.7
+        }
+
+        CheckBox {
+            anchors.horizontalCenter: parent.horizontalCenter
+            text: qsTr("Keep on top")
+            checked: root.stayOnTop
+            onToggled: root.stayOnTop = checked
+        }
+    }
+}
@@ -2,6 +2,8 @@ pragma ComponentBehavior: Bound
 
 import QtQuick 2.15
 import QtQuick.Controls 2.15
+import QtQuick.Dialogs
+import QtQuick.Layouts
 
 ApplicationWindow {
     id: mainWindow
@@ -11,8 +13,41 @@ ApplicationWindow {
     title: "LibrePods"
     objectName: "mainWindowObject"
 
+    // Device controls stay hidden until the AirPods sent their initial state, instead of showing defaults
+    readonly property bool deviceReady: airPodsTrayApp.airpodsConnected && !airPodsTrayApp.deviceLoading
+    // --read-only keeps the state visible but nothing may be sent to the AirPods
+    readonly property bool controlsEnabled: !airPodsTrayApp.readOnly
+
     onClosing: mainWindow.visible = false
 
+    // Transient toast offering to revert the last destructive action
+    footer: Pane {
+        visible: airPodsTrayApp.undoDescription !== ""
+        padding: 8
+
+        RowLayout {
+            anchors.fill: parent
+            spacing: 10
+
+            Label {
+                Layout.fillWidth: true
+                text: airPodsTrayApp.undoDescription
+                elide: Text.ElideRight
+            }
+
+            Button {
+                text: qsTr("Undo")
+                onClicked: airPodsTrayApp.undo()
+            }
+
+            Button {
+                text: qsTr("Dismiss")
+                flat: true
+                onClicked: airPodsTrayApp.dismissUndo()
+            }
+        }
+    }
+
     function reopen(pageToLoad) {
         if (pageToLoad == "settings")
         {
@@ -60,6 +95,88 @@ ApplicationWindow {
         source: "qrc:/icons/assets/fonts/SF-Symbols-6.ttf"
     }
 
+    ModeOsd {
+        id: modeOsd
+        iconSource: airPodsTrayApp.deviceInfo.podIcon ? "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon : ""
+    }
+
+    Connections {
+        target: airPodsTrayApp
+        function onModeOsdRequested(modeName) { modeOsd.show(modeName) }
+    }
+
+    // Built when the palette opens so only what's currently possible is listed
+    function paletteCommands() {
+        const commands = []
+        const connected = airPodsTrayApp.airpodsConnected
+        const canControl = mainWindow.deviceReady && mainWindow.controlsEnabled
+        const info = airPodsTrayApp.deviceInfo
+
+        if (connected || airPodsTrayApp.lastDeviceAddress !== "")
+            commands.push({ name: connected ? qsTr("Disconnect AirPods") : qsTr("Connect AirPods"), hint: qsTr("Device"),
+                            run: () => airPodsTrayApp.toggleDeviceConnection() })
+        if (connected)
+            commands.push({ name: qsTr("Refresh device state"), hint: qsTr("Device"), run: () => airPodsTrayApp.refreshDeviceState() })
+
+        if (canControl && info.noiseControlAvailable) {
+            const modes = [qsTr("Off"), qsTr("Noise Cancellation"), qsTr("Transparency"), qsTr("Adaptive")]
+            for (let mode = info.allowOffOption ? 0 : 1; mode < modes.length; mode++)
+                commands.push({ name: qsTr("Listening mode: %1").arg(modes[mode]), hint: qsTr("Mode"),
+                                run: () => airPodsTrayApp.setNoiseControlModeInt(mode) })
+            commands.push({ name: qsTr("Next listening mode"), hint: qsTr("Mode"), run: () => airPodsTrayApp.cycleNoiseControlMode() })
+        }
+        if (canControl && info.conversationalAwarenessAvailable)
+            commands.push({ name: info.conversationalAwareness ? qsTr("Turn off Conversational Awareness") : qsTr("Turn on Conversational Awareness"),
+                            hint: qsTr("Feature"), run: () => airPodsTrayApp.setConversationalAwareness(!info.conversationalAwareness) })
+        if (canControl && info.hearingAidAvailable)
+            commands.push({ name: info.hearingAidEnabled ? qsTr("Turn off Hearing Aid") : qsTr("Turn on Hearing Aid"),
+                            hint: qsTr("Feature"), run: () => airPodsTrayApp.setHearingAidEnabled(!info.hearingAidEnabled) })
+        if (mainWindow.controlsEnabled)
+            commands.push({ name: airPodsTrayApp.volumeLock ? qsTr("Unlock volumes") : qsTr("Lock volumes together"), hint: qsTr("Feature"),
+                            run: () => airPodsTrayApp.volumeLock = !airPodsTrayApp.volumeLock })
+        commands.push({ name: airPodsTrayApp.notificationsEnabled ? qsTr("Turn off notifications") : qsTr("Turn on notifications"),
+                        hint: qsTr("Feature"), run: () => airPodsTrayApp.notificationsEnabled = !airPodsTrayApp.notificationsEnabled })
+
+        commands.push({ name: qsTr("Open Settings"), hint: qsTr("Page"), run: () => openPage(settingsPage) })
+        commands.push({ name: qsTr("Open Connection Timeline"), hint: qsTr("Page"), run: () => openPage(timelinePage) })
+        commands.push({ name: qsTr("Open Diagnostics"), hint: qsTr("Page"), run: () => openPage(diagnosticsPage) })
+        commands.push({ name: qsTr("Open Other Devices"), hint: qsTr("Page"), run: () => openPage(audioDevicesPage) })
+        if (connected)
+            commands.push({ name: qsTr("Open Spatial Audio Test"), hint: qsTr("Page"), run: () => openPage(spatialTestPage) })
+        if (connected && airPodsTrayApp.deviceInfo.hasEarTips)
+            commands.push({ name: qsTr("Open Ear Tip Fit Check"), hint: qsTr("Page"), run: () => openPage(fitCheckPage) })
+        commands.push({ name: qsTr("Open battery in a separate window"), hint: qsTr("Page"), run: () => openDeviceWindow() })
+        commands.push({ name: qsTr("Back to the main page"), hint: qsTr("Page"), run: () => stackView.pop(null) })
+        return commands
+    }
+
+    function openPage(page) {
+        stackView.pop(null)
+        stackView.push(page)
+    }
+
+    Shortcut {
+        sequence: "Ctrl+K"
+        onActivated: {
+            commandPalette.commands = mainWindow.paletteCommands()
+            commandPalette.open()
+        }
+    }
+
+    CommandPalette {
+        id: commandPalette
+    }
+
+    DeviceWindow {
+        id: deviceWindow
+    }
+
+    function openDeviceWindow() {
+        deviceWindow.show()
+        deviceWindow.raise()
+        deviceWindow.requestActivate()
+    }
+
     Component {
         id: mainPage
         Item {
@@ -68,6 +185,90 @@ ApplicationWindow {
                 spacing: 20
                 padding: 20
 
+                // Quick actions mirroring the tray menu
+                Row {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    spacing: 6
+
+                    Button {
+                        flat: true
+                        enabled: airPodsTrayApp.airpodsConnected || airPodsTrayApp.lastDeviceAddress !== ""
+                        text: airPodsTrayApp.airpodsConnected ? qsTr("Disconnect") : qsTr("Connect")
+                        onClicked: airPodsTrayApp.toggleDeviceConnection()
+                    }
+
+                    Button {
+                        flat: true
+                        visible: mainWindow.deviceReady
+                        text: qsTr("Refresh")
+                        onClicked: airPodsTrayApp.refreshDeviceState()
+                    }
+
+                    Button {
+                        flat: true
+                        visible: mainWindow.deviceReady
+                        enabled: mainWindow.controlsEnabled
+                        text: qsTr("Next mode")
+                        onClicked: airPodsTrayApp.cycleNoiseControlMode()
+                    }
+
+                    Button {
+                        flat: true
+                        visible: airPodsTrayApp.airpodsConnected
+                        text: qsTr("Pop out")
+                        ToolTip.visible: hovered
+                        ToolTip.text: qsTr("Show the battery levels in a separate window")
+                        onClicked: mainWindow.openDeviceWindow()
+                    }
+                }
+
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: airPodsTrayApp.readOnly
+                    text: qsTr("Read-only mode")
+                    font.pixelSize: 12
+                    opacity: 0.7
+                }
+
+                Rectangle {
+                    width: parent.width
+                    height: dependencyColumn.implicitHeight + 16
+                    radius: 8
+                    color: "#33FF9F0A"
+                    border.color: "#FF9F0A"
+                    visible: airPodsTrayApp.missingDependencies.length > 0
+
+                    Column {
+                        id: dependencyColumn
+                        anchors.fill: parent
+                        anchors.margins: 8
+                        spacing: 4
+
+                        Label {
+                            text: qsTr("Some features are unavailable")
+                            font.bold: true
+                        }
+
+                        Repeater {
+                            model: airPodsTrayApp.missingDependencies
+
+                            Label {
+                                required property string modelData
+                                width: dependencyColumn.width
+                                wrapMode: Text.WordWrap
+                                text: "• " + modelData
+                                font.pixelSize: 12
+                            }
+                        }
+
+                        Button {
+                            flat: true
+                            text: qsTr("Dismiss")
+                            onClicked: airPodsTrayApp.dismissDependencyBanner()
+                        }
+                    }
+                }
+
                 // Connection status indicator (Apple-like pill shape)
                 Rectangle {
                     anchors.horizontalCenter: parent.horizontalCenter
@@ -88,56 +289,105 @@ ApplicationWindow {
                     }
                 }
 
-                // Battery Indicator Row
-                Row {
+                Column {
                     anchors.horizontalCenter: parent.horizontalCenter
+                    visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.deviceLoading
                     spacing: 8
 
-                    PodColumn {
-                        visible: airPodsTrayApp.deviceInfo.battery.leftPodAvailable
-                        inEar: airPodsTrayApp.deviceInfo.leftPodInEar
-                        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
-                        batteryLevel: airPodsTrayApp.deviceInfo.battery.leftPodLevel
-                        isCharging: airPodsTrayApp.deviceInfo.battery.leftPodCharging
-                        indicator: "L"
+                    BusyIndicator {
+                        anchors.horizontalCenter: parent.horizontalCenter
+                        running: parent.visible
                     }
 
-                    PodColumn {
-                        visible: airPodsTrayApp.deviceInfo.battery.rightPodAvailable
-                        inEar: airPodsTrayApp.deviceInfo.rightPodInEar
-                        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
-                        batteryLevel: airPodsTrayApp.deviceInfo.battery.rightPodLevel
-                        isCharging: airPodsTrayApp.deviceInfo.battery.rightPodCharging
-                        indicator: "R"
+                    Label {
+                        text: qsTr("Loading AirPods settings…")
+                        opacity: 0.7
                     }
+                }
+
+                Column {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    width: parent.width
+                    visible: airPodsTrayApp.connectionConflict !== ""
+                    spacing: 8
 
-                    PodColumn {
-                        visible: airPodsTrayApp.deviceInfo.battery.caseAvailable
-                        inEar: true
-                        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.caseIcon
-                        batteryLevel: airPodsTrayApp.deviceInfo.battery.caseLevel
-                        isCharging: airPodsTrayApp.deviceInfo.battery.caseCharging
+                    Label {
+                        width: parent.width
+                        horizontalAlignment: Text.AlignHCenter
+                        wrapMode: Text.WordWrap
+                        text: airPodsTrayApp.connectionConflict
+                        color: "red"
                     }
 
-                    PodColumn {
-                        visible: airPodsTrayApp.deviceInfo.battery.headsetAvailable
-                        inEar: true
-                        iconSource: "qrc:/icons/assets/" + airPodsTrayApp.deviceInfo.podIcon
-                        batteryLevel: airPodsTrayApp.deviceInfo.battery.headsetLevel
-                        isCharging: airPodsTrayApp.deviceInfo.battery.headsetCharging
+                    Button {
+                        anchors.horizontalCenter: parent.horizontalCenter
+                        text: qsTr("Retry")
+                        onClicked: airPodsTrayApp.retryConnection()
                     }
                 }
 
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: airPodsTrayApp.pendingWrites > 0
+                    text: qsTr("%n change(s) will be sent when the AirPods reconnect", "", airPodsTrayApp.pendingWrites)
+                    opacity: 0.7
+                }
+
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: !airPodsTrayApp.airpodsConnected && airPodsTrayApp.lastSeen.time !== undefined
+                    text: qsTr("%1 last seen near <a href=\"%2\">%3, %4</a> on %5")
+                        .arg(airPodsTrayApp.lastSeen.name)
+                        .arg("https://www.openstreetmap.org/?mlat=" + airPodsTrayApp.lastSeen.latitude
+                             + "&mlon=" + airPodsTrayApp.lastSeen.longitude + "#map=15/" + airPodsTrayApp.lastSeen.latitude
+                             + "/" + airPodsTrayApp.lastSeen.longitude)
+                        .arg(Number(airPodsTrayApp.lastSeen.latitude).toFixed(3))
+                        .arg(Number(airPodsTrayApp.lastSeen.longitude).toFixed(3))
+                        .arg(airPodsTrayApp.lastSeen.time)
+                    textFormat: Text.StyledText
+                    onLinkActivated: (link) => Qt.openUrlExternally(link)
+                }
+
+                BatteryRow {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                }
+
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: !airPodsTrayApp.airpodsConnected && airPodsTrayApp.phoneBatteryStatus !== ""
+                    text: qsTr("%1 on your phone: %2").arg(airPodsTrayApp.phoneDeviceName).arg(airPodsTrayApp.phoneBatteryStatus)
+                    opacity: 0.7
+                }
+
+                // AirPods Max only, estimated from ear detection
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: text !== ""
+                    text: airPodsTrayApp.headsetPower.label
+                    opacity: 0.7
+                }
+
+                Label {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    visible: mainWindow.deviceReady && airPodsTrayApp.otherHosts.length > 0
+                    text: qsTr("Also connected to: %1").arg(airPodsTrayApp.otherHosts.join(", "))
+                    opacity: 0.7
+                }
+
                 SegmentedControl {
+                    // Off is the first mode, drop it when the AirPods don't allow it
+                    readonly property int modeOffset: airPodsTrayApp.deviceInfo.allowOffOption ? 0 : 1
                     anchors.horizontalCenter: parent.horizontalCenter
-                    model: [qsTr("Off"), qsTr("Noise Cancellation"), qsTr("Transparency"), qsTr("Adaptive")]
-                    currentIndex: airPodsTrayApp.deviceInfo.noiseControlMode
-                    onCurrentIndexChanged: airPodsTrayApp.setNoiseControlModeInt(currentIndex)
-                    visible: airPodsTrayApp.airpodsConnected
+                    model: [qsTr("Off"), qsTr("Noise Cancellation"), qsTr("Transparency"), qsTr("Adaptive")].slice(modeOffset)
+                    currentIndex: airPodsTrayApp.deviceInfo.noiseControlMode - modeOffset
+                    onCurrentIndexChanged: if (currentIndex >= 0) airPodsTrayApp.setNoiseControlModeInt(currentIndex + modeOffset)
+                    visible: mainWindow.deviceReady && airPodsTrayApp.deviceInfo.noiseControlAvailable
+                    enabled: mainWindow.controlsEnabled
                 }
 
                 Slider {
                     visible: airPodsTrayApp.deviceInfo.adaptiveModeActive
+                    enabled: mainWindow.controlsEnabled
                     from: 0
                     to: 100
                     stepSize: 1
@@ -158,19 +408,154 @@ ApplicationWindow {
                     }
                 }
 
+                Slider {
+                    visible: mainWindow.deviceReady && airPodsTrayApp.deviceInfo.chimeVolumeAvailable
+                    enabled: mainWindow.controlsEnabled
+                    from: 0
+                    to: 100
+                    stepSize: 1
+                    value: airPodsTrayApp.deviceInfo.chimeVolume
+
+                    onPressedChanged: if (!pressed) airPodsTrayApp.setChimeVolume(value)
+
+                    Label {
+                        text: qsTr("Tone Volume: ") + parent.value
+                        anchors.top: parent.bottom
+                    }
+                }
+
                 Switch {
-                    visible: airPodsTrayApp.airpodsConnected
+                    visible: mainWindow.deviceReady && airPodsTrayApp.deviceInfo.conversationalAwarenessAvailable
+                    enabled: mainWindow.controlsEnabled
                     text: qsTr("Conversational Awareness")
                     checked: airPodsTrayApp.deviceInfo.conversationalAwareness
                     onCheckedChanged: airPodsTrayApp.setConversationalAwareness(checked)
                 }
 
+                Slider {
+                    visible: mainWindow.deviceReady && airPodsTrayApp.deviceInfo.conversationalAwareness
+                    from: 0
+                    to: 10000
+                    stepSize: 500
+                    value: airPodsTrayApp.conversationReleaseDelay
+
+                    onPressedChanged: if (!pressed) airPodsTrayApp.conversationReleaseDelay = value
+
+                    Label {
+                        text: qsTr("Restore volume after: %1 s").arg((parent.value / 1000).toFixed(1))
+                        anchors.top: parent.bottom
+                    }
+                }
+
+                Label {
+                    property var stats: airPodsTrayApp.conversationStats
+                    visible: mainWindow.deviceReady && stats.todayCount > 0
+                    text: qsTr("Audio ducked %n time(s) today, total %1 min", "", stats.todayCount)
+                          .arg(Math.round(stats.todaySeconds / 60)) + qsTr(" (last at %1)").arg(stats.lastDucked)
+                    font.pixelSize: 12
+                    opacity: 0.7
+                }
+
                 Switch {
-                    visible: airPodsTrayApp.airpodsConnected
+                    visible: mainWindow.deviceReady && airPodsTrayApp.deviceInfo.hearingAidAvailable
+                    enabled: mainWindow.controlsEnabled
                     text: qsTr("Hearing Aid")
                     checked: airPodsTrayApp.deviceInfo.hearingAidEnabled
                     onCheckedChanged: airPodsTrayApp.setHearingAidEnabled(checked)
                 }
+
+                Row {
+                    spacing: 10
+                    visible: mainWindow.deviceReady && airPodsTrayApp.audioCodecs.length > 0
+
+                    Label {
+                        text: qsTr("Codec:")
+                        anchors.verticalCenter: parent.verticalCenter
+                    }
+
+                    ComboBox {
+                        model: airPodsTrayApp.audioCodecs
+                        textRole: "description"
+                        valueRole: "name"
+                        currentIndex: indexOfValue(airPodsTrayApp.activeAudioCodec)
+                        onActivated: airPodsTrayApp.setAudioCodec(currentValue)
+                    }
+
+                    Label {
+                        text: qsTr("Active: ") + (airPodsTrayApp.activeAudioCodec || qsTr("unknown"))
+                        anchors.verticalCenter: parent.verticalCenter
+                        opacity: 0.6
+                    }
+
+                    Connections {
+                        target: airPodsTrayApp
+                        function onAirPodsStatusChanged() { airPodsTrayApp.refreshAudioCodecs() }
+                    }
+
+                    Component.onCompleted: airPodsTrayApp.refreshAudioCodecs()
+                }
+
+                Row {
+                    spacing: 10
+                    visible: mainWindow.deviceReady
+
+                    Label {
+                        text: qsTr("Volume: AirPods %1 · System %2")
+                            .arg(airPodsTrayApp.deviceVolume < 0 ? "–" : airPodsTrayApp.deviceVolume + "%")
+                            .arg(airPodsTrayApp.hostVolume < 0 ? "–" : airPodsTrayApp.hostVolume + "%")
+                        anchors.verticalCenter: parent.verticalCenter
+                    }
+
+                    Switch {
+                        text: qsTr("Lock together")
+                        enabled: mainWindow.controlsEnabled
+                        checked: airPodsTrayApp.volumeLock
+                        onCheckedChanged: airPodsTrayApp.volumeLock = checked
+                    }
+
+                    Component.onCompleted: airPodsTrayApp.refreshVolumes()
+                }
+
+                // Tap along with the clicks, the result is a rough output latency for the active codec
+                Row {
+                    spacing: 10
+                    visible: mainWindow.deviceReady
+
+                    Button {
+                        readonly property var test: airPodsTrayApp.latencyTest
+                        text: test.running ? qsTr("Tap with the clicks (%1)").arg(test.taps) : qsTr("Test latency")
+                        onClicked: test.running ? test.tap() : test.start()
+                    }
+
+                    Label {
+                        visible: !airPodsTrayApp.latencyTest.running
+                        text: airPodsTrayApp.latencyTest.resultMs < 0 ? qsTr("Tap in time with what you hear")
+                              : qsTr("About %1 ms with %2").arg(airPodsTrayApp.latencyTest.resultMs)
+                                                          .arg(airPodsTrayApp.activeAudioCodec || qsTr("the current codec"))
+                        anchors.verticalCenter: parent.verticalCenter
+                        opacity: 0.6
+                    }
+                }
+
+                Row {
+                    anchors.horizontalCenter: parent.horizontalCenter
+                    spacing: 10
+
+                    Button {
+                        text: qsTr("Connection Timeline")
+                        onClicked: stackView.push(timelinePage)
+                    }
+
+                    Button {
+                        text: qsTr("Diagnostics")
+                        onClicked: stackView.push(diagnosticsPage)
+                    }
+
+                    Button {
+                        text: qsTr("Other Devices")
+                        onClicked: stackView.push(audioDevicesPage)
+                    }
+                }
             }
 
             RoundButton {
@@ -185,98 +570,879 @@ ApplicationWindow {
         }
     }
 
+    // The seal measurement the AirPods do for Apple devices isn't known, so the listener judges
+    // each ear from a bass tone: with a good seal it sounds full, a loose tip makes it thin
     Component {
-        id: settingsPage
+        id: fitCheckPage
         Page {
-            id: settingsPageItem
-            title: qsTr("Settings")
+            title: qsTr("Ear Tip Fit Check")
 
-            ScrollView {
+            ColumnLayout {
                 anchors.fill: parent
+                anchors.margins: 20
+                anchors.topMargin: 60
+                spacing: 15
 
-                Column {
-                    width: parent.width
-                    spacing: 20
-                    padding: 20
+                Label {
+                    Layout.fillWidth: true
+                    wrapMode: Text.WordWrap
+                    text: qsTr("Put both AirPods in and play the tone for each ear. Does the bass sound full and steady?")
+                }
 
-                    Label {
-                        text: qsTr("Settings")
-                        font.pixelSize: 24
-                        // center the label
-                        anchors.horizontalCenter: parent.horizontalCenter
-                    }
+                Repeater {
+                    model: [{ name: qsTr("Left"), pan: -1 }, { name: qsTr("Right"), pan: 1 }]
 
-                    Column {
-                        spacing: 5 // Small gap between label and ComboBox
+                    delegate: RowLayout {
+                        required property var modelData
+                        // Empty until answered, then "good" or "loose"
+                        property string fit: ""
+                        spacing: 10
 
                         Label {
-                            text: qsTr("Pause Behavior When Removing AirPods:")
+                            text: modelData.name
+                            font.bold: true
+                            Layout.preferredWidth: 60
                         }
 
-                        ComboBox {
-                            width: parent.width // Ensures full width
-                            model: [qsTr("One Removed"), qsTr("Both Removed"), qsTr("Never")]
-                            currentIndex: airPodsTrayApp.earDetectionBehavior
-                            onActivated: airPodsTrayApp.earDetectionBehavior = currentIndex
+                        Button {
+                            text: qsTr("Play")
+                            onClicked: airPodsTrayApp.playFitCheckTone(modelData.pan)
                         }
-                    }
 
-                    Switch {
-                        text: qsTr("Cross-Device Connectivity with Android")
-                        checked: airPodsTrayApp.crossDeviceEnabled
-                        onCheckedChanged: {
-                            airPodsTrayApp.setCrossDeviceEnabled(checked)
+                        Button {
+                            flat: true
+                            text: qsTr("Sounds full")
+                            onClicked: fit = "good"
                         }
-                    }
-
-                    Switch {
-                        text: qsTr("Auto-Start on Login")
-                        checked: airPodsTrayApp.autoStartManager.autoStartEnabled
-                        onCheckedChanged: airPodsTrayApp.autoStartManager.autoStartEnabled = checked
-                    }
-
-                    Switch {
-                        text: qsTr("Enable System Notifications")
-                        checked: airPodsTrayApp.notificationsEnabled
-                        onCheckedChanged: airPodsTrayApp.notificationsEnabled = checked
-                    }
-
-                    Switch {
-                        visible: airPodsTrayApp.airpodsConnected
-                        text: qsTr("One Bud ANC Mode")
-                        checked: airPodsTrayApp.deviceInfo.oneBudANCMode
-                        onCheckedChanged: airPodsTrayApp.deviceInfo.oneBudANCMode = checked
 
-                        ToolTip {
-                            visible: parent.hovered
-                            text: qsTr("Enable ANC when using one AirPod\n(More noise reduction, but uses more battery)")
-                            delay: 500
+                        Button {
+                            flat: true
+                            text: qsTr("Sounds thin")
+                            onClicked: fit = "loose"
                         }
-                    }
 
-                    Row {
-                        spacing: 5
                         Label {
-                            text: qsTr("Bluetooth Retry Attempts:")
-                            anchors.verticalCenter: parent.verticalCenter
-                        }
-                        SpinBox {
-                            from: 1
-                            to: 10
-                            value: airPodsTrayApp.retryAttempts
-                            onValueChanged: airPodsTrayApp.retryAttempts = value
+                            visible: fit !== ""
+                            text: fit === "good" ? qsTr("Good seal") : qsTr("Adjust or try a different tip")
+                            color: fit === "good" ? "#30D158" : "#FF9F0A"
                         }
                     }
+                }
 
-                    Row {
-                        spacing: 10
-                        visible: airPodsTrayApp.airpodsConnected
+                Item {
+                    Layout.fillHeight: true
+                }
+            }
 
-                        TextField {
-                            id: newNameField
-                            placeholderText: airPodsTrayApp.deviceInfo.deviceName
-                            maximumLength: 32
-                        }
+            RoundButton {
+                anchors.top: parent.top
+                anchors.left: parent.left
+                anchors.margins: 10
+                font.family: iconFont.name
+                font.pixelSize: 18
+                text: "\uecb1" // U+ECB1
+                onClicked: stackView.pop()
+            }
+        }
+    }
+
+    Component {
+        id: spatialTestPage
+        Page {
+            title: qsTr("Spatial Audio Test")
+
+            // Head tracking only runs while this page is open
+            Component.onDestruction: airPodsTrayApp.setHeadTrackingActive(false)
+
+            ColumnLayout {
+                anchors.fill: parent
+                anchors.margins: 20
+                anchors.topMargin: 60
+                spacing: 15
+
+                Label {
+                    text: qsTr("Test tones")
+                    font.bold: true
+                }
+
+                Row {
+                    spacing: 10
+
+                    Button {
+                        text: qsTr("Left")
+                        onClicked: airPodsTrayApp.playTestTone(-1)
+                    }
+
+                    Button {
+                        text: qsTr("Centre")
+                        onClicked: airPodsTrayApp.playTestTone(0)
+                    }
+
+                    Button {
+                        text: qsTr("Right")
+                        onClicked: airPodsTrayApp.playTestTone(1)
+                    }
+                }
+
+                Switch {
+                    text: qsTr("Head tracking")
+                    enabled: airPodsTrayApp.airpodsConnected
+                    checked: airPodsTrayApp.headTracking.active
+                    onToggled: airPodsTrayApp.setHeadTrackingActive(checked)
+                }
+
+                Label {
+                    visible: airPodsTrayApp.headTracking.active && !airPodsTrayApp.headTracking.calibrated
+                    text: qsTr("Calibrating, keep your head still…")
+                }
+
+                Label {
+                    visible: airPodsTrayApp.headTracking.calibrated
+                    text: qsTr("Pitch: %1°  Yaw: %2°").arg(airPodsTrayApp.headTracking.pitch.toFixed(1))
+                                                     .arg(airPodsTrayApp.headTracking.yaw.toFixed(1))
+                    font.family: "monospace"
+                }
+
+                // Top-down view of the head: the marker turns with yaw and moves up and down with pitch
+                Rectangle {
+                    Layout.alignment: Qt.AlignHCenter
+                    width: 160
+                    height: 160
+                    radius: width / 2
+                    color: "transparent"
+                    border.color: palette.mid
+                    border.width: 2
+
+                    Rectangle {
+                        width: 12
+                        height: 60
+                        radius: 6
+                        color: "#0A84FF"
+                        x: (parent.width - width) / 2
+                        y: parent.height / 2 - height - Math.max(-20, Math.min(20, airPodsTrayApp.headTracking.pitch))
+                        transformOrigin: Item.Bottom
+                        rotation: Math.max(-90, Math.min(90, airPodsTrayApp.headTracking.yaw))
+                    }
+                }
+
+                Item {
+                    Layout.fillHeight: true
+                }
+            }
+        }
+    }
+
+    Component {
+        id: timelinePage
+        Page {
+            title: qsTr("Connection Timeline")
+
+            ColumnLayout {
+                anchors.fill: parent
+                anchors.margins: 20
+                anchors.topMargin: 60
+                spacing: 10
+
+                Row {
+                    spacing: 10
+
+                    Button {
+                        text: qsTr("Export CSV")
+                        onClicked: timelineExportDialog.open()
+                    }
+
+                    Button {
+                        text: qsTr("Clear")
+                        onClicked: airPodsTrayApp.timeline.clear()
+                    }
+                }
+
+                // Empty dates export everything that's kept, the last 90 days
+                Row {
+                    spacing: 10
+
+                    TextField {
+                        id: historyFromField
+                        width: 110
+                        placeholderText: qsTr("From yyyy-mm-dd")
+                        validator: RegularExpressionValidator { regularExpression: /^(\d{4}-\d{2}-\d{2})?$/ }
+                    }
+
+                    TextField {
+                        id: historyToField
+                        width: 110
+                        placeholderText: qsTr("To yyyy-mm-dd")
+                        validator: RegularExpressionValidator { regularExpression: /^(\d{4}-\d{2}-\d{2})?$/ }
+                    }
+
+                    Button {
+                        text: qsTr("Export battery history")
+                        onClicked: batteryHistoryExportDialog.open()
+                    }
+                }
+
+                // Resets the baseline of one component in exports and drain warnings
+                Row {
+                    spacing: 10
+                    visible: airPodsTrayApp.deviceInfo.bluetoothAddress !== ""
+
+                    ComboBox {
+                        id: markerComponent
+                        textRole: "text"
+                        valueRole: "value"
+                        model: [{ text: qsTr("Left"), value: "left" }, { text: qsTr("Right"), value: "right" },
+                                { text: qsTr("Case"), value: "case" }]
+                    }
+
+                    ComboBox {
+                        id: markerKind
+                        textRole: "text"
+                        valueRole: "value"
+                        model: [{ text: qsTr("Battery replaced"), value: "replaced" }, { text: qsTr("Calibrated"), value: "calibrated" }]
+                    }
+
+                    Button {
+                        text: qsTr("Add marker")
+                        onClicked: airPodsTrayApp.batteryHistory.addMarker(airPodsTrayApp.deviceInfo.bluetoothAddress,
+                                                                           markerComponent.currentValue, markerKind.currentValue)
+                    }
+                }
+
+                Label {
+                    visible: timelineList.count === 0
+                    text: qsTr("No events recorded yet")
+                }
+
+                ListView {
+                    id: timelineList
+                    Layout.fillWidth: true
+                    Layout.fillHeight: true
+                    clip: true
+                    model: airPodsTrayApp.timeline.events
+                    ScrollBar.vertical: ScrollBar {}
+
+                    delegate: Row {
+                        required property var modelData
+                        spacing: 10
+                        Label {
+                            text: modelData.time
+                            font.family: "monospace"
+                        }
+                        Label {
+                            text: modelData.event
+                            font.bold: true
+                        }
+                        Label {
+                            text: modelData.detail
+                        }
+                        Label {
+                            text: modelData.address
+                            opacity: 0.6
+                        }
+                    }
+                }
+            }
+
+            FileDialog {
+                id: timelineExportDialog
+                fileMode: FileDialog.SaveFile
+                defaultSuffix: "csv"
+                nameFilters: [qsTr("CSV files (*.csv)")]
+                onAccepted: airPodsTrayApp.timeline.exportCsv(selectedFile)
+            }
+
+            FileDialog {
+                id: batteryHistoryExportDialog
+                fileMode: FileDialog.SaveFile
+                defaultSuffix: "csv"
+                nameFilters: [qsTr("CSV files (*.csv)"), qsTr("JSON files (*.json)")]
+                onAccepted: airPodsTrayApp.batteryHistory.exportToFile(selectedFile, historyFromField.text, historyToField.text)
+            }
+
+            RoundButton {
+                anchors.top: parent.top
+                anchors.left: parent.left
+                anchors.margins: 10
+                font.family: iconFont.name
+                font.pixelSize: 18
+                text: "\uecb1" // U+ECB1
+                onClicked: stackView.pop()
+            }
+        }
+    }
+
+    // Paired Bluetooth audio devices that aren't AirPods, with what BlueZ knows about them
+    Component {
+        id: audioDevicesPage
+        Page {
+            title: qsTr("Other Devices")
+
+            Component.onCompleted: airPodsTrayApp.refreshAudioDevices()
+
+            ColumnLayout {
+                anchors.fill: parent
+                anchors.margins: 20
+                anchors.topMargin: 60
+                spacing: 10
+
+                Button {
+                    text: qsTr("Refresh")
+                    onClicked: airPodsTrayApp.refreshAudioDevices()
+                }
+
+                Label {
+                    visible: audioDeviceList.count === 0
+                    text: qsTr("No other Bluetooth audio devices are paired")
+                }
+
+                ListView {
+                    id: audioDeviceList
+                    Layout.fillWidth: true
+                    Layout.fillHeight: true
+                    clip: true
+                    spacing: 8
+                    model: airPodsTrayApp.audioDevices
+                    ScrollBar.vertical: ScrollBar {}
+
+                    delegate: RowLayout {
+                        required property var modelData
+                        width: ListView.view.width
+                        spacing: 10
+
+                        Column {
+                            Layout.fillWidth: true
+
+                            Label {
+                                text: modelData.name
+                                font.bold: true
+                            }
+
+                            Label {
+                                text: (modelData.connected ? qsTr("Connected") : qsTr("Not connected"))
+                                      + (modelData.battery >= 0 ? " · " + qsTr("Battery: %1%").arg(modelData.battery) : "")
+                                opacity: 0.7
+                            }
+                        }
+
+                        Button {
+                            text: modelData.connected ? qsTr("Disconnect") : qsTr("Connect")
+                            onClicked: airPodsTrayApp.setAudioDeviceConnected(modelData.address, !modelData.connected)
+                        }
+                    }
+                }
+            }
+
+            RoundButton {
+                anchors.top: parent.top
+                anchors.left: parent.left
+                anchors.margins: 10
+                font.family: iconFont.name
+                font.pixelSize: 18
+                text: "\uecb1" // U+ECB1
+                onClicked: stackView.pop()
+            }
+        }
+    }
+
+    Component {
+        id: diagnosticsPage
+        Page {
+            title: qsTr("Diagnostics")
+
+            Timer {
+                interval: 3000
+                running: true
+                repeat: true
+                triggeredOnStart: true
+                onTriggered: airPodsTrayApp.refreshDiagnostics()
+            }
+
+            GridLayout {
+                anchors.top: parent.top
+                anchors.left: parent.left
+                anchors.margins: 20
+                anchors.topMargin: 60
+                columns: 2
+                columnSpacing: 20
+                rowSpacing: 8
+
+                Label { text: qsTr("Uptime"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.uptime ?? "" }
+
+                Label { text: qsTr("Packets parsed per minute"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.packetsPerMinute ?? 0 }
+
+                Label { text: qsTr("Last packet"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.lastPacket ?? "" }
+
+                Label { text: qsTr("Reconnects"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.reconnects ?? 0 }
+
+                Label { text: qsTr("Channel errors"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.channelErrors ?? 0 }
+
+                Label { text: qsTr("Write queue"); font.bold: true }
+                Label { text: airPodsTrayApp.diagnostics.writeQueue ?? 0 }
+
+                Label { text: qsTr("Memory"); font.bold: true }
+                Label { text: qsTr("%1 MB").arg(Number(airPodsTrayApp.diagnostics.memoryMb ?? 0).toFixed(1)) }
+            }
+
+            RoundButton {
+                anchors.top: parent.top
+                anchors.left: parent.left
+                anchors.margins: 10
+                font.family: iconFont.name
+                font.pixelSize: 18
+                text: "\uecb1" // U+ECB1
+                onClicked: stackView.pop()
+            }
+        }
+    }
+
+    Component {
+        id: settingsPage
+        Page {
+            id: settingsPageItem
+            title: qsTr("Settings")
+
+            ScrollView {
+                anchors.fill: parent
+
+                Column {
+                    width: parent.width
+                    spacing: 20
+                    padding: 20
+
+                    Label {
+                        text: qsTr("Settings")
+                        font.pixelSize: 24
+                        // center the label
+                        anchors.horizontalCenter: parent.horizontalCenter
+                    }
+
+                    Column {
+                        spacing: 5 // Small gap between label and ComboBox
+
+                        Label {
+                            text: qsTr("Pause Behavior When Removing AirPods:")
+                        }
+
+                        ComboBox {
+                            width: parent.width // Ensures full width
+                            model: [qsTr("One Removed"), qsTr("Both Removed"), qsTr("Never")]
+                            currentIndex: airPodsTrayApp.earDetectionBehavior
+                            onActivated: airPodsTrayApp.earDetectionBehavior = currentIndex
+                        }
+                    }
+
+                    Column {
+                        spacing: 5
+
+                        Label {
+                            text: qsTr("Theme:")
+                        }
+
+                        ComboBox {
+                            width: parent.width
+                            model: [qsTr("Auto (follow system)"), qsTr("Light"), qsTr("Dark")]
+                            currentIndex: airPodsTrayApp.theme
+                            onActivated: airPodsTrayApp.theme = currentIndex
+                        }
+                    }
+
+                    Column {
+                        spacing: 5
+
+                        Label {
+                            text: qsTr("Bluetooth Adapter")
+                            font.bold: true
+                        }
+
+                        Label {
+                            visible: !airPodsTrayApp.adapterAvailable
+                            text: qsTr("No Bluetooth adapter found")
+                            opacity: 0.6
+                        }
+
+                        Switch {
+                            visible: airPodsTrayApp.adapterAvailable
+                            text: qsTr("Powered")
+                            checked: airPodsTrayApp.adapterPowered
+                            onToggled: airPodsTrayApp.adapterPowered = checked
+                        }
+
+                        Switch {
+                            visible: airPodsTrayApp.adapterAvailable
+                            enabled: airPodsTrayApp.adapterPowered
+                            text: qsTr("Discoverable")
+                            checked: airPodsTrayApp.adapterDiscoverable
+                            onToggled: airPodsTrayApp.adapterDiscoverable = checked
+                        }
+
+                        Switch {
+                            visible: airPodsTrayApp.adapterAvailable
+                            enabled: airPodsTrayApp.adapterPowered
+                            text: qsTr("Pairable")
+                            checked: airPodsTrayApp.adapterPairable
+                            onToggled: airPodsTrayApp.adapterPairable = checked
+                        }
+                    }
+
+                    // Checked AirPods are tried top to bottom at startup while none are connected
+                    Column {
+                        spacing: 5
+                        visible: airPodsTrayApp.knownAirPods.length > 0
+                        Component.onCompleted: airPodsTrayApp.refreshKnownAirPods()
+
+                        Label {
+                            text: qsTr("Connect at Startup")
+                            font.bold: true
+                        }
+
+                        Repeater {
+                            model: airPodsTrayApp.knownAirPods
+
+                            delegate: RowLayout {
+                                required property var modelData
+                                required property int index
+                                spacing: 6
+
+                                Image {
+                                    source: "qrc:/icons/assets/" + modelData.icon
+                                    sourceSize.height: 24
+                                    fillMode: Image.PreserveAspectFit
+                                }
+
+                                CheckBox {
+                                    text: modelData.name
+                                    checked: modelData.autoConnect
+                                    onToggled: airPodsTrayApp.setAutoConnect(modelData.address, checked)
+                                }
+
+                                Button {
+                                    flat: true
+                                    text: qsTr("Up")
+                                    enabled: index > 0
+                                    onClicked: airPodsTrayApp.moveKnownAirPods(modelData.address, -1)
+                                }
+
+                                Button {
+                                    flat: true
+                                    text: qsTr("Down")
+                                    enabled: index < airPodsTrayApp.knownAirPods.length - 1
+                                    onClicked: airPodsTrayApp.moveKnownAirPods(modelData.address, 1)
+                                }
+                            }
+                        }
+                    }
+
+                    Switch {
+                        text: qsTr("Cross-Device Connectivity with Android")
+                        checked: airPodsTrayApp.crossDeviceEnabled
+                        onCheckedChanged: {
+                            airPodsTrayApp.setCrossDeviceEnabled(checked)
+                        }
+                    }
+
+                    Switch {
+                        text: qsTr("Auto-Start on Login")
+                        checked: airPodsTrayApp.autoStartManager.autoStartEnabled
+                        onCheckedChanged: airPodsTrayApp.autoStartManager.autoStartEnabled = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Enable System Notifications")
+                        checked: airPodsTrayApp.notificationsEnabled
+                        onCheckedChanged: airPodsTrayApp.notificationsEnabled = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Show the window when AirPods connect")
+                        checked: airPodsTrayApp.showWindowOnConnect
+                        onCheckedChanged: airPodsTrayApp.showWindowOnConnect = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Alternate the tray icon with the case battery")
+                        checked: airPodsTrayApp.trayShowsCaseBattery
+                        onCheckedChanged: airPodsTrayApp.trayShowsCaseBattery = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Show an on-screen display when the listening mode changes")
+                        checked: airPodsTrayApp.modeOsdEnabled
+                        onCheckedChanged: airPodsTrayApp.modeOsdEnabled = checked
+                    }
+
+                    // Same order as AirPodsTrayApp::TrayAction
+                    GridLayout {
+                        columns: 2
+                        columnSpacing: 10
+
+                        Label {
+                            text: qsTr("Tray click:")
+                        }
+
+                        ComboBox {
+                            model: [qsTr("Open window"), qsTr("Toggle noise cancellation"), qsTr("Refresh battery"), qsTr("Nothing")]
+                            currentIndex: airPodsTrayApp.trayClickAction
+                            onActivated: airPodsTrayApp.trayClickAction = currentIndex
+                        }
+
+                        Label {
+                            text: qsTr("Tray double click:")
+                        }
+
+                        ComboBox {
+                            model: [qsTr("Open window"), qsTr("Toggle noise cancellation"), qsTr("Refresh battery"), qsTr("Nothing")]
+                            currentIndex: airPodsTrayApp.trayDoubleClickAction
+                            onActivated: airPodsTrayApp.trayDoubleClickAction = currentIndex
+                        }
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected
+                        text: qsTr("Ignore the case battery for these AirPods")
+                        checked: airPodsTrayApp.deviceInfo.ignoreCase
+                        onCheckedChanged: airPodsTrayApp.setIgnoreCaseBattery(checked)
+                    }
+
+                    Switch {
+                        text: qsTr("Restore each device's last volume when it reconnects")
+                        checked: airPodsTrayApp.restoreVolume
+                        onCheckedChanged: airPodsTrayApp.restoreVolume = checked
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected
+                        text: qsTr("Make these AirPods the default output while connected")
+                        checked: airPodsTrayApp.switchDefaultOutput
+                        onCheckedChanged: airPodsTrayApp.switchDefaultOutput = checked
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.switchDefaultOutput
+                        text: qsTr("Move playing streams along when switching")
+                        checked: airPodsTrayApp.moveStreamsToDefaultOutput
+                        onCheckedChanged: airPodsTrayApp.moveStreamsToDefaultOutput = checked
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected
+                        text: qsTr("Notify me if these AirPods go out of range")
+                        checked: airPodsTrayApp.outOfRangeAlert
+                        onCheckedChanged: airPodsTrayApp.outOfRangeAlert = checked
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.outOfRangeAlert
+                        text: qsTr("Play a sound with the out of range alert")
+                        checked: airPodsTrayApp.outOfRangeAlertSound
+                        onCheckedChanged: airPodsTrayApp.outOfRangeAlertSound = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Remember where AirPods were last seen (uses GeoClue)")
+                        checked: airPodsTrayApp.lastSeenEnabled
+                        onCheckedChanged: airPodsTrayApp.lastSeenEnabled = checked
+                    }
+
+                    Switch {
+                        text: qsTr("Announce incoming callers while wearing the AirPods")
+                        checked: airPodsTrayApp.announceCalls
+                        onCheckedChanged: airPodsTrayApp.announceCalls = checked
+                    }
+
+                    Column {
+                        spacing: 5
+
+                        Label {
+                            text: qsTr("Sounds")
+                            font.bold: true
+                        }
+
+                        Repeater {
+                            model: [
+                                { event: "connected", label: qsTr("Connected:") },
+                                { event: "disconnected", label: qsTr("Disconnected:") },
+                                { event: "lowBattery", label: qsTr("Low battery:") }
+                            ]
+
+                            // Names from the freedesktop sound theme, any other theme sound can be typed in
+                            delegate: RowLayout {
+                                spacing: 10
+
+                                Label {
+                                    text: modelData.label
+                                    Layout.preferredWidth: 110
+                                }
+
+                                ComboBox {
+                                    id: soundBox
+                                    editable: true
+                                    model: ["", "device-added", "device-removed", "battery-low", "bell", "complete", "message"]
+                                    editText: airPodsTrayApp.eventSounds[modelData.event] || ""
+                                    onActivated: airPodsTrayApp.setEventSound(modelData.event, currentText)
+                                    onAccepted: airPodsTrayApp.setEventSound(modelData.event, editText)
+                                }
+
+                                Button {
+                                    text: qsTr("Play")
+                                    enabled: soundBox.editText !== ""
+                                    onClicked: airPodsTrayApp.previewEventSound(soundBox.editText)
+                                }
+                            }
+                        }
+
+                        Switch {
+                            text: qsTr("Announce low battery at 20%, 10% and 5% through the AirPods")
+                            checked: airPodsTrayApp.lowBatteryAnnouncement
+                            onCheckedChanged: airPodsTrayApp.lowBatteryAnnouncement = checked
+                        }
+                    }
+
+                    Column {
+                        spacing: 5
+
+                        Label {
+                            text: qsTr("Wear-time reminder")
+                            font.bold: true
+                        }
+
+                        ComboBox {
+                            readonly property var intervals: [0, 60, 120, 180, 240]
+                            model: [qsTr("Off"), qsTr("Every hour"), qsTr("Every 2 hours"), qsTr("Every 3 hours"), qsTr("Every 4 hours")]
+                            currentIndex: Math.max(0, intervals.indexOf(airPodsTrayApp.wearReminderInterval))
+                            onActivated: airPodsTrayApp.wearReminderInterval = intervals[currentIndex]
+                        }
+
+                        Row {
+                            spacing: 10
+                            visible: airPodsTrayApp.wearReminderInterval > 0
+
+                            Label {
+                                anchors.verticalCenter: parent.verticalCenter
+                                text: qsTr("Quiet hours:")
+                            }
+
+                            TextField {
+                                width: 70
+                                placeholderText: "22:00"
+                                text: airPodsTrayApp.wearReminderQuietStart
+                                validator: RegularExpressionValidator { regularExpression: /^(\d{2}:\d{2})?$/ }
+                                onEditingFinished: airPodsTrayApp.wearReminderQuietStart = text
+                            }
+
+                            Label {
+                                anchors.verticalCenter: parent.verticalCenter
+                                text: qsTr("to")
+                            }
+
+                            TextField {
+                                width: 70
+                                placeholderText: "07:00"
+                                text: airPodsTrayApp.wearReminderQuietEnd
+                                validator: RegularExpressionValidator { regularExpression: /^(\d{2}:\d{2})?$/ }
+                                onEditingFinished: airPodsTrayApp.wearReminderQuietEnd = text
+                            }
+                        }
+                    }
+
+                    Column {
+                        spacing: 5
+
+                        Label {
+                            text: qsTr("Warn when audio plays while neither AirPod is worn")
+                            font.bold: true
+                        }
+
+                        ComboBox {
+                            readonly property var delays: [0, 15, 30, 60, 120]
+                            model: [qsTr("Off"), qsTr("After 15 seconds"), qsTr("After 30 seconds"), qsTr("After 1 minute"), qsTr("After 2 minutes")]
+                            currentIndex: Math.max(0, delays.indexOf(airPodsTrayApp.outOfEarPlaybackDelay))
+                            onActivated: airPodsTrayApp.outOfEarPlaybackDelay = delays[currentIndex]
+                        }
+
+                        Switch {
+                            visible: airPodsTrayApp.outOfEarPlaybackDelay > 0
+                            text: qsTr("Pause playback too")
+                            checked: airPodsTrayApp.outOfEarPlaybackPause
+                            onCheckedChanged: airPodsTrayApp.outOfEarPlaybackPause = checked
+                        }
+                    }
+
+                    Column {
+                        spacing: 5
+                        visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.deviceInfo.callControlsAvailable
+
+                        Label {
+                            text: qsTr("Calls")
+                            font.bold: true
+                        }
+
+                        Label {
+                            text: qsTr("Mute/Unmute and Hang Up:")
+                        }
+
+                        ComboBox {
+                            width: parent.width
+                            enabled: mainWindow.controlsEnabled
+                            model: [qsTr("Press once to mute, twice to hang up"), qsTr("Press once to hang up, twice to mute")]
+                            currentIndex: airPodsTrayApp.deviceInfo.callControlsFlipped ? 1 : 0
+                            onActivated: airPodsTrayApp.setCallControlsFlipped(currentIndex === 1)
+                        }
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.deviceInfo.allowOffOptionAvailable
+                        text: qsTr("Allow Off Listening Mode")
+                        enabled: mainWindow.controlsEnabled
+                        checked: airPodsTrayApp.deviceInfo.allowOffOption
+                        onToggled: airPodsTrayApp.setAllowOffOption(checked)
+                    }
+
+                    Switch {
+                        visible: airPodsTrayApp.airpodsConnected && airPodsTrayApp.deviceInfo.oneBudANCAvailable
+                        text: qsTr("One Bud ANC Mode")
+                        checked: airPodsTrayApp.deviceInfo.oneBudANCMode
+                        onCheckedChanged: airPodsTrayApp.deviceInfo.oneBudANCMode = checked
+
+                        ToolTip {
+                            visible: parent.hovered
+                            text: qsTr("Enable ANC when using one AirPod\n(More noise reduction, but uses more battery)")
+                            delay: 500
+                        }
+                    }
+
+                    Label {
+                        text: qsTr("Tray icon font: ") + airPodsTrayApp.trayIconFontStatus
+                    }
+
+                    Row {
+                        spacing: 5
+                        Label {
+                            text: qsTr("Bluetooth Retry Attempts:")
+                            anchors.verticalCenter: parent.verticalCenter
+                        }
+                        SpinBox {
+                            from: 1
+                            to: 10
+                            value: airPodsTrayApp.retryAttempts
+                            onValueChanged: airPodsTrayApp.retryAttempts = value
+                        }
+                    }
+
+                    Row {
+                        spacing: 10
+                        visible: airPodsTrayApp.airpodsConnected
+                        enabled: mainWindow.controlsEnabled
+
+                        TextField {
+                            id: newNameField
+                            placeholderText: airPodsTrayApp.deviceInfo.deviceName
+                            maximumLength: 32
+                        }
 
                         Button {
                             text: qsTr("Rename")
@@ -301,6 +1467,10 @@ ApplicationWindow {
                     }
 
 
+                    Label {
+                        text: qsTr("Magic Cloud Keys: ") + airPodsTrayApp.deviceInfo.keyStorageStatus
+                    }
+
                     Button {
                         text: qsTr("Show Magic Cloud Keys QR")
                         onClicked: keysQrDialog.show()
@@ -311,6 +1481,186 @@ ApplicationWindow {
                         encKey: airPodsTrayApp.deviceInfo.magicAccEncKey
                         irk: airPodsTrayApp.deviceInfo.magicAccIRK
                     }
+
+                    CheckBox {
+                        id: exportSerialsCheckBox
+                        text: qsTr("Include serial numbers in export")
+                        checked: false
+                    }
+
+                    CheckBox {
+                        id: exportKeysCheckBox
+                        text: qsTr("Include Magic Cloud Keys in export")
+                        checked: false
+                    }
+
+                    Row {
+                        spacing: 10
+
+                        Button {
+                            text: qsTr("Export Devices")
+                            onClicked: exportDialog.open()
+                        }
+
+                        Button {
+                            text: qsTr("Import Devices")
+                            onClicked: importDialog.open()
+                        }
+                    }
+
+                    FileDialog {
+                        id: exportDialog
+                        fileMode: FileDialog.SaveFile
+                        defaultSuffix: "json"
+                        nameFilters: [qsTr("JSON files (*.json)")]
+                        onAccepted: airPodsTrayApp.exportDevices(selectedFile, exportSerialsCheckBox.checked, exportKeysCheckBox.checked)
+                    }
+
+                    FileDialog {
+                        id: importDialog
+                        fileMode: FileDialog.OpenFile
+                        nameFilters: [qsTr("JSON files (*.json)")]
+                        onAccepted: airPodsTrayApp.importDevices(selectedFile)
+                    }
+
+                    Switch {
+                        id: advancedSwitch
+                        visible: airPodsTrayApp.airpodsConnected
+                        text: qsTr("Developer tools")
+                        checked: false
+                        onCheckedChanged: airPodsTrayApp.setBluezPropertiesWatched(checked)
+                        Component.onDestruction: airPodsTrayApp.setBluezPropertiesWatched(false)
+                    }
+
+                    Column {
+                        visible: advancedSwitch.visible && advancedSwitch.checked
+                        spacing: 4
+
+                        Repeater {
+                            model: airPodsTrayApp.bluezProperties
+                            delegate: Row {
+                                required property var modelData
+                                spacing: 10
+                                Label {
+                                    text: modelData.name
+                                    font.bold: true
+                                    width: 140
+                                }
+                                Label {
+                                    text: modelData.value
+                                    font.family: "monospace"
+                                }
+                            }
+                        }
+
+                        Button {
+                            text: qsTr("Spatial Audio Test")
+                            onClicked: stackView.push(spatialTestPage)
+                        }
+
+                        Button {
+                            visible: airPodsTrayApp.deviceInfo.hasEarTips
+                            text: qsTr("Ear Tip Fit Check")
+                            onClicked: stackView.push(fitCheckPage)
+                        }
+
+                        Label {
+                            text: qsTr("Raw battery: %1").arg(airPodsTrayApp.deviceInfo.battery.rawStatus)
+                            font.family: "monospace"
+                            topPadding: 10
+                        }
+
+                        Label {
+                            text: qsTr("Recent packets")
+                            font.bold: true
+                            topPadding: 10
+                        }
+
+                        Repeater {
+                            model: airPodsTrayApp.recentPackets
+                            delegate: Label {
+                                required property string modelData
+                                text: modelData
+                                font.family: "monospace"
+                            }
+                        }
+
+                        Label {
+                            text: qsTr("Settings changes this session")
+                            font.bold: true
+                            topPadding: 10
+                        }
+
+                        Label {
+                            visible: airPodsTrayApp.settingsLog.entries.length === 0
+                            text: qsTr("No changes yet")
+                            opacity: 0.6
+                        }
+
+                        Repeater {
+                            model: airPodsTrayApp.settingsLog.entries
+                            delegate: Label {
+                                required property var modelData
+                                text: qsTr("%1  %2: %3 → %4 (%5)").arg(modelData.time).arg(modelData.setting)
+                                    .arg(modelData.oldValue).arg(modelData.newValue).arg(modelData.source)
+                                font.family: "monospace"
+                            }
+                        }
+                    }
+
+                    Column {
+                        visible: airPodsTrayApp.syntheticDevices !== null
+                        spacing: 4
+
+                        Label {
+                            text: qsTr("Synthetic devices")
+                            font.bold: true
+                            topPadding: 10
+                        }
+
+                        Row {
+                            spacing: 10
+
+                            TextField {
+                                id: syntheticNameField
+                                placeholderText: qsTr("Name")
+                            }
+
+                            ComboBox {
+                                id: syntheticBehaviorBox
+                                model: [qsTr("Static"), qsTr("Draining"), qsTr("Charging"), qsTr("Cycling modes")]
+                            }
+
+                            Button {
+                                text: qsTr("Add")
+                                onClicked: {
+                                    airPodsTrayApp.syntheticDevices.add(syntheticNameField.text, 0, syntheticBehaviorBox.currentIndex)
+                                    syntheticNameField.clear()
+                                }
+                            }
+                        }
+
+                        Repeater {
+                            model: airPodsTrayApp.syntheticDevices ? airPodsTrayApp.syntheticDevices.devices : []
+                            delegate: Row {
+                                required property var modelData
+                                spacing: 10
+
+                                Label {
+                                    anchors.verticalCenter: parent.verticalCenter
+                                    text: qsTr("%1 (%2)  L %3%  R %4%  Case %5%  %6").arg(modelData.name).arg(modelData.behavior)
+                                        .arg(modelData.left).arg(modelData.right).arg(modelData.case).arg(modelData.mode)
+                                    font.family: "monospace"
+                                }
+
+                                Button {
+                                    flat: true
+                                    text: qsTr("Remove")
+                                    onClicked: airPodsTrayApp.syntheticDevices.remove(modelData.address)
+                                }
+                            }
+                        }
+                    }
                 }
             }
 
@@ -0,0 +1,55 @@
+import QtQuick 2.15
+import QtQuick.Controls 2.15
+import QtQuick.Window 2.15
+
+// Brief on-screen display for listening mode changes, like a volume OSD
+Window {
+    id: root
+    property string iconSource
+
+    flags: Qt.ToolTip | Qt.FramelessWindowHint | Qt.WindowStaysOnTopHint | Qt.WindowDoesNotAcceptFocus | Qt.WindowTransparentForInput
+    color: "transparent"
+    width: 260
+    height: 72
+    x: Screen.virtualX + (Screen.width - width) / 2
+    y: Screen.virtualY + Screen.height * 0.8 - height
+
+    function show(text) {
+        label.text = text
+        visible = true
+        hideTimer.restart()
+    }
+
+    Timer {
+        id: hideTimer
+        interval: 1500
+        onTriggered: root.visible = false
+    }
+
+    Rectangle {
+        anchors.fill: parent
+        radius: 16
+        color: "#cc202020"
+
+        Row {
+            anchors.centerIn: parent
+            spacing: 12
+
+            Image {
+                anchors.verticalCenter: parent.verticalCenter
+                source: root.iconSource
+                sourceSize.height: 40
+                fillMode: Image.PreserveAspectFit
+                visible: root.iconSource !== ""
+            }
+
+            Label {
+                id: label
+                anchors.verticalCenter: parent.verticalCenter
+                color: "white"
+                font.pixelSize: 18
+                font.weight: Font.Medium
+            }
+        }
+    }
+}
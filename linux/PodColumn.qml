@@ -1,13 +1,16 @@
 import QtQuick 2.15
+import QtQuick.Controls 2.15
 
 Column {
     id: root
     property bool inEar: true
+    // Set when the other bud is in use and this one stays in the case
+    property bool inCase: false
     property string iconSource
     property int batteryLevel: 0
     property bool isCharging: false
     property string indicator: ""
-    property real targetOpacity: inEar ? 1 : 0.5
+    property real targetOpacity: inCase ? 0.3 : inEar ? 1 : 0.5
 
     Timer {
         id: opacityTimer
@@ -19,6 +22,10 @@ Column {
         opacityTimer.restart()
     }
 
+    onInCaseChanged: {
+        opacityTimer.restart()
+    }
+
     spacing: 5
 
     Image {
@@ -36,4 +43,11 @@ Column {
         isCharging: parent.isCharging
         indicator: parent.indicator
     }
+
+    Label {
+        visible: root.inCase
+        text: qsTr("In case")
+        font.pixelSize: 12
+        anchors.horizontalCenter: parent.horizontalCenter
+    }
 }
\ No newline at end of file
@@ -3,7 +3,11 @@
 #define AIRPODS_PACKETS_H
 
 #include <QByteArray>
+#include <QString>
+#include <QStringList>
+#include <QPair>
 #include <optional>
+#include <algorithm>
 #include <climits>
 
 #include "enums.h"
@@ -145,6 +149,55 @@ namespace AirPodsPackets
         inline std::optional<bool> parseState(const QByteArray &data) { return Type::parseState(data); }
     }
 
+    // Tone volume of the connect chime and Siri sounds, 0-100
+    namespace ChimeVolume
+    {
+        static constexpr quint8 ID = 0x1F;
+        static const QByteArray HEADER = ControlCommand::HEADER + static_cast<char>(ID);
+
+        inline QByteArray getPacket(int volume)
+        {
+            return ControlCommand::createCommand(ID, static_cast<quint8>(volume), 0x50);
+        }
+
+        inline std::optional<int> parseVolume(const QByteArray &data)
+        {
+            if (!data.startsWith(HEADER))
+                return std::nullopt;
+
+            auto value = ControlCommand::parseActive(data);
+            if (!value || static_cast<quint8>(value.value()) > 100)
+                return std::nullopt;
+            return static_cast<quint8>(value.value());
+        }
+    }
+
+    // Call gestures: by default pressing once mutes/unmutes and pressing twice hangs up,
+    // the flipped configuration swaps the two
+    namespace CallManagement
+    {
+        static constexpr quint8 ID = 0x24;
+        static const QByteArray HEADER = ControlCommand::HEADER + static_cast<char>(ID);
+        static const QByteArray DEFAULT = ControlCommand::createCommand(ID, 0x00, 0x03);
+        static const QByteArray FLIPPED = ControlCommand::createCommand(ID, 0x00, 0x02);
+
+        inline std::optional<bool> parseFlipped(const QByteArray &data)
+        {
+            if (!data.startsWith(HEADER) || data.size() < HEADER.size() + 2)
+                return std::nullopt;
+
+            switch (data.at(HEADER.size() + 1))
+            {
+            case 0x02:
+                return true;
+            case 0x03:
+                return false;
+            default:
+                return std::nullopt;
+            }
+        }
+    }
+
     // Connection Packets
     namespace Connection
     {
@@ -247,6 +300,227 @@ namespace AirPodsPackets
         static const QByteArray HANDSHAKE_ACK = QByteArray::fromHex("01000400");
         static const QByteArray FEATURES_ACK = QByteArray::fromHex("040004002b00"); // Note: Only tested with airpods pro 2
     }
+
+    namespace Metadata
+    {
+        struct Info
+        {
+            QString name;
+            QString modelNumber;
+            QString manufacturer;
+            // Only sent by newer firmware, empty otherwise
+            QString serialNumber;
+            QString firmwareVersion;
+        };
+
+        inline std::optional<Info> parse(const QByteArray &data)
+        {
+            // Verify the data starts with the METADATA header
+            if (!data.startsWith(Parse::METADATA))
+            {
+                return std::nullopt;
+            }
+
+            int pos = Parse::METADATA.size(); // Start after the header
+
+            // Check if there is enough data to skip the initial bytes (based on example structure)
+            if (data.size() < pos + 6)
+            {
+                return std::nullopt;
+            }
+            pos += 6; // Skip 6 bytes after the header as per example structure
+
+            auto extractString = [&data, &pos]() -> QString
+            {
+                if (pos >= data.size())
+                {
+                    return QString();
+                }
+                int start = pos;
+                while (pos < data.size() && data.at(pos) != '\0')
+                {
+                    ++pos;
+                }
+                QString str = QString::fromUtf8(data.mid(start, pos - start));
+                if (pos < data.size())
+                {
+                    ++pos; // Move past the null terminator
+                }
+                return str;
+            };
+
+            Info info;
+            info.name = extractString();
+            info.modelNumber = extractString();
+            info.manufacturer = extractString();
+
+            // The remaining fields can be separated by several null bytes: serial number,
+            // firmware version, a second version, hardware revision, updater identifier, ...
+            QStringList extra;
+            while (pos < data.size())
+            {
+                QString field = extractString();
+                if (!field.isEmpty())
+                {
+                    extra << field;
+                }
+            }
+            info.serialNumber = extra.value(0);
+            info.firmwareVersion = extra.value(1);
+            return info;
+        }
+    }
+
+    // Head tracking (opcode 0x17), same packets the Android app sends by default
+    namespace HeadTracking
+    {
+        static const QByteArray START = QByteArray::fromHex("040004001700000010000f000873420b081010021a0501409c0000");
+        static const QByteArray STOP = QByteArray::fromHex("040004001700000010000f000875420b081010021a050100000000");
+        static const QByteArray DATA_HEADER = QByteArray::fromHex("04000400170000001000");
+
+        struct Sample
+        {
+            // Raw orientation components and acceleration, little endian in the packet
+            qint16 o1 = 0;
+            qint16 o2 = 0;
+            qint16 o3 = 0;
+            qint16 horizontalAcceleration = 0;
+            qint16 verticalAcceleration = 0;
+        };
+
+        inline std::optional<Sample> parse(const QByteArray &data)
+        {
+            if (!data.startsWith(DATA_HEADER) || data.size() <= 60)
+            {
+                return std::nullopt;
+            }
+
+            auto readInt16 = [&data](int index)
+            {
+                return static_cast<qint16>(static_cast<quint8>(data.at(index)) | (static_cast<quint8>(data.at(index + 1)) << 8));
+            };
+            return Sample{readInt16(43), readInt16(45), readInt16(47), readInt16(51), readInt16(53)};
+        }
+    }
+
+    // Other hosts the AirPods are connected to, reported on connect and whenever it changes
+    namespace ConnectedDevices
+    {
+        static const QByteArray HEADER = QByteArray::fromHex("040004002e00");
+        static const QByteArray SMART_ROUTING_RESPONSE = QByteArray::fromHex("040004001100");
+
+        struct Device
+        {
+            QString address;
+            quint8 info1 = 0;
+            quint8 info2 = 0;
+        };
+
+        inline std::optional<QList<Device>> parse(const QByteArray &data)
+        {
+            if (!data.startsWith(HEADER) || data.size() < 9)
+            {
+                return std::nullopt;
+            }
+
+            // Count at byte 8, then 6 address bytes and 2 unknown info bytes per device
+            quint8 count = static_cast<quint8>(data.at(8));
+            QList<Device> devices;
+            for (int i = 0, offset = 9; i < count && offset + 8 <= data.size(); ++i, offset += 8)
+            {
+                Device device;
+                device.address = QString::fromLatin1(data.mid(offset, 6).toHex(':').toUpper());
+                device.info1 = static_cast<quint8>(data.at(offset + 6));
+                device.info2 = static_cast<quint8>(data.at(offset + 7));
+                devices.append(device);
+            }
+            return devices;
+        }
+
+        // Smart routing responses carry the sender's address (reversed) and a plist mentioning its
+        // platform, which is the only hint of what kind of device it is
+        inline std::optional<QPair<QString, QString>> parseDeviceType(const QByteArray &data)
+        {
+            if (!data.startsWith(SMART_ROUTING_RESPONSE) || data.size() < 12)
+            {
+                return std::nullopt;
+            }
+
+            QByteArray address = data.mid(6, 6);
+            std::reverse(address.begin(), address.end());
+            for (const char *type : {"iPad", "Mac", "iPhone", "Linux", "Android"})
+            {
+                if (data.contains(type))
+                {
+                    return qMakePair(QString::fromLatin1(address.toHex(':').toUpper()), QString::fromLatin1(type));
+                }
+            }
+            return std::nullopt;
+        }
+    }
+
+    // What a received frame is, checked in the order AirPodsTrayApp::parseData handles them in.
+    // The snapshot tests dispatch through here too, so they see frames the way the app does.
+    enum class Frame
+    {
+        HandshakeAck,
+        FeaturesAck,
+        HeadTracking,
+        ConnectedDevices,
+        HostType,
+        MagicCloudKeys,
+        ConversationalAwareness,
+        HearingAid,
+        NoiseControl,
+        EarDetection,
+        Battery,
+        ConversationalAwarenessData,
+        Metadata,
+        AllowOffOption,
+        CallManagement,
+        ChimeVolume,
+        OneBudANCMode,
+        Unknown
+    };
+
+    inline Frame classify(const QByteArray &data)
+    {
+        if (data.startsWith(Parse::HANDSHAKE_ACK))
+            return Frame::HandshakeAck;
+        if (data.startsWith(Parse::FEATURES_ACK))
+            return Frame::FeaturesAck;
+        if (HeadTracking::parse(data))
+            return Frame::HeadTracking;
+        if (ConnectedDevices::parse(data))
+            return Frame::ConnectedDevices;
+        if (ConnectedDevices::parseDeviceType(data))
+            return Frame::HostType;
+        if (data.startsWith(MagicPairing::MAGIC_CLOUD_KEYS_HEADER))
+            return Frame::MagicCloudKeys;
+        if (data.startsWith(ConversationalAwareness::HEADER))
+            return Frame::ConversationalAwareness;
+        if (data.startsWith(HearingAid::HEADER))
+            return Frame::HearingAid;
+        if (data.size() == 11 && data.startsWith(NoiseControl::HEADER))
+            return Frame::NoiseControl;
+        if (data.size() == 8 && data.startsWith(Parse::EAR_DETECTION))
+            return Frame::EarDetection;
+        if ((data.size() == 22 || data.size() == 12) && data.startsWith(Parse::BATTERY_STATUS))
+            return Frame::Battery;
+        if (data.size() == 10 && data.startsWith(ConversationalAwareness::DATA_HEADER))
+            return Frame::ConversationalAwarenessData;
+        if (data.startsWith(Parse::METADATA))
+            return Frame::Metadata;
+        if (data.startsWith(AllowOffOption::HEADER))
+            return Frame::AllowOffOption;
+        if (data.startsWith(CallManagement::HEADER))
+            return Frame::CallManagement;
+        if (data.startsWith(ChimeVolume::HEADER))
+            return Frame::ChimeVolume;
+        if (data.startsWith(OneBudANCMode::HEADER))
+            return Frame::OneBudANCMode;
+        return Frame::Unknown;
+    }
 }
 
 #endif // AIRPODS_PACKETS_H
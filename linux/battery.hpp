@@ -4,7 +4,10 @@
 #include <QMap>
 #include <QString>
 #include <QObject>
+#include <QMetaEnum>
+#include <QStringList>
 #include <climits>
+#include <optional>
 
 #include "airpods_packets.h"
 #include "logger.h"
@@ -25,6 +28,7 @@ class Battery : public QObject
     Q_PROPERTY(quint8 caseLevel READ getCaseLevel NOTIFY batteryStatusChanged)
     Q_PROPERTY(bool caseCharging READ isCaseCharging NOTIFY batteryStatusChanged)
     Q_PROPERTY(bool caseAvailable READ isCaseAvailable NOTIFY batteryStatusChanged)
+    Q_PROPERTY(QString rawStatus READ getRawStatus NOTIFY batteryStatusChanged)
 
 public:
     explicit Battery(QObject *parent = nullptr) : QObject(parent)
@@ -39,6 +43,8 @@ public:
         states[Component::Left] = {};
         states[Component::Right] = {};
         states[Component::Case] = {};
+        rawStates.clear();
+        smoothing.clear();
         emit batteryStatusChanged();
     }
 
@@ -70,7 +76,7 @@ public:
     // Parse the battery status packet and detect primary/secondary pods
     bool parsePacket(const QByteArray &packet)
     {
-        if (!packet.startsWith(AirPodsPackets::Parse::BATTERY_STATUS))
+        if (!packet.startsWith(AirPodsPackets::Parse::BATTERY_STATUS) || packet.size() < 7)
         {
             return false;
         }
@@ -104,9 +110,14 @@ public:
             auto level = static_cast<quint8>(packet[offset + 2]);
             auto status = static_cast<BatteryStatus>(packet[offset + 3]);
 
+            // A disconnected component keeps its last level, only the raw state follows
             if (status != BatteryStatus::Disconnected)
             {
-                newStates[comp] = {level, status};
+                newStates[comp] = smoothed(comp, {level, status});
+            }
+            else
+            {
+                rawStates[comp] = {level, status};
             }
 
             // If this is a pod (Left or Right), add it to the list
@@ -184,7 +195,7 @@ public:
                 std::size_t idx = it - std::begin(batteries);
                 int battery = *it;
                 primaryPod = Component::Headset;
-                states[Component::Headset] =  {static_cast<quint8>(battery), statuses[idx] ? BatteryStatus::Charging : BatteryStatus::Discharging};
+                states[Component::Headset] = smoothed(Component::Headset, {static_cast<quint8>(battery), statuses[idx] ? BatteryStatus::Charging : BatteryStatus::Discharging});
             }
         } else {
             if (rawLeftBattery == CHAR_MAX) {
@@ -203,10 +214,10 @@ public:
             }
 
             // Update states
-            states[Component::Left] = {static_cast<quint8>(rawLeftBattery), isLeftCharging ? BatteryStatus::Charging : BatteryStatus::Discharging};
-            states[Component::Right] = {static_cast<quint8>(rawRightBattery), isRightCharging ? BatteryStatus::Charging : BatteryStatus::Discharging};
+            states[Component::Left] = smoothed(Component::Left, {static_cast<quint8>(rawLeftBattery), isLeftCharging ? BatteryStatus::Charging : BatteryStatus::Discharging});
+            states[Component::Right] = smoothed(Component::Right, {static_cast<quint8>(rawRightBattery), isRightCharging ? BatteryStatus::Charging : BatteryStatus::Discharging});
             if (podInCase) {
-                states[Component::Case] = {static_cast<quint8>(rawCaseBattery), isCaseCharging ? BatteryStatus::Charging : BatteryStatus::Discharging};
+                states[Component::Case] = smoothed(Component::Case, {static_cast<quint8>(rawCaseBattery), isCaseCharging ? BatteryStatus::Charging : BatteryStatus::Discharging});
             }
             primaryPod = isLeftPodPrimary ? Component::Left : Component::Right;
             secondaryPod = isLeftPodPrimary ? Component::Right : Component::Left;
@@ -223,10 +234,32 @@ public:
         return states.value(comp, {});
     }
 
+    // Get the last reported state for a component, before smoothing
+    BatteryState getRawState(Component comp) const
+    {
+        return rawStates.value(comp, {});
+    }
+
     // Get a formatted status string including charging state
     QString getComponentStatus(Component comp) const
     {
-        BatteryState state = getState(comp);
+        return formatState(getState(comp));
+    }
+
+    // Unsmoothed values as reported by the AirPods, for the developer tools
+    QString getRawStatus() const
+    {
+        QStringList parts;
+        for (auto it = rawStates.cbegin(); it != rawStates.cend(); ++it)
+        {
+            parts << QString("%1: %2").arg(QMetaEnum::fromType<Component>().valueToKey(static_cast<int>(it.key())),
+                                           formatState(it.value()));
+        }
+        return parts.join(", ");
+    }
+
+    static QString formatState(const BatteryState &state)
+    {
         if (state.level == 0)
         {
             return "Unknown";
@@ -277,6 +310,49 @@ private:
         return states.value(component).status == status;
     }
 
+    static bool same(const BatteryState &a, const BatteryState &b)
+    {
+        return a.level == b.level && a.status == b.status;
+    }
+
+    // Reported levels jitter between neighbouring steps (60 <-> 65) and the charging flag
+    // can flip for a single report. Changes show up right away, a bud going on the charger or
+    // into the case included; only a change that undoes the previous one has to be reported
+    // twice in a row, so flip-flopping values don't make the tray icon and notifications flap.
+    BatteryState smoothed(Component comp, const BatteryState &raw)
+    {
+        rawStates[comp] = raw;
+        BatteryState current = states.value(comp);
+        Smoothing &history = smoothing[comp];
+        if (current.status == BatteryStatus::Disconnected || raw.status == BatteryStatus::Disconnected)
+        {
+            history = {};
+            return raw;
+        }
+
+        if (same(raw, current))
+        {
+            history.pending.reset();
+            // Once the value has settled, going back later is a real change again
+            if (++history.steadyReports >= SettleReports)
+            {
+                history.previous.reset();
+            }
+            return current;
+        }
+
+        bool reversal = history.previous && same(raw, history.previous.value());
+        if (reversal && !(history.pending && same(raw, history.pending.value())))
+        {
+            history.pending = raw;
+            return current;
+        }
+        history.previous = current;
+        history.pending.reset();
+        history.steadyReports = 0;
+        return raw;
+    }
+
     std::pair<bool, int> formatBattery(unsigned char byteVal)
     {
         bool charging = (byteVal & 0x80) != 0;
@@ -284,7 +360,19 @@ private:
         return std::make_pair(charging, level);
     }
 
+    // What smoothed() needs to tell a flip-flop from a real change
+    struct Smoothing
+    {
+        std::optional<BatteryState> previous; // Shown before the last change
+        std::optional<BatteryState> pending;  // Reversal reported once so far
+        int steadyReports = 0;
+    };
+
+    static constexpr int SettleReports = 3;
+
     QMap<Component, BatteryState> states;
+    QMap<Component, BatteryState> rawStates;
+    QMap<Component, Smoothing> smoothing;
     Component primaryPod;
     Component secondaryPod;
 };
@@ -0,0 +1,281 @@
+#pragma once
+
+#include <QDateTime>
+#include <QFile>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QObject>
+#include <QTextStream>
+#include <QUrl>
+
+#include "battery.hpp"
+#include "logger.h"
+#include "storage.hpp"
+
+// Battery levels over time per device, appended to <state dir>/battery-history.csv whenever
+// they change and only read back for exports. "Battery replaced" and "calibrated" markers per
+// component go to battery-markers.csv; exports leave out a component's levels from before its
+// latest marker, so a new battery starts with a fresh baseline.
+class BatteryHistory : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit BatteryHistory(QObject *parent = nullptr)
+        : QObject(parent), m_file(Storage::stateDir() + "/battery-history.csv"),
+          m_markersFile(Storage::stateDir() + "/battery-markers.csv")
+    {
+        prune();
+        loadMarkers();
+    }
+
+    struct Levels
+    {
+        int left = -1;
+        int right = -1;
+        int caseLevel = -1;
+
+        bool isEmpty() const { return left < 0 && right < 0 && caseLevel < 0; }
+    };
+
+    // -1 for components that aren't reported, and for the case while it is ignored
+    static Levels levels(const Battery &battery, bool ignoreCase)
+    {
+        auto level = [&battery](Battery::Component component)
+        {
+            Battery::BatteryState state = battery.getState(component);
+            return state.status == Battery::BatteryStatus::Disconnected || state.level == 0 ? -1 : int(state.level);
+        };
+        return {level(Battery::Component::Left), level(Battery::Component::Right),
+                ignoreCase ? -1 : level(Battery::Component::Case)};
+    }
+
+    void record(const QString &address, const Battery &battery, bool ignoreCase)
+    {
+        Levels current = levels(battery, ignoreCase);
+        if (!current.isEmpty())
+            record(address, current.left, current.right, current.caseLevel);
+    }
+
+    // Levels are -1 when the component isn't reported
+    void record(const QString &address, int left, int right, int caseLevel)
+    {
+        Sample sample{QDateTime::currentDateTime(), address, left, right, caseLevel};
+        if (sample.sameLevels(m_last))
+            return;
+        m_last = sample;
+
+        QFile file(m_file);
+        if (file.open(QIODevice::Append | QIODevice::Text))
+            QTextStream(&file) << toCsv(sample) << "\n";
+    }
+
+    // Samples from the start of `from` to the end of `to`, an invalid date leaves that side open
+    QByteArray exportData(const QDate &from, const QDate &to, bool json) const
+    {
+        QList<Sample> samples = sinceBaselines(load(from, to));
+        if (json)
+        {
+            QJsonArray array;
+            for (const Sample &sample : samples)
+            {
+                QJsonObject object{{"time", sample.time.toString(Qt::ISODate)}, {"address", sample.address}};
+                if (sample.left >= 0)
+                    object.insert("left", sample.left);
+                if (sample.right >= 0)
+                    object.insert("right", sample.right);
+                if (sample.caseLevel >= 0)
+                    object.insert("case", sample.caseLevel);
+                array.append(object);
+            }
+            return QJsonDocument(array).toJson();
+        }
+
+        QByteArray csv = "time,address,left,right,case\n";
+        for (const Sample &sample : samples)
+            csv += toCsv(sample).toUtf8() + "\n";
+        return csv;
+    }
+
+    // Time of the latest marker for the component, invalid when there is none
+    QDateTime baseline(const QString &address, const QString &component) const
+    {
+        QDateTime latest;
+        for (const Marker &marker : m_markers)
+            if (marker.address == address && marker.component == component && (!latest.isValid() || marker.time > latest))
+                latest = marker.time;
+        return latest;
+    }
+
+public slots:
+    // component is left, right or case, kind is replaced or calibrated
+    bool addMarker(const QString &address, const QString &component, const QString &kind)
+    {
+        if (address.isEmpty() || !Components.contains(component) || !MarkerKinds.contains(kind))
+            return false;
+
+        Marker marker{QDateTime::currentDateTime(), address, component, kind};
+        QFile file(m_markersFile);
+        if (!file.open(QIODevice::Append | QIODevice::Text))
+        {
+            LOG_ERROR("Failed to save battery marker to " << file.fileName() << ": " << file.errorString());
+            return false;
+        }
+        QTextStream(&file) << QStringList{marker.time.toString(Qt::ISODate), address, component, kind}.join(',') << "\n";
+        m_markers.append(marker);
+        LOG_INFO("Battery marker for " << address << ": " << component << " " << kind);
+        emit markerAdded(address, component, kind);
+        return true;
+    }
+
+    // The format follows the file extension, dates are yyyy-MM-dd and may be empty
+    bool exportToFile(const QUrl &fileUrl, const QString &from, const QString &to) const
+    {
+        QFile file(fileUrl.isLocalFile() ? fileUrl.toLocalFile() : fileUrl.toString());
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text))
+        {
+            LOG_ERROR("Failed to export battery history to " << file.fileName() << ": " << file.errorString());
+            return false;
+        }
+
+        bool json = file.fileName().endsWith(".json", Qt::CaseInsensitive);
+        file.write(exportData(QDate::fromString(from, Qt::ISODate), QDate::fromString(to, Qt::ISODate), json));
+        LOG_INFO("Exported battery history to " << file.fileName());
+        return true;
+    }
+
+signals:
+    void markerAdded(const QString &address, const QString &component, const QString &kind);
+
+private:
+    struct Marker
+    {
+        QDateTime time;
+        QString address;
+        QString component;
+        QString kind;
+    };
+
+    inline static const QStringList Components = {"left", "right", "case"};
+    inline static const QStringList MarkerKinds = {"replaced", "calibrated"};
+
+    struct Sample
+    {
+        QDateTime time;
+        QString address;
+        int left = -1;
+        int right = -1;
+        int caseLevel = -1;
+
+        bool sameLevels(const Sample &other) const
+        {
+            return address == other.address && left == other.left && right == other.right && caseLevel == other.caseLevel;
+        }
+    };
+
+    static constexpr int MaxAgeDays = 90;
+
+    static QString level(int value) { return value < 0 ? QString() : QString::number(value); }
+
+    static QString toCsv(const Sample &sample)
+    {
+        return QStringList{sample.time.toString(Qt::ISODate), sample.address, level(sample.left), level(sample.right),
+                           level(sample.caseLevel)}
+            .join(',');
+    }
+
+    static int parseLevel(const QString &field)
+    {
+        bool ok = false;
+        int value = field.toInt(&ok);
+        return ok ? value : -1;
+    }
+
+    QList<Sample> load(const QDate &from, const QDate &to) const
+    {
+        QList<Sample> samples;
+        QFile file(m_file);
+        if (!file.open(QIODevice::ReadOnly | QIODevice::Text))
+            return samples;
+
+        QTextStream in(&file);
+        while (!in.atEnd())
+        {
+            QStringList fields = in.readLine().split(',');
+            if (fields.size() != 5)
+                continue;
+            QDateTime time = QDateTime::fromString(fields[0], Qt::ISODate);
+            if (!time.isValid() || (from.isValid() && time.date() < from) || (to.isValid() && time.date() > to))
+                continue;
+            samples.append({time, fields[1], parseLevel(fields[2]), parseLevel(fields[3]), parseLevel(fields[4])});
+        }
+        return samples;
+    }
+
+    // Markers are kept for good, they're rare and an old one still sets the baseline
+    void loadMarkers()
+    {
+        QFile file(m_markersFile);
+        if (!file.open(QIODevice::ReadOnly | QIODevice::Text))
+            return;
+
+        QTextStream in(&file);
+        while (!in.atEnd())
+        {
+            QStringList fields = in.readLine().split(',');
+            if (fields.size() != 4)
+                continue;
+            QDateTime time = QDateTime::fromString(fields[0], Qt::ISODate);
+            if (time.isValid() && Components.contains(fields[2]) && MarkerKinds.contains(fields[3]))
+                m_markers.append({time, fields[1], fields[2], fields[3]});
+        }
+    }
+
+    // Blanks levels recorded before the component's latest marker, samples left without any
+    // level are dropped
+    QList<Sample> sinceBaselines(QList<Sample> samples) const
+    {
+        if (m_markers.isEmpty())
+            return samples;
+
+        for (Sample &sample : samples)
+        {
+            auto before = [this, &sample](const QString &component) {
+                QDateTime marker = baseline(sample.address, component);
+                return marker.isValid() && sample.time < marker;
+            };
+            if (before("left"))
+                sample.left = -1;
+            if (before("right"))
+                sample.right = -1;
+            if (before("case"))
+                sample.caseLevel = -1;
+        }
+        samples.removeIf([](const Sample &sample) { return sample.left < 0 && sample.right < 0 && sample.caseLevel < 0; });
+        return samples;
+    }
+
+    // Rewrites the file without samples older than MaxAgeDays
+    void prune()
+    {
+        QDate cutoff = QDate::currentDate().addDays(-MaxAgeDays);
+        QList<Sample> samples = load(QDate(), QDate());
+        qsizetype before = samples.size();
+        samples.removeIf([&cutoff](const Sample &sample) { return sample.time.date() < cutoff; });
+        if (samples.size() == before)
+            return;
+
+        QFile file(m_file);
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text))
+            return;
+        QTextStream out(&file);
+        for (const Sample &sample : samples)
+            out << toCsv(sample) << "\n";
+    }
+
+    QString m_file;
+    QString m_markersFile;
+    QList<Marker> m_markers;
+    Sample m_last;
+};
@@ -0,0 +1,184 @@
+#pragma once
+
+#include <QDBusConnection>
+#include <QDBusMessage>
+#include <QDBusMetaType>
+#include <QDBusObjectPath>
+#include <QMap>
+#include <QObject>
+
+#include "BluetoothMonitor.h"
+#include "logger.h"
+
+typedef QMap<QString, QVariantMap> InterfaceList;
+Q_DECLARE_METATYPE(InterfaceList)
+
+// A single org.bluez.BatteryProvider1 object, BlueZ mirrors it as Battery1 on the device
+class BlueZBatteryObject : public QObject
+{
+    Q_OBJECT
+    Q_CLASSINFO("D-Bus Interface", "org.bluez.BatteryProvider1")
+    Q_PROPERTY(uchar Percentage READ percentage)
+    Q_PROPERTY(QDBusObjectPath Device READ device)
+    Q_PROPERTY(QString Source READ source)
+
+public:
+    BlueZBatteryObject(const QDBusObjectPath &device, uchar percentage, QObject *parent)
+        : QObject(parent), m_device(device), m_percentage(percentage) {}
+
+    uchar percentage() const { return m_percentage; }
+    void setPercentage(uchar percentage) { m_percentage = percentage; }
+    QDBusObjectPath device() const { return m_device; }
+    QString source() const { return "LibrePods"; }
+
+    InterfaceList interfaces() const
+    {
+        return {{"org.bluez.BatteryProvider1", QVariantMap{{"Percentage", QVariant::fromValue(m_percentage)},
+                                                           {"Device", QVariant::fromValue(m_device)},
+                                                           {"Source", source()}}}};
+    }
+
+private:
+    QDBusObjectPath m_device;
+    uchar m_percentage;
+};
+
+// Registers with org.bluez.BatteryProviderManager1 so the AirPods battery shows up in
+// everything reading BlueZ's Battery1 interface, e.g. the desktop's Bluetooth panel
+class BlueZBatteryProvider : public QObject
+{
+    Q_OBJECT
+    Q_CLASSINFO("D-Bus Interface", "org.freedesktop.DBus.ObjectManager")
+
+public:
+    explicit BlueZBatteryProvider(QObject *parent = nullptr)
+        : QObject(parent), m_dbus(QDBusConnection::systemBus())
+    {
+        qDBusRegisterMetaType<InterfaceList>();
+        qDBusRegisterMetaType<ManagedObjectList>();
+    }
+
+    ~BlueZBatteryProvider() override { unregisterProvider(); }
+
+    // Publishes or updates the level of the device at adapterPath/dev_XX_XX_...
+    void update(const QString &adapterPath, const QString &macAddress, uchar percentage)
+    {
+        if (adapterPath.isEmpty() || macAddress.isEmpty() || !registerProvider(adapterPath))
+            return;
+
+        QString path = objectPath(macAddress);
+        if (BlueZBatteryObject *battery = m_batteries.value(path))
+        {
+            if (battery->percentage() == percentage)
+                return;
+            battery->setPercentage(percentage);
+
+            QDBusMessage changed = QDBusMessage::createSignal(path, "org.freedesktop.DBus.Properties", "PropertiesChanged");
+            changed << "org.bluez.BatteryProvider1" << QVariantMap{{"Percentage", QVariant::fromValue(percentage)}} << QStringList();
+            m_dbus.send(changed);
+            return;
+        }
+
+        QDBusObjectPath device(adapterPath + "/dev_" + QString(macAddress).replace(":", "_").toUpper());
+        BlueZBatteryObject *battery = new BlueZBatteryObject(device, percentage, this);
+        if (!m_dbus.registerObject(path, battery, QDBusConnection::ExportAllProperties))
+        {
+            LOG_WARN("Failed to export battery provider object " << path);
+            delete battery;
+            return;
+        }
+        m_batteries.insert(path, battery);
+
+        QDBusMessage added = QDBusMessage::createSignal(RootPath, "org.freedesktop.DBus.ObjectManager", "InterfacesAdded");
+        added << QVariant::fromValue(QDBusObjectPath(path)) << QVariant::fromValue(battery->interfaces());
+        m_dbus.send(added);
+    }
+
+    void remove(const QString &macAddress)
+    {
+        QString path = objectPath(macAddress);
+        BlueZBatteryObject *battery = m_batteries.take(path);
+        if (!battery)
+            return;
+
+        m_dbus.unregisterObject(path);
+        delete battery;
+
+        QDBusMessage removed = QDBusMessage::createSignal(RootPath, "org.freedesktop.DBus.ObjectManager", "InterfacesRemoved");
+        removed << QVariant::fromValue(QDBusObjectPath(path)) << QStringList{"org.bluez.BatteryProvider1"};
+        m_dbus.send(removed);
+    }
+
+public slots:
+    ManagedObjectList GetManagedObjects() const
+    {
+        ManagedObjectList objects;
+        for (auto it = m_batteries.constBegin(); it != m_batteries.constEnd(); ++it)
+            objects.insert(QDBusObjectPath(it.key()), it.value()->interfaces());
+        return objects;
+    }
+
+private:
+    static constexpr const char *RootPath = "/me/kavishdevar/librepods/battery";
+
+    static QString objectPath(const QString &macAddress)
+    {
+        return QString(RootPath) + "/dev_" + QString(macAddress).replace(":", "_").toUpper();
+    }
+
+    bool registerProvider(const QString &adapterPath)
+    {
+        if (m_adapterPath == adapterPath)
+            return true;
+        if (m_registrationFailed)
+            return false;
+        unregisterProvider();
+
+        if (!m_dbus.registerObject(RootPath, this, QDBusConnection::ExportAllSlots))
+        {
+            LOG_WARN("Failed to export the battery provider on the system bus");
+            m_registrationFailed = true;
+            return false;
+        }
+
+        QDBusMessage call = QDBusMessage::createMethodCall("org.bluez", adapterPath, "org.bluez.BatteryProviderManager1",
+                                                           "RegisterBatteryProvider");
+        call << QVariant::fromValue(QDBusObjectPath(RootPath));
+        QDBusMessage reply = m_dbus.call(call);
+        if (reply.type() == QDBusMessage::ErrorMessage)
+        {
+            // Older BlueZ versions only offer the provider API with --experimental
+            LOG_WARN("BlueZ battery provider registration failed: " << reply.errorMessage());
+            m_dbus.unregisterObject(RootPath);
+            m_registrationFailed = true;
+            return false;
+        }
+
+        LOG_INFO("Registered as BlueZ battery provider on " << adapterPath);
+        m_adapterPath = adapterPath;
+        return true;
+    }
+
+    void unregisterProvider()
+    {
+        if (m_adapterPath.isEmpty())
+            return;
+
+        for (const QString &path : m_batteries.keys())
+            m_dbus.unregisterObject(path);
+        qDeleteAll(m_batteries);
+        m_batteries.clear();
+
+        QDBusMessage call = QDBusMessage::createMethodCall("org.bluez", m_adapterPath, "org.bluez.BatteryProviderManager1",
+                                                           "UnregisterBatteryProvider");
+        call << QVariant::fromValue(QDBusObjectPath(RootPath));
+        m_dbus.call(call);
+        m_dbus.unregisterObject(RootPath);
+        m_adapterPath.clear();
+    }
+
+    QDBusConnection m_dbus;
+    QString m_adapterPath;
+    bool m_registrationFailed = false;
+    QMap<QString, BlueZBatteryObject *> m_batteries;
+};
@@ -0,0 +1,78 @@
+#pragma once
+
+#include <QDBusConnection>
+#include <QDBusMessage>
+#include <QDBusObjectPath>
+#include <QDBusPendingCallWatcher>
+#include <QDBusPendingReply>
+#include <QObject>
+#include <QProcess>
+#include <QStandardPaths>
+#include <QVariantMap>
+
+#include "logger.h"
+
+// Incoming calls from KDE Connect's telephony plugin on the session bus and from ModemManager
+// (Linux phones, USB modems) on the system bus. Each ringing call is reported once through
+// incomingCall(); announce() reads the caller out with speech-dispatcher or espeak-ng.
+class CallAnnouncer : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit CallAnnouncer(QObject *parent = nullptr) : QObject(parent)
+    {
+        // Empty service and path match every paired KDE Connect device
+        QDBusConnection::sessionBus().connect(QString(), QString(), "org.kde.kdeconnect.device.telephony", "callReceived", this,
+                                              SLOT(onKdeConnectCall(QString, QString, QString)));
+        QDBusConnection::systemBus().connect("org.freedesktop.ModemManager1", QString(), "org.freedesktop.ModemManager1.Modem.Voice",
+                                             "CallAdded", this, SLOT(onModemCallAdded(QDBusObjectPath)));
+    }
+
+    // Text for the announcement, the contact name when the source knows it
+    static QString announcement(const QString &number, const QString &contactName)
+    {
+        if (!contactName.isEmpty())
+            return tr("Call from %1").arg(contactName);
+        if (!number.isEmpty())
+            return tr("Call from %1").arg(number);
+        return tr("Incoming call");
+    }
+
+    static bool announce(const QString &text)
+    {
+        if (!QStandardPaths::findExecutable("spd-say").isEmpty())
+            return QProcess::startDetached("spd-say", {"--wait", text});
+        if (!QStandardPaths::findExecutable("espeak-ng").isEmpty())
+            return QProcess::startDetached("espeak-ng", {text});
+        LOG_WARN("Neither spd-say nor espeak-ng is installed, cannot announce the caller");
+        return false;
+    }
+
+signals:
+    void incomingCall(const QString &number, const QString &contactName);
+
+private slots:
+    void onKdeConnectCall(const QString &event, const QString &number, const QString &contactName)
+    {
+        if (event == "ringing")
+            emit incomingCall(number, contactName);
+    }
+
+    void onModemCallAdded(const QDBusObjectPath &path)
+    {
+        QDBusMessage message = QDBusMessage::createMethodCall("org.freedesktop.ModemManager1", path.path(),
+                                                              "org.freedesktop.DBus.Properties", "GetAll");
+        message << QString("org.freedesktop.ModemManager1.Call");
+        auto *watcher = new QDBusPendingCallWatcher(QDBusConnection::systemBus().asyncCall(message), this);
+        connect(watcher, &QDBusPendingCallWatcher::finished, this, [this](QDBusPendingCallWatcher *call) {
+            call->deleteLater();
+            QDBusPendingReply<QVariantMap> reply = *call;
+            if (reply.isError())
+                return;
+            // MM_CALL_DIRECTION_INCOMING, ModemManager has no contact names
+            if (reply.value().value("Direction").toUInt() == 1)
+                emit incomingCall(reply.value().value("Number").toString(), QString());
+        });
+    }
+};
@@ -0,0 +1,179 @@
+#pragma once
+
+#include <QDateTime>
+#include <QFile>
+#include <QObject>
+#include <QTextStream>
+#include <QUrl>
+#include <QVariantList>
+
+#include "logger.h"
+#include "storage.hpp"
+
+// Connect/disconnect/reconnect/mode change history, kept in <state dir>/timeline.csv
+// so dropouts can be correlated with other activity later on
+class ConnectionTimeline : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(QVariantList events READ events NOTIFY eventsChanged)
+
+public:
+    explicit ConnectionTimeline(QObject *parent = nullptr)
+        : QObject(parent), m_file(Storage::stateDir() + "/timeline.csv")
+    {
+        load();
+    }
+
+    QVariantList events() const
+    {
+        QVariantList list;
+        // Newest first for the timeline view
+        for (auto it = m_events.crbegin(); it != m_events.crend(); ++it)
+        {
+            list.append(QVariantMap{{"time", it->time.toString("yyyy-MM-dd HH:mm:ss")},
+                                    {"address", it->address},
+                                    {"event", it->event},
+                                    {"detail", it->detail}});
+        }
+        return list;
+    }
+
+    void record(const QString &address, const QString &event, const QString &detail = QString())
+    {
+        Event entry{QDateTime::currentDateTime(), address, event, detail};
+        m_events.append(entry);
+        if (m_events.size() > MaxEvents)
+        {
+            m_events.removeFirst();
+        }
+
+        QFile file(m_file);
+        if (file.open(QIODevice::Append | QIODevice::Text))
+        {
+            QTextStream(&file) << toCsv(entry) << "\n";
+        }
+        emit eventsChanged();
+    }
+
+public slots:
+    bool exportCsv(const QUrl &fileUrl) const
+    {
+        QFile file(fileUrl.isLocalFile() ? fileUrl.toLocalFile() : fileUrl.toString());
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text))
+        {
+            LOG_ERROR("Failed to export timeline to " << file.fileName() << ": " << file.errorString());
+            return false;
+        }
+
+        QTextStream out(&file);
+        out << "time,address,event,detail\n";
+        for (const Event &entry : m_events)
+        {
+            out << toCsv(entry) << "\n";
+        }
+        LOG_INFO("Exported " << m_events.size() << " timeline events to " << file.fileName());
+        return true;
+    }
+
+    void clear()
+    {
+        m_events.clear();
+        QFile::remove(m_file);
+        emit eventsChanged();
+    }
+
+signals:
+    void eventsChanged();
+
+private:
+    struct Event
+    {
+        QDateTime time;
+        QString address;
+        QString event;
+        QString detail;
+    };
+
+    static constexpr int MaxEvents = 500;
+
+    static QString escape(QString field)
+    {
+        if (field.contains(',') || field.contains('"') || field.contains('\n'))
+        {
+            field = '"' + field.replace("\"", "\"\"") + '"';
+        }
+        return field;
+    }
+
+    static QString toCsv(const Event &entry)
+    {
+        return QStringList{entry.time.toString(Qt::ISODate), escape(entry.address), escape(entry.event), escape(entry.detail)}.join(',');
+    }
+
+    static QStringList parseCsvLine(const QString &line)
+    {
+        QStringList fields;
+        QString field;
+        bool quoted = false;
+        for (qsizetype i = 0; i < line.size(); ++i)
+        {
+            QChar c = line.at(i);
+            if (quoted && c == '"' && i + 1 < line.size() && line.at(i + 1) == '"')
+            {
+                field += '"';
+                ++i;
+            }
+            else if (c == '"')
+            {
+                quoted = !quoted;
+            }
+            else if (c == ',' && !quoted)
+            {
+                fields.append(field);
+                field.clear();
+            }
+            else
+            {
+                field += c;
+            }
+        }
+        fields.append(field);
+        return fields;
+    }
+
+    void load()
+    {
+        QFile file(m_file);
+        if (!file.open(QIODevice::ReadOnly | QIODevice::Text))
+        {
+            return;
+        }
+
+        QTextStream in(&file);
+        while (!in.atEnd())
+        {
+            QStringList fields = parseCsvLine(in.readLine());
+            if (fields.size() != 4)
+                continue;
+            m_events.append({QDateTime::fromString(fields[0], Qt::ISODate), fields[1], fields[2], fields[3]});
+        }
+        while (m_events.size() > MaxEvents)
+        {
+            m_events.removeFirst();
+        }
+        file.close();
+
+        // Keep the file from growing without bound
+        if (file.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text))
+        {
+            QTextStream out(&file);
+            for (const Event &entry : std::as_const(m_events))
+            {
+                out << toCsv(entry) << "\n";
+            }
+        }
+    }
+
+    QString m_file;
+    QList<Event> m_events;
+};
@@ -0,0 +1,162 @@
+#pragma once
+
+#include <QDate>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QLocalServer>
+#include <QLocalSocket>
+#include <QObject>
+#include <QSettings>
+
+#include <functional>
+
+#include "localsocketauth.hpp"
+#include "logger.h"
+
+// Commands from librepods-ctl and from a second instance on the local socket, one per connection.
+// Listening mode changes go out as the same commands the HTTP API and D-Bus send, the reports
+// are read through the providers. Peers outside ipc/allowedGroups are turned away.
+class ControlServer : public QObject
+{
+    Q_OBJECT
+
+public:
+    struct Providers
+    {
+        std::function<QString()> status;
+        std::function<QString()> battery;
+        std::function<QJsonArray()> devices;
+        // Invalid dates leave the range open
+        std::function<QByteArray(const QDate &from, const QDate &to, bool json)> history;
+    };
+
+    explicit ControlServer(Providers providers, QObject *parent = nullptr) : QObject(parent), m_providers(std::move(providers))
+    {
+        m_server.setSocketOptions(QLocalServer::AbstractNamespaceOption);
+        connect(&m_server, &QLocalServer::newConnection, this, &ControlServer::acceptConnections);
+        connect(&m_server, &QLocalServer::serverError, this, [this]()
+                {
+                    LOG_ERROR("Server failed to accept a new connection");
+                    LOG_DEBUG("Server error: " << m_server.errorString()); });
+    }
+
+    void setReadOnly(bool readOnly) { m_readOnly = readOnly; }
+
+    bool start(QSettings &settings)
+    {
+        m_allowedGroups = LocalSocketAuth::parseGroups(settings.value("ipc/allowedGroups").toStringList());
+        if (!m_server.listen(LocalSocketAuth::serverName()))
+        {
+            LOG_ERROR("Unable to start the listening server");
+            LOG_DEBUG("Server error: " << m_server.errorString());
+            return false;
+        }
+        LOG_DEBUG("Server started, waiting for connections...");
+        return true;
+    }
+
+    void close()
+    {
+        if (m_server.isListening())
+            m_server.close();
+    }
+
+signals:
+    void commandReceived(const QString &source, const QJsonObject &command);
+    void reopenRequested();
+    // KDE Connect's Run Command plugin sends connect from the phone to hand the AirPods over
+    void connectRequested();
+
+private:
+    void acceptConnections()
+    {
+        while (QLocalSocket *socket = m_server.nextPendingConnection())
+        {
+            if (!LocalSocketAuth::isPeerAllowed(socket->socketDescriptor(), m_allowedGroups))
+            {
+                LOG_WARN("Rejected control connection from another user");
+                socket->abort();
+                socket->deleteLater();
+                continue;
+            }
+            connect(socket, &QLocalSocket::readyRead, this, [this, socket]()
+                    {
+                        QByteArray reply = handle(QString::fromUtf8(socket->readAll()));
+                        if (!reply.isEmpty())
+                        {
+                            socket->write(reply);
+                            socket->flush();
+                        }
+                        socket->disconnectFromServer(); });
+            connect(socket, &QLocalSocket::disconnected, socket, &QObject::deleteLater);
+            connect(socket, &QLocalSocket::errorOccurred, this, [socket]()
+                    {
+                        LOG_ERROR("Failed to connect to the duplicate app instance");
+                        LOG_DEBUG("Connection error: " << socket->errorString()); });
+        }
+    }
+
+    // The reply sent back, empty for commands that have none
+    QByteArray handle(const QString &message)
+    {
+        static const QStringList modes = {"off", "anc", "transparency", "adaptive"};
+
+        if (message == "reopen")
+        {
+            LOG_INFO("Reopening app window");
+            emit reopenRequested();
+        }
+        else if (message.startsWith("noise:") && modes.contains(message.mid(6)))
+        {
+            setMode(modes.indexOf(message.mid(6)));
+        }
+        else if (message.startsWith("set-mode "))
+        {
+            int mode = modes.indexOf(message.mid(9).trimmed());
+            if (mode < 0)
+                return "Unknown mode, use off, anc, transparency or adaptive\n";
+            setMode(mode);
+        }
+        else if (message == "connect")
+        {
+            if (m_readOnly)
+                return "Blocked: LibrePods is in read-only mode\n";
+            emit connectRequested();
+        }
+        else if (message == "status")
+        {
+            return m_providers.status().toUtf8();
+        }
+        else if (message == "battery")
+        {
+            return m_providers.battery().toUtf8();
+        }
+        else if (message == "list-devices")
+        {
+            return QJsonDocument(m_providers.devices()).toJson();
+        }
+        else if (message.startsWith("export-history"))
+        {
+            // export-history <csv|json> <from> <to>, "-" leaves a date open
+            QStringList args = message.split(' ');
+            return m_providers.history(QDate::fromString(args.value(2), Qt::ISODate),
+                                       QDate::fromString(args.value(3), Qt::ISODate), args.value(1) == "json");
+        }
+        else
+        {
+            LOG_ERROR("Unknown message received: " << message);
+        }
+        return QByteArray();
+    }
+
+    void setMode(int mode)
+    {
+        emit commandReceived("librepods-ctl", {{"command", "setNoiseControlMode"}, {"mode", mode}});
+    }
+
+    QLocalServer m_server;
+    Providers m_providers;
+    QList<uint> m_allowedGroups;
+    bool m_readOnly = false;
+};
@@ -0,0 +1,100 @@
+#pragma once
+
+#include <QDate>
+#include <QDateTime>
+#include <QFile>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QObject>
+
+#include "logger.h"
+#include "storage.hpp"
+
+// Per day count and duration of conversational awareness volume ducks, kept in
+// <state dir>/conversation-stats.json so users can judge how often it kicks in
+class ConversationStats : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(int todayCount READ todayCount NOTIFY statsChanged)
+    Q_PROPERTY(int todaySeconds READ todaySeconds NOTIFY statsChanged)
+    Q_PROPERTY(QString lastDucked READ lastDucked NOTIFY statsChanged)
+
+public:
+    explicit ConversationStats(QObject *parent = nullptr)
+        : QObject(parent), m_file(Storage::stateDir() + "/conversation-stats.json")
+    {
+        load();
+    }
+
+    int todayCount() const { return m_days.value(today()).toObject().value("count").toInt(); }
+    int todaySeconds() const { return m_days.value(today()).toObject().value("seconds").toInt(); }
+    QString lastDucked() const { return m_days.value(today()).toObject().value("last").toString(); }
+
+    void duckingStarted()
+    {
+        if (m_duckStart.isValid())
+            return;
+        m_duckStart = QDateTime::currentDateTime();
+
+        QJsonObject day = m_days.value(today()).toObject();
+        day["count"] = day.value("count").toInt() + 1;
+        day["last"] = m_duckStart.toString("HH:mm");
+        m_days[today()] = day;
+        save();
+    }
+
+    void duckingEnded()
+    {
+        if (!m_duckStart.isValid())
+            return;
+
+        // Attributed to the day the duck started on
+        QString key = m_duckStart.date().toString(Qt::ISODate);
+        QJsonObject day = m_days.value(key).toObject();
+        day["seconds"] = day.value("seconds").toInt() + static_cast<int>(m_duckStart.secsTo(QDateTime::currentDateTime()));
+        m_days[key] = day;
+        m_duckStart = QDateTime();
+        save();
+    }
+
+signals:
+    void statsChanged();
+
+private:
+    static constexpr int KeptDays = 30;
+
+    static QString today() { return QDate::currentDate().toString(Qt::ISODate); }
+
+    void load()
+    {
+        QFile file(m_file);
+        if (!file.open(QIODevice::ReadOnly))
+            return;
+        m_days = QJsonDocument::fromJson(file.readAll()).object();
+    }
+
+    void save()
+    {
+        QString cutoff = QDate::currentDate().addDays(-KeptDays).toString(Qt::ISODate);
+        for (auto it = m_days.begin(); it != m_days.end();)
+        {
+            // ISO dates compare correctly as strings
+            it = it.key() < cutoff ? m_days.erase(it) : it + 1;
+        }
+
+        QFile file(m_file);
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate))
+        {
+            LOG_WARN("Failed to write " << m_file << ": " << file.errorString());
+        }
+        else
+        {
+            file.write(QJsonDocument(m_days).toJson(QJsonDocument::Compact));
+        }
+        emit statsChanged();
+    }
+
+    QString m_file;
+    QJsonObject m_days;
+    QDateTime m_duckStart;
+};
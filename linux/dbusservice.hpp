@@ -0,0 +1,137 @@
+#pragma once
+
+#include <QDBusConnection>
+#include <QDBusContext>
+#include <QDBusMessage>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QObject>
+
+#include <functional>
+
+#include "logger.h"
+
+// org.librepods.Daemon at /org/librepods/Daemon on the session bus, for desktop applets and
+// scripts. The properties follow the real device, GetDevices() returns the same list as
+// GET /devices on the HTTP API.
+class DBusService : public QObject, protected QDBusContext
+{
+    Q_OBJECT
+    Q_CLASSINFO("D-Bus Interface", "org.librepods.Daemon")
+    Q_PROPERTY(bool Connected READ connected)
+    Q_PROPERTY(QString Address READ address)
+    Q_PROPERTY(QString Name READ name)
+    Q_PROPERTY(QString ListeningMode READ listeningMode)
+    Q_PROPERTY(bool ConversationDetection READ conversationDetection)
+    Q_PROPERTY(int BatteryLeft READ batteryLeft)
+    Q_PROPERTY(int BatteryRight READ batteryRight)
+    Q_PROPERTY(int BatteryCase READ batteryCase)
+
+public:
+    using DevicesProvider = std::function<QJsonArray()>;
+
+    static constexpr const char *ServiceName = "org.librepods.Daemon";
+    static constexpr const char *ObjectPath = "/org/librepods/Daemon";
+
+    explicit DBusService(DevicesProvider devices, QObject *parent = nullptr)
+        : QObject(parent), m_devices(std::move(devices)) {}
+
+    bool start()
+    {
+        QDBusConnection bus = QDBusConnection::sessionBus();
+        if (!bus.registerObject(ObjectPath, this, QDBusConnection::ExportAllSlots | QDBusConnection::ExportAllProperties))
+        {
+            LOG_WARN("Failed to register the D-Bus object: " << bus.lastError().message());
+            return false;
+        }
+        if (!bus.registerService(ServiceName))
+        {
+            LOG_WARN("Failed to register " << ServiceName << " on the session bus: " << bus.lastError().message());
+            bus.unregisterObject(ObjectPath);
+            return false;
+        }
+        m_last = properties();
+        LOG_INFO("D-Bus service " << ServiceName << " registered");
+        return true;
+    }
+
+    // Sends PropertiesChanged for whatever differs from the last call
+    void refresh()
+    {
+        QVariantMap current = properties();
+        QVariantMap changed;
+        for (auto it = current.cbegin(); it != current.cend(); ++it)
+            if (m_last.value(it.key()) != it.value())
+                changed.insert(it.key(), it.value());
+        m_last = current;
+        if (changed.isEmpty())
+            return;
+
+        QDBusMessage signal = QDBusMessage::createSignal(ObjectPath, "org.freedesktop.DBus.Properties", "PropertiesChanged");
+        signal << QString(ServiceName) << changed << QStringList();
+        QDBusConnection::sessionBus().send(signal);
+    }
+
+    bool connected() const { return device().value("connected").toBool(); }
+    QString address() const { return device().value("address").toString(); }
+    QString name() const { return device().value("deviceName").toString(); }
+    QString listeningMode() const { return device().value("listening_mode").toString(); }
+    bool conversationDetection() const { return device().value("conversational_awareness").toBool(); }
+    int batteryLeft() const { return battery("left"); }
+    int batteryRight() const { return battery("right"); }
+    int batteryCase() const { return battery("case"); }
+
+public slots:
+    QString GetDevices() const
+    {
+        return QString::fromUtf8(QJsonDocument(m_devices()).toJson(QJsonDocument::Compact));
+    }
+
+    // off, anc, transparency or adaptive
+    void SetListeningMode(const QString &mode)
+    {
+        static const QStringList modes = {"off", "anc", "transparency", "adaptive"};
+        if (!modes.contains(mode))
+        {
+            if (calledFromDBus())
+                sendErrorReply(QDBusError::InvalidArgs, "mode must be one of off, anc, transparency, adaptive");
+            return;
+        }
+        emit commandReceived("dbus", {{"command", "setNoiseControlMode"}, {"mode", modes.indexOf(mode)}});
+    }
+
+    void SetConversationDetection(bool enabled)
+    {
+        emit commandReceived("dbus", {{"command", "setConversationalAwareness"}, {"enabled", enabled}});
+    }
+
+signals:
+    void commandReceived(const QString &source, const QJsonObject &command);
+
+private:
+    // Synthetic devices from --debug are only listed by GetDevices()
+    QJsonObject device() const
+    {
+        for (const QJsonValue &value : m_devices())
+            if (!value.toObject().value("synthetic").toBool())
+                return value.toObject();
+        return QJsonObject();
+    }
+
+    // -1 when not reported
+    int battery(const QString &component) const
+    {
+        return device().value("battery").toObject().value(component).toInt(-1);
+    }
+
+    QVariantMap properties() const
+    {
+        return {{"Connected", connected()}, {"Address", address()}, {"Name", name()}, {"ListeningMode", listeningMode()},
+                {"ConversationDetection", conversationDetection()}, {"BatteryLeft", batteryLeft()},
+                {"BatteryRight", batteryRight()}, {"BatteryCase", batteryCase()}};
+    }
+
+    DevicesProvider m_devices;
+    QVariantMap m_last;
+};
@@ -0,0 +1,126 @@
+#pragma once
+
+#include <QByteArray>
+#include <QMetaEnum>
+#include <QObject>
+#include <QStringList>
+
+#include "airpods_packets.h"
+
+// Features a device has shown it supports. The bits of the features ack haven't been mapped yet,
+// so a capability is learned from the first frame the device sends for it. Stored per device, it
+// lets the controls show as soon as the device reconnects instead of waiting for those frames.
+// The ack itself is kept as is, for the status report and for whoever decodes it.
+class DeviceCapabilities
+{
+    Q_GADGET
+
+public:
+    enum Capability
+    {
+        None = 0,
+        NoiseControl = 1 << 0,
+        ConversationalAwareness = 1 << 1,
+        HearingAid = 1 << 2,
+        AllowOffOption = 1 << 3,
+        CallControls = 1 << 4,
+        ChimeVolume = 1 << 5,
+        OneBudANC = 1 << 6,
+        HeadTracking = 1 << 7,
+    };
+    Q_ENUM(Capability)
+
+    static Capability forFrame(AirPodsPackets::Frame frame)
+    {
+        using AirPodsPackets::Frame;
+        switch (frame)
+        {
+        case Frame::NoiseControl:
+            return NoiseControl;
+        case Frame::ConversationalAwareness:
+            return ConversationalAwareness;
+        case Frame::HearingAid:
+            return HearingAid;
+        case Frame::AllowOffOption:
+            return AllowOffOption;
+        case Frame::CallManagement:
+            return CallControls;
+        case Frame::ChimeVolume:
+            return ChimeVolume;
+        case Frame::OneBudANCMode:
+            return OneBudANC;
+        case Frame::HeadTracking:
+            return HeadTracking;
+        default:
+            return None;
+        }
+    }
+
+    bool has(Capability capability) const { return m_flags & capability; }
+    bool isEmpty() const { return m_flags == 0 && m_featuresAck.isEmpty(); }
+
+    // False when the capability was already known
+    bool add(Capability capability)
+    {
+        if (capability == None || has(capability))
+            return false;
+        m_flags |= capability;
+        return true;
+    }
+
+    // Payload of the features ack after its header, empty until one was received
+    QByteArray featuresAck() const { return m_featuresAck; }
+    bool setFeaturesAck(const QByteArray &ack)
+    {
+        QByteArray payload = ack.mid(AirPodsPackets::Parse::FEATURES_ACK.size());
+        if (payload == m_featuresAck)
+            return false;
+        m_featuresAck = payload;
+        return true;
+    }
+
+    // Capability names plus "features:<hex>", the form kept in the settings file
+    QStringList toStringList() const
+    {
+        QStringList names;
+        const QMetaEnum capabilities = QMetaEnum::fromType<Capability>();
+        for (int i = 0; i < capabilities.keyCount(); ++i)
+        {
+            if (capabilities.value(i) != None && has(static_cast<Capability>(capabilities.value(i))))
+                names << capabilities.key(i);
+        }
+        if (!m_featuresAck.isEmpty())
+            names << "features:" + QString::fromLatin1(m_featuresAck.toHex());
+        return names;
+    }
+
+    // Unknown names are skipped, a newer version may have written them
+    static DeviceCapabilities fromStringList(const QStringList &names)
+    {
+        DeviceCapabilities result;
+        const QMetaEnum capabilities = QMetaEnum::fromType<Capability>();
+        for (const QString &name : names)
+        {
+            if (name.startsWith("features:"))
+            {
+                result.m_featuresAck = QByteArray::fromHex(name.mid(9).toLatin1());
+                continue;
+            }
+            bool ok = false;
+            int value = capabilities.keyToValue(name.toLatin1().constData(), &ok);
+            if (ok)
+                result.add(static_cast<Capability>(value));
+        }
+        return result;
+    }
+
+    bool operator==(const DeviceCapabilities &other) const
+    {
+        return m_flags == other.m_flags && m_featuresAck == other.m_featuresAck;
+    }
+    bool operator!=(const DeviceCapabilities &other) const { return !(*this == other); }
+
+private:
+    int m_flags = None;
+    QByteArray m_featuresAck;
+};
@@ -3,9 +3,13 @@
 #include <QObject>
 #include <QByteArray>
 #include <QSettings>
+#include <QTimer>
+#include <QJsonObject>
 #include "battery.hpp"
+#include "devicecapabilities.hpp"
 #include "enums.h"
 #include "eardetection.hpp"
+#include "keyring.hpp"
 
 using namespace AirpodsTrayApp::Enums;
 
@@ -17,31 +21,65 @@ class DeviceInfo : public QObject
     Q_PROPERTY(bool conversationalAwareness READ conversationalAwareness WRITE setConversationalAwareness NOTIFY conversationalAwarenessChanged)
     Q_PROPERTY(bool hearingAidEnabled READ hearingAidEnabled WRITE setHearingAidEnabled NOTIFY hearingAidEnabledChanged)
     Q_PROPERTY(int adaptiveNoiseLevel READ adaptiveNoiseLevel WRITE setAdaptiveNoiseLevel NOTIFY adaptiveNoiseLevelChanged)
+    Q_PROPERTY(int chimeVolume READ chimeVolume WRITE setChimeVolume NOTIFY chimeVolumeChanged)
+    Q_PROPERTY(bool chimeVolumeAvailable READ chimeVolumeAvailable NOTIFY capabilitiesChanged)
     Q_PROPERTY(QString deviceName READ deviceName WRITE setDeviceName NOTIFY deviceNameChanged)
     Q_PROPERTY(Battery *battery READ getBattery CONSTANT)
+    Q_PROPERTY(bool ignoreCase READ ignoreCase NOTIFY ignoreCaseChanged)
     Q_PROPERTY(bool oneBudANCMode READ oneBudANCMode WRITE setOneBudANCMode NOTIFY oneBudANCModeChanged)
+    Q_PROPERTY(bool allowOffOption READ allowOffOption NOTIFY allowOffOptionChanged)
+    Q_PROPERTY(bool callControlsAvailable READ callControlsAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool noiseControlAvailable READ noiseControlAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool conversationalAwarenessAvailable READ conversationalAwarenessAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool hearingAidAvailable READ hearingAidAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool allowOffOptionAvailable READ allowOffOptionAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool oneBudANCAvailable READ oneBudANCAvailable NOTIFY capabilitiesChanged)
+    Q_PROPERTY(bool callControlsFlipped READ callControlsFlipped NOTIFY callControlsChanged)
     Q_PROPERTY(AirPodsModel model READ model WRITE setModel NOTIFY modelChanged)
     Q_PROPERTY(bool adaptiveModeActive READ adaptiveModeActive NOTIFY noiseControlModeChangedInt)
     Q_PROPERTY(QString podIcon READ podIcon NOTIFY modelChanged)
     Q_PROPERTY(QString caseIcon READ caseIcon NOTIFY modelChanged)
+    Q_PROPERTY(bool hasEarTips READ hasEarTips NOTIFY modelChanged)
     Q_PROPERTY(bool leftPodInEar READ isLeftPodInEar NOTIFY primaryChanged)
     Q_PROPERTY(bool rightPodInEar READ isRightPodInEar NOTIFY primaryChanged)
+    Q_PROPERTY(bool leftPodInCase READ isLeftPodInCase NOTIFY primaryChanged)
+    Q_PROPERTY(bool rightPodInCase READ isRightPodInCase NOTIFY primaryChanged)
     Q_PROPERTY(QString bluetoothAddress READ bluetoothAddress WRITE setBluetoothAddress NOTIFY bluetoothAddressChanged)
-    Q_PROPERTY(QString magicAccIRK READ magicAccIRKHex CONSTANT)
-    Q_PROPERTY(QString magicAccEncKey READ magicAccEncKeyHex CONSTANT)
+    Q_PROPERTY(QString magicAccIRK READ magicAccIRKHex NOTIFY magicKeysChanged)
+    Q_PROPERTY(QString magicAccEncKey READ magicAccEncKeyHex NOTIFY magicKeysChanged)
+    Q_PROPERTY(QString keyStorageStatus READ keyStorageStatus NOTIFY keyStorageStatusChanged)
 
 public:
     explicit DeviceInfo(QObject *parent = nullptr) : QObject(parent), m_battery(new Battery(this)), m_earDetection(new EarDetection(this)) {
         connect(getEarDetection(), &EarDetection::statusChanged, this, &DeviceInfo::primaryChanged);
+        m_batteryNotifyTimer.setSingleShot(true);
+        m_batteryNotifyTimer.setInterval(BatteryNotifyIntervalMs);
+        connect(&m_batteryNotifyTimer, &QTimer::timeout, this, &DeviceInfo::notifyBatteryStatus);
+        // The battery status marks buds in the case, so it changes with ear detection too
+        connect(getEarDetection(), &EarDetection::statusChanged, this, [this]()
+                {
+                    if (!batteryStatus().isEmpty())
+                        updateBatteryStatus(); });
     }
 
     QString batteryStatus() const { return m_batteryStatus; }
+    // Battery packets tend to arrive in bursts. The value is current right away, but the change
+    // is signalled at most once per BatteryNotifyIntervalMs, so QML and the tray re-render less.
+    // Clearing it on disconnect is signalled immediately.
     void setBatteryStatus(const QString &status)
     {
-        if (m_batteryStatus != status)
+        if (m_batteryStatus == status)
+            return;
+        m_batteryStatus = status;
+        if (status.isEmpty())
         {
-            m_batteryStatus = status;
-            emit batteryStatusChanged(status);
+            m_batteryNotifyTimer.stop();
+            notifyBatteryStatus();
+        }
+        else if (!m_batteryNotifyTimer.isActive())
+        {
+            notifyBatteryStatus();
+            m_batteryNotifyTimer.start();
         }
     }
 
@@ -88,6 +126,43 @@ public:
         }
     }
 
+    int chimeVolume() const { return m_chimeVolume; }
+    void setChimeVolume(int volume)
+    {
+        if (m_chimeVolume != volume)
+        {
+            m_chimeVolume = volume;
+            emit chimeVolumeChanged(volume);
+        }
+    }
+
+    // Controls are only shown for features the device has reported, see DeviceCapabilities
+    const DeviceCapabilities &capabilities() const { return m_capabilities; }
+    void setCapabilities(const DeviceCapabilities &capabilities)
+    {
+        if (m_capabilities != capabilities)
+        {
+            m_capabilities = capabilities;
+            emit capabilitiesChanged();
+        }
+    }
+    // True when the capability is new, so the caller knows to store it
+    bool addCapability(DeviceCapabilities::Capability capability)
+    {
+        if (!m_capabilities.add(capability))
+            return false;
+        emit capabilitiesChanged();
+        return true;
+    }
+    bool setFeaturesAck(const QByteArray &ack) { return m_capabilities.setFeaturesAck(ack); }
+
+    bool noiseControlAvailable() const { return m_capabilities.has(DeviceCapabilities::NoiseControl); }
+    bool conversationalAwarenessAvailable() const { return m_capabilities.has(DeviceCapabilities::ConversationalAwareness); }
+    bool hearingAidAvailable() const { return m_capabilities.has(DeviceCapabilities::HearingAid); }
+    bool allowOffOptionAvailable() const { return m_capabilities.has(DeviceCapabilities::AllowOffOption); }
+    bool oneBudANCAvailable() const { return m_capabilities.has(DeviceCapabilities::OneBudANC); }
+    bool chimeVolumeAvailable() const { return m_capabilities.has(DeviceCapabilities::ChimeVolume); }
+
     QString deviceName() const { return m_deviceName; }
     void setDeviceName(const QString &name)
     {
@@ -100,6 +175,29 @@ public:
 
     Battery *getBattery() const { return m_battery; }
 
+    bool allowOffOption() const { return m_allowOffOption; }
+    void setAllowOffOption(bool allowed)
+    {
+        if (m_allowOffOption != allowed)
+        {
+            m_allowOffOption = allowed;
+            emit allowOffOptionChanged(allowed);
+        }
+    }
+
+    bool callControlsAvailable() const { return m_capabilities.has(DeviceCapabilities::CallControls); }
+    // Whether the current setting arrived yet, the capability alone comes from an earlier connection
+    bool callControlsReported() const { return m_callControlsFlipped.has_value(); }
+    bool callControlsFlipped() const { return m_callControlsFlipped.value_or(false); }
+    void setCallControlsFlipped(std::optional<bool> flipped)
+    {
+        if (m_callControlsFlipped != flipped)
+        {
+            m_callControlsFlipped = flipped;
+            emit callControlsChanged();
+        }
+    }
+
     bool oneBudANCMode() const { return m_oneBudANCMode; }
     void setOneBudANCMode(bool enabled)
     {
@@ -120,20 +218,72 @@ public:
         }
     }
 
+    // Leaves the case out of the battery status for lost or misreporting third-party cases
+    bool ignoreCase() const { return m_ignoreCase; }
+    void setIgnoreCase(bool ignore)
+    {
+        if (m_ignoreCase != ignore)
+        {
+            m_ignoreCase = ignore;
+            if (!batteryStatus().isEmpty())
+                updateBatteryStatus();
+            emit ignoreCaseChanged(ignore);
+        }
+    }
+
     QByteArray magicAccIRK() const { return m_magicAccIRK; }
-    void setMagicAccIRK(const QByteArray &irk) { m_magicAccIRK = irk; }
+    // Keys arrive from the device, the settings file or, a while after startup, the keyring
+    void setMagicAccIRK(const QByteArray &irk)
+    {
+        if (m_magicAccIRK != irk)
+        {
+            m_magicAccIRK = irk;
+            emit magicKeysChanged();
+        }
+    }
     QString magicAccIRKHex() const { return QString::fromUtf8(m_magicAccIRK.toHex()); }
 
     QByteArray magicAccEncKey() const { return m_magicAccEncKey; }
-    void setMagicAccEncKey(const QByteArray &key) { m_magicAccEncKey = key; }
+    void setMagicAccEncKey(const QByteArray &key)
+    {
+        if (m_magicAccEncKey != key)
+        {
+            m_magicAccEncKey = key;
+            emit magicKeysChanged();
+        }
+    }
     QString magicAccEncKeyHex() const { return QString::fromUtf8(m_magicAccEncKey.toHex()); }
 
+    enum class KeyStorage
+    {
+        None,
+        Keyring,
+        SettingsFile,
+    };
+    QString keyStorageStatus() const
+    {
+        switch (m_keyStorage)
+        {
+        case KeyStorage::Keyring:
+            return tr("Stored in the system keyring");
+        case KeyStorage::SettingsFile:
+            return tr("Stored unencrypted in the settings file (keyring unavailable)");
+        default:
+            return tr("No keys stored");
+        }
+    }
+
     QString modelNumber() const { return m_modelNumber; }
     void setModelNumber(const QString &modelNumber) { m_modelNumber = modelNumber; }
+    QString serialNumber() const { return m_serialNumber; }
+    void setSerialNumber(const QString &serialNumber) { m_serialNumber = serialNumber; }
 
     QString manufacturer() const { return m_manufacturer; }
     void setManufacturer(const QString &manufacturer) { m_manufacturer = manufacturer; }
 
+    QString firmwareVersion() const { return m_firmwareVersion; }
+    void setFirmwareVersion(const QString &version) { m_firmwareVersion = version; }
+
     QString bluetoothAddress() const { return m_bluetoothAddress; }
     void setBluetoothAddress(const QString &address)
     {
@@ -146,6 +296,7 @@ public:
 
     QString podIcon() const { return getModelIcon(model()).first; }
     QString caseIcon() const { return getModelIcon(model()).second; }
+    bool hasEarTips() const { return AirpodsTrayApp::Enums::hasEarTips(model()); }
     bool isLeftPodInEar() const
     {
         if (getBattery()->getPrimaryPod() == Battery::Component::Left) return getEarDetection()->isPrimaryInEar();
@@ -156,6 +307,16 @@ public:
         if (getBattery()->getPrimaryPod() == Battery::Component::Right) return getEarDetection()->isPrimaryInEar();
         else return getEarDetection()->isSecondaryInEar();
     }
+    bool isLeftPodInCase() const
+    {
+        if (getBattery()->getPrimaryPod() == Battery::Component::Left) return getEarDetection()->isPrimaryInCase();
+        else return getEarDetection()->isSecondaryInCase();
+    }
+    bool isRightPodInCase() const
+    {
+        if (getBattery()->getPrimaryPod() == Battery::Component::Right) return getEarDetection()->isPrimaryInCase();
+        else return getEarDetection()->isSecondaryInCase();
+    }
 
     bool adaptiveModeActive() const { return noiseControlMode() == NoiseControlMode::Adaptive; }
 
@@ -171,6 +332,8 @@ public:
         setBluetoothAddress("");
         getEarDetection()->reset();
         setHearingAidEnabled(false);
+        setCallControlsFlipped(std::nullopt);
+        setCapabilities(DeviceCapabilities());
     }
 
     void saveToSettings(QSettings &settings)
@@ -178,18 +341,62 @@ public:
         settings.beginGroup("DeviceInfo");
         settings.setValue("deviceName", deviceName());
         settings.setValue("model", static_cast<int>(model()));
-        settings.setValue("magicAccIRK", magicAccIRK());
-        settings.setValue("magicAccEncKey", magicAccEncKey());
         settings.setValue("hearingAidEnabled", hearingAidEnabled());
+        settings.setValue("serialNumber", serialNumber());
         settings.endGroup();
+        saveKeys(settings);
     }
-    void loadFromSettings(const QSettings &settings)
+    void loadFromSettings(QSettings &settings)
     {
         setDeviceName(settings.value("DeviceInfo/deviceName", "").toString());
         setModel(static_cast<AirPodsModel>(settings.value("DeviceInfo/model", (int)(AirPodsModel::Unknown)).toInt()));
-        setMagicAccIRK(settings.value("DeviceInfo/magicAccIRK", QByteArray()).toByteArray());
-        setMagicAccEncKey(settings.value("DeviceInfo/magicAccEncKey", QByteArray()).toByteArray());
         setHearingAidEnabled(settings.value("DeviceInfo/hearingAidEnabled", false).toBool());
+        setSerialNumber(settings.value("DeviceInfo/serialNumber").toString());
+        loadKeys(settings);
+    }
+
+    // Serial and keys are optional so an export can be shared without identifying the device
+    QJsonObject toJson(bool includeSerial, bool includeKeys) const
+    {
+        QJsonObject json;
+        json["deviceName"] = deviceName();
+        json["model"] = static_cast<int>(model());
+        json["hearingAidEnabled"] = hearingAidEnabled();
+        if (includeSerial && !serialNumber().isEmpty())
+            json["serialNumber"] = serialNumber();
+        if (includeKeys)
+        {
+            json["magicAccIRK"] = magicAccIRKHex();
+            json["magicAccEncKey"] = magicAccEncKeyHex();
+        }
+        return json;
+    }
+    // The entry GET /devices, GetDevices() and librepods-ctl list-devices report
+    QJsonObject apiJson(bool connected) const
+    {
+        static const QStringList modes = {"off", "anc", "transparency", "adaptive"};
+        QJsonObject device = toJson(false, false);
+        device["address"] = bluetoothAddress();
+        device["connected"] = connected;
+        device["listening_mode"] = modes.value(noiseControlModeInt());
+        device["conversational_awareness"] = conversationalAwareness();
+        device["battery"] = QJsonObject{{"left", getBattery()->getState(Battery::Component::Left).level},
+                                        {"right", getBattery()->getState(Battery::Component::Right).level},
+                                        {"case", getBattery()->getState(Battery::Component::Case).level}};
+        return device;
+    }
+
+    void fromJson(const QJsonObject &json)
+    {
+        setDeviceName(json.value("deviceName").toString(deviceName()));
+        setModel(static_cast<AirPodsModel>(json.value("model").toInt(static_cast<int>(model()))));
+        setHearingAidEnabled(json.value("hearingAidEnabled").toBool(hearingAidEnabled()));
+        setSerialNumber(json.value("serialNumber").toString(serialNumber()));
+        if (json.contains("magicAccIRK") && json.contains("magicAccEncKey"))
+        {
+            setMagicAccIRK(QByteArray::fromHex(json.value("magicAccIRK").toString().toUtf8()));
+            setMagicAccEncKey(QByteArray::fromHex(json.value("magicAccEncKey").toString().toUtf8()));
+        }
     }
 
     void updateBatteryStatus()
@@ -201,37 +408,161 @@ public:
             int headsetLevel = getBattery()->getState(Battery::Component::Headset).level;
             setBatteryStatus(QString("Headset: %1%").arg(headsetLevel));
         } else {
-            setBatteryStatus(QString("Left: %1%, Right: %2%, Case: %3%").arg(leftLevel).arg(rightLevel).arg(caseLevel));
+            // Only one bud in use gets the other one marked, so the tray can show the active one
+            bool oneBudInUse = isLeftPodInCase() != isRightPodInCase();
+            QString leftSuffix = oneBudInUse && isLeftPodInCase() ? " (In case)" : "";
+            QString rightSuffix = oneBudInUse && isRightPodInCase() ? " (In case)" : "";
+            QString status = QString("Left: %1%%2, Right: %3%%4").arg(leftLevel).arg(leftSuffix).arg(rightLevel).arg(rightSuffix);
+            if (!m_ignoreCase)
+                status += QString(", Case: %1%").arg(caseLevel);
+            setBatteryStatus(status);
+        }
+    }
+
+private:
+    void notifyBatteryStatus()
+    {
+        if (m_notifiedBatteryStatus == m_batteryStatus)
+            return;
+        m_notifiedBatteryStatus = m_batteryStatus;
+        emit batteryStatusChanged(m_batteryStatus);
+    }
+
+    void setKeyStorage(KeyStorage storage)
+    {
+        if (m_keyStorage != storage)
+        {
+            m_keyStorage = storage;
+            emit keyStorageStatusChanged();
+        }
+    }
+
+    // Keys go to the keyring when possible, the settings file is only a fallback. Nothing is
+    // written unless the keys differ from what was last saved or loaded.
+    void saveKeys(QSettings &settings)
+    {
+        if (magicAccIRK().isEmpty() || magicAccEncKey().isEmpty())
+        {
+            return;
+        }
+        if (magicAccIRK() == m_savedIRK && magicAccEncKey() == m_savedEncKey)
+        {
+            return;
         }
+        m_savedIRK = magicAccIRK();
+        m_savedEncKey = magicAccEncKey();
+
+        QSettings *file = &settings;
+        QByteArray irk = m_savedIRK, encKey = m_savedEncKey;
+        auto fallback = [this, file, irk, encKey]() {
+            file->setValue("DeviceInfo/magicAccIRK", irk);
+            file->setValue("DeviceInfo/magicAccEncKey", encKey);
+            setKeyStorage(KeyStorage::SettingsFile);
+        };
+        Keyring::store("magicAccIRK", irk, this, [this, file, encKey, fallback](bool stored) {
+            if (!stored)
+            {
+                fallback();
+                return;
+            }
+            Keyring::store("magicAccEncKey", encKey, this, [this, file, fallback](bool stored) {
+                if (!stored)
+                {
+                    fallback();
+                    return;
+                }
+                file->remove("DeviceInfo/magicAccIRK");
+                file->remove("DeviceInfo/magicAccEncKey");
+                setKeyStorage(KeyStorage::Keyring);
+                // Migration is done, the keys now shown come from the keyring
+                emit magicKeysChanged();
+            });
+        });
+    }
+
+    // The settings file is read right away, the keyring answers later
+    void loadKeys(QSettings &settings)
+    {
+        setMagicAccIRK(settings.value("DeviceInfo/magicAccIRK", QByteArray()).toByteArray());
+        setMagicAccEncKey(settings.value("DeviceInfo/magicAccEncKey", QByteArray()).toByteArray());
+        if (!magicAccIRK().isEmpty() && !magicAccEncKey().isEmpty())
+        {
+            // Keys from older versions are still in plaintext, move them over
+            LOG_INFO("Migrating Magic Cloud Keys from the settings file to the keyring");
+            setKeyStorage(KeyStorage::SettingsFile);
+            saveKeys(settings);
+            return;
+        }
+
+        setKeyStorage(KeyStorage::None);
+        Keyring::lookup("magicAccIRK", this, [this](const QByteArray &irk) {
+            if (irk.isEmpty())
+            {
+                return;
+            }
+            Keyring::lookup("magicAccEncKey", this, [this, irk](const QByteArray &encKey) {
+                // Keys the device sent in the meantime are newer
+                if (encKey.isEmpty() || !magicAccIRK().isEmpty())
+                {
+                    return;
+                }
+                setMagicAccIRK(irk);
+                setMagicAccEncKey(encKey);
+                m_savedIRK = irk;
+                m_savedEncKey = encKey;
+                setKeyStorage(KeyStorage::Keyring);
+            });
+        });
     }
 
 signals:
     void batteryStatusChanged(const QString &status);
+    void ignoreCaseChanged(bool ignore);
     void noiseControlModeChanged(NoiseControlMode mode);
     void noiseControlModeChangedInt(int mode);
     void conversationalAwarenessChanged(bool enabled);
     void hearingAidEnabledChanged(bool enabled);
     void adaptiveNoiseLevelChanged(int level);
+    void chimeVolumeChanged(int volume);
     void deviceNameChanged(const QString &name);
     void primaryChanged();
     void oneBudANCModeChanged(bool enabled);
+    void allowOffOptionChanged(bool allowed);
+    void callControlsChanged();
+    void capabilitiesChanged();
     void modelChanged();
     void bluetoothAddressChanged(const QString &address);
+    void keyStorageStatusChanged();
+    void magicKeysChanged();
 
 private:
+    static constexpr int BatteryNotifyIntervalMs = 250;
+
     QString m_batteryStatus;
+    QString m_notifiedBatteryStatus;
+    QTimer m_batteryNotifyTimer;
     NoiseControlMode m_noiseControlMode = NoiseControlMode::Transparency;
     bool m_conversationalAwareness = false;
     bool m_hearingAidEnabled = false;
     int m_adaptiveNoiseLevel = 50;
+    int m_chimeVolume = 75;
     QString m_deviceName;
     Battery *m_battery;
     QByteArray m_magicAccIRK;
     QByteArray m_magicAccEncKey;
+    QByteArray m_savedIRK;
+    QByteArray m_savedEncKey;
+    KeyStorage m_keyStorage = KeyStorage::None;
     bool m_oneBudANCMode = false;
+    bool m_ignoreCase = false;
+    bool m_allowOffOption = true;
+    std::optional<bool> m_callControlsFlipped;
+    DeviceCapabilities m_capabilities;
     AirPodsModel m_model = AirPodsModel::Unknown;
     QString m_modelNumber;
+    QString m_serialNumber;
     QString m_manufacturer;
+    QString m_firmwareVersion;
     QString m_bluetoothAddress;
     EarDetection *m_earDetection;
 };
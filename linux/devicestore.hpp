@@ -0,0 +1,152 @@
+#pragma once
+
+#include <QCoreApplication>
+#include <QJsonArray>
+#include <QJsonObject>
+#include <QJsonValue>
+#include <QMap>
+#include <QRegularExpression>
+#include <QSettings>
+#include <QStringList>
+
+// Per-device settings live under <group>/<address with underscores>. The store gathers them by
+// address for the devices export and writes them back on import, so they survive a reinstall.
+// Serials and keys only end up in an export when the caller puts them in currentInfo.
+class DeviceStore
+{
+public:
+    static QStringList groups()
+    {
+        return {"ignoreCase", "autoConnect", "lostMode", "defaultOutput", "volume", "capabilities", "model"};
+    }
+
+    static QString key(const QString &group, const QString &address)
+    {
+        return group + "/" + QString(address).replace(":", "_");
+    }
+
+    static bool isAddress(const QString &address)
+    {
+        static const QRegularExpression pattern("^([0-9A-Fa-f]{2}:){5}[0-9A-Fa-f]{2}$");
+        return pattern.match(address).hasMatch();
+    }
+
+    // Every address with at least one setting, keys that aren't addresses (autoConnect/priority) are left out
+    static QStringList addresses(QSettings &settings)
+    {
+        QStringList result;
+        for (const QString &group : groups())
+        {
+            settings.beginGroup(group);
+            const QStringList keys = settings.childKeys();
+            settings.endGroup();
+            for (const QString &key : keys)
+            {
+                QString address = QString(key).replace("_", ":");
+                if (isAddress(address) && !result.contains(address))
+                    result << address;
+            }
+        }
+        return result;
+    }
+
+    static QJsonObject settingsFor(QSettings &settings, const QString &address)
+    {
+        QJsonObject json;
+        for (const QString &group : groups())
+        {
+            QVariant value = settings.value(key(group, address));
+            if (value.isValid())
+                json[group] = QJsonValue::fromVariant(value);
+        }
+        return json;
+    }
+
+    // Groups missing from json keep their current value, unknown ones are ignored
+    static void applySettings(QSettings &settings, const QString &address, const QJsonObject &json)
+    {
+        for (const QString &group : groups())
+        {
+            if (json.contains(group))
+                settings.setValue(key(group, address), json.value(group).toVariant());
+        }
+    }
+
+    // Every device with settings or a name in pairedNames, the current one first. Name, model,
+    // serial and keys are only known for the current device and come in currentInfo, the others
+    // carry their BlueZ name.
+    static QJsonObject exportJson(QSettings &settings, const QMap<QString, QString> &pairedNames,
+                                  const QString &current, const QJsonObject &currentInfo)
+    {
+        QStringList known = addresses(settings);
+        for (auto it = pairedNames.constBegin(); it != pairedNames.constEnd(); ++it)
+        {
+            if (!known.contains(it.key()))
+                known << it.key();
+        }
+        if (!current.isEmpty())
+        {
+            known.removeAll(current);
+            known.prepend(current);
+        }
+
+        QJsonArray devices;
+        for (const QString &address : std::as_const(known))
+        {
+            QJsonObject device = address == current ? currentInfo : QJsonObject{{"deviceName", pairedNames.value(address)}};
+            device["address"] = address;
+            device["settings"] = settingsFor(settings, address);
+            devices.append(device);
+        }
+
+        QJsonObject root;
+        root["version"] = 2;
+        root["devices"] = devices;
+        root["autoConnectPriority"] = QJsonArray::fromStringList(settings.value("autoConnect/priority").toStringList());
+        return root;
+    }
+
+    struct ImportResult
+    {
+        int imported = 0;
+        // Names of the entries without a valid address, or their position when unnamed
+        QStringList skipped;
+        // Entry carrying the name, model and keys for the current device, empty when there was none
+        QString infoAddress;
+        QJsonObject info;
+    };
+
+    // Writes the settings of every entry and the startup order. On a new install, without a
+    // current device, the first entry with a model becomes the current one.
+    static ImportResult importJson(QSettings &settings, const QJsonObject &root, const QString &current)
+    {
+        ImportResult result;
+        const QJsonArray devices = root.value("devices").toArray();
+        for (qsizetype i = 0; i < devices.size(); ++i)
+        {
+            QJsonObject device = devices.at(i).toObject();
+            QString address = device.value("address").toString();
+            // Version 1 files held a single entry without an address, for the device last connected
+            if (address.isEmpty() && root.value("version").toInt() < 2)
+                address = current;
+            if (!isAddress(address))
+            {
+                QString name = device.value("deviceName").toString();
+                result.skipped << (name.isEmpty() ? QCoreApplication::translate("DeviceStore", "entry %1").arg(i + 1) : name);
+                continue;
+            }
+
+            applySettings(settings, address, device.value("settings").toObject());
+            if (result.infoAddress.isEmpty() && device.contains("model") && (current.isEmpty() || address == current))
+            {
+                result.infoAddress = address;
+                result.info = device;
+            }
+            ++result.imported;
+        }
+
+        if (root.contains("autoConnectPriority"))
+            settings.setValue("autoConnect/priority", root.value("autoConnectPriority").toVariant().toStringList());
+        return result;
+    }
+};
@@ -0,0 +1,76 @@
+#pragma once
+
+#include <QtGlobal>
+
+#include <optional>
+
+#include "battery.hpp"
+
+// Compares how fast the two buds discharge while both are worn, a bud that keeps
+// draining much faster than the other usually has a worn out battery
+class DrainMonitor
+{
+public:
+    struct Divergence
+    {
+        Battery::Component faster;
+        double ratio;
+    };
+
+    static constexpr qint64 MinDurationMs = 30 * 60 * 1000;
+    static constexpr int MinDrop = 10;       // Percent the faster bud has to lose before comparing
+    static constexpr double MinRatio = 2.0;
+
+    // Call for every battery update while both buds are in ear and discharging. Reports a
+    // divergence once per session, until reset() is called.
+    std::optional<Divergence> addSample(qint64 timeMs, int leftLevel, int rightLevel)
+    {
+        if (leftLevel <= 0 || rightLevel <= 0)
+            return std::nullopt;
+
+        // A level going up means the bud was charged in between, start over
+        if (!m_started || leftLevel > m_lastLeft || rightLevel > m_lastRight)
+        {
+            m_started = true;
+            m_startTime = timeMs;
+            m_startLeft = leftLevel;
+            m_startRight = rightLevel;
+        }
+        m_lastLeft = leftLevel;
+        m_lastRight = rightLevel;
+
+        if (m_reported || timeMs - m_startTime < MinDurationMs)
+            return std::nullopt;
+
+        int leftDrop = m_startLeft - leftLevel;
+        int rightDrop = m_startRight - rightLevel;
+        int fasterDrop = qMax(leftDrop, rightDrop);
+        if (fasterDrop < MinDrop)
+            return std::nullopt;
+
+        double ratio = static_cast<double>(fasterDrop) / qMax(qMin(leftDrop, rightDrop), 1);
+        if (ratio < MinRatio)
+            return std::nullopt;
+
+        m_reported = true;
+        return Divergence{leftDrop > rightDrop ? Battery::Component::Left : Battery::Component::Right, ratio};
+    }
+
+    // Drops the current measurement, e.g. when a bud is taken out or put on the charger
+    void interrupt() { m_started = false; }
+
+    void reset()
+    {
+        m_started = false;
+        m_reported = false;
+    }
+
+private:
+    bool m_started = false;
+    bool m_reported = false;
+    qint64 m_startTime = 0;
+    int m_startLeft = 0;
+    int m_startRight = 0;
+    int m_lastLeft = 0;
+    int m_lastRight = 0;
+};
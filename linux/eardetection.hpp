@@ -33,7 +33,8 @@ public:
 
     bool parseData(const QByteArray &data)
     {
-        if (data.size() < 2)
+        // Status bytes are at index 6 and 7, right after the header
+        if (data.size() < 8)
         {
             return false;
         }
@@ -57,6 +58,8 @@ public:
 
     bool isPrimaryInEar() const { return primaryStatus == EarDetectionStatus::InEar; }
     bool isSecondaryInEar() const { return secondaryStatus == EarDetectionStatus::InEar; }
+    bool isPrimaryInCase() const { return primaryStatus == EarDetectionStatus::InCase; }
+    bool isSecondaryInCase() const { return secondaryStatus == EarDetectionStatus::InCase; }
     bool oneOrMorePodsInCase() const { return primaryStatus == EarDetectionStatus::InCase || secondaryStatus == EarDetectionStatus::InCase; }
     bool oneOrMorePodsInEar() const { return isPrimaryInEar() || isSecondaryInEar(); }
 
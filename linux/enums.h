@@ -91,6 +91,18 @@ namespace AirpodsTrayApp
             }
         }
 
+        // Models that come with silicone ear tips, the ones a fit check applies to
+        inline bool hasEarTips(AirPodsModel model) {
+            switch (model) {
+                case AirPodsModel::AirPodsPro:
+                case AirPodsModel::AirPodsPro2Lightning:
+                case AirPodsModel::AirPodsPro2USBC:
+                    return true;
+                default:
+                    return false;
+            }
+        }
+
         // TODO: Only used for parseEncryptedPacket for battery status. Is it possible to determine this
         // from the data in the packet rather than by model? i.e number of batteries
         inline bool isModelHeadset(AirPodsModel model) {
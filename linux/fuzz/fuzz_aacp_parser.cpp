@@ -0,0 +1,43 @@
+#include <QByteArray>
+#include <QLoggingCategory>
+
+#include <cstddef>
+#include <cstdint>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+#include "eardetection.hpp"
+
+Q_LOGGING_CATEGORY(librepods, "librepods")
+
+extern "C" int LLVMFuzzerInitialize(int *, char ***)
+{
+    // The parsers log every packet, which only slows the fuzzer down
+    QLoggingCategory::setFilterRules("librepods.*=false");
+    return 0;
+}
+
+// Feeds arbitrary bytes to every parser that handles data coming from the AirPods
+extern "C" int LLVMFuzzerTestOneInput(const uint8_t *data, size_t size)
+{
+    const QByteArray frame = QByteArray::fromRawData(reinterpret_cast<const char *>(data), static_cast<qsizetype>(size));
+
+    Battery battery;
+    battery.parsePacket(frame);
+    if (size > 0)
+    {
+        battery.parseEncryptedPacket(frame, data[0] & 0x01, data[0] & 0x02, data[0] & 0x04);
+    }
+
+    EarDetection earDetection;
+    earDetection.parseData(frame);
+
+    AirPodsPackets::NoiseControl::parseMode(frame);
+    AirPodsPackets::ConversationalAwareness::parseState(frame);
+    AirPodsPackets::HearingAid::parseState(frame);
+    AirPodsPackets::OneBudANCMode::parseState(frame);
+    AirPodsPackets::AllowOffOption::parseState(frame);
+    AirPodsPackets::MagicPairing::parseMagicCloudKeysPacket(frame);
+    AirPodsPackets::Metadata::parse(frame);
+    return 0;
+}
@@ -0,0 +1,88 @@
+#pragma once
+
+#include <QObject>
+#include <QString>
+#include <QTimer>
+
+// Power state of AirPods Max, estimated from ear detection. No AACP frame reports it and the
+// low power timing can't be set, but Apple documents that the headphones go to low power after
+// five minutes off the head. Lying still and the Smart Case can't be told apart from here.
+class HeadsetPower : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(State state READ state NOTIFY stateChanged)
+    Q_PROPERTY(QString label READ label NOTIFY stateChanged)
+
+public:
+    enum State
+    {
+        // Not a headset or not connected
+        Unknown,
+        Awake,
+        OffHead,
+        LowPower,
+    };
+    Q_ENUM(State)
+
+    static constexpr int LowPowerDelayMs = 5 * 60 * 1000;
+
+    explicit HeadsetPower(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_lowPowerTimer.setSingleShot(true);
+        m_lowPowerTimer.setInterval(LowPowerDelayMs);
+        connect(&m_lowPowerTimer, &QTimer::timeout, this, [this]()
+                { setState(LowPower); });
+    }
+
+    static State stateFor(bool headset, bool connected, bool onHead, bool offHeadExpired)
+    {
+        if (!headset || !connected)
+            return Unknown;
+        if (onHead)
+            return Awake;
+        return offHeadExpired ? LowPower : OffHead;
+    }
+
+    State state() const { return m_state; }
+
+    // Empty while the state is unknown
+    QString label() const
+    {
+        switch (m_state)
+        {
+        case Awake:
+            return tr("Awake");
+        case OffHead:
+            return tr("Awake, low power after %1 minutes off your head").arg(LowPowerDelayMs / 60000);
+        case LowPower:
+            return tr("Low power (estimated)");
+        default:
+            return QString();
+        }
+    }
+
+    void update(bool headset, bool connected, bool onHead)
+    {
+        State next = stateFor(headset, connected, onHead, m_state == LowPower);
+        if (next == OffHead && !m_lowPowerTimer.isActive())
+            m_lowPowerTimer.start();
+        else if (next != OffHead)
+            m_lowPowerTimer.stop();
+        setState(next);
+    }
+
+signals:
+    void stateChanged();
+
+private:
+    void setState(State state)
+    {
+        if (state == m_state)
+            return;
+        m_state = state;
+        emit stateChanged();
+    }
+
+    QTimer m_lowPowerTimer;
+    State m_state = Unknown;
+};
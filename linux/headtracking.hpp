@@ -0,0 +1,91 @@
+#pragma once
+
+#include <QList>
+#include <QObject>
+
+#include "airpods_packets.h"
+
+// Turns raw head tracking samples into pitch and yaw in degrees. The first samples
+// after starting are averaged as the neutral position, so hold still for a moment.
+class HeadTracking : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(bool active READ active NOTIFY activeChanged)
+    Q_PROPERTY(bool calibrated READ calibrated NOTIFY orientationChanged)
+    Q_PROPERTY(double pitch READ pitch NOTIFY orientationChanged)
+    Q_PROPERTY(double yaw READ yaw NOTIFY orientationChanged)
+
+public:
+    explicit HeadTracking(QObject *parent = nullptr) : QObject(parent) {}
+
+    bool active() const { return m_active; }
+    bool calibrated() const { return m_calibrated; }
+    double pitch() const { return m_pitch; }
+    double yaw() const { return m_yaw; }
+
+    void setActive(bool active)
+    {
+        if (m_active == active)
+            return;
+        m_active = active;
+        reset();
+        emit activeChanged(active);
+    }
+
+    void reset()
+    {
+        m_calibrationSamples.clear();
+        m_calibrated = false;
+        m_pitch = 0;
+        m_yaw = 0;
+        emit orientationChanged();
+    }
+
+    void process(const AirPodsPackets::HeadTracking::Sample &sample)
+    {
+        if (!m_calibrated)
+        {
+            m_calibrationSamples.append(sample);
+            if (m_calibrationSamples.size() >= CalibrationSampleCount)
+                calibrate();
+            return;
+        }
+
+        double o2 = sample.o2 + OrientationOffset - m_o2Neutral;
+        double o3 = sample.o3 + OrientationOffset - m_o3Neutral;
+        m_pitch = (o2 + o3) / 2 / 32000 * 180;
+        m_yaw = (o2 - o3) / 2 / 32000 * 180;
+        emit orientationChanged();
+    }
+
+signals:
+    void activeChanged(bool active);
+    void orientationChanged();
+
+private:
+    static constexpr int CalibrationSampleCount = 10;
+    static constexpr int OrientationOffset = 5500;
+
+    void calibrate()
+    {
+        double o2Sum = 0;
+        double o3Sum = 0;
+        for (const auto &sample : m_calibrationSamples)
+        {
+            o2Sum += sample.o2 + OrientationOffset;
+            o3Sum += sample.o3 + OrientationOffset;
+        }
+        m_o2Neutral = o2Sum / m_calibrationSamples.size();
+        m_o3Neutral = o3Sum / m_calibrationSamples.size();
+        m_calibrated = true;
+        emit orientationChanged();
+    }
+
+    QList<AirPodsPackets::HeadTracking::Sample> m_calibrationSamples;
+    bool m_active = false;
+    bool m_calibrated = false;
+    double m_o2Neutral = 0;
+    double m_o3Neutral = 0;
+    double m_pitch = 0;
+    double m_yaw = 0;
+};
@@ -0,0 +1,262 @@
+#pragma once
+
+#include <QHostAddress>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QObject>
+#include <QRandomGenerator>
+#include <QRegularExpression>
+#include <QSettings>
+#include <QTcpServer>
+#include <QTcpSocket>
+
+#include <functional>
+
+#include "keyring.hpp"
+#include "logger.h"
+
+// Small HTTP API on localhost for tools that can't use the local socket or D-Bus.
+// Every request needs an "Authorization: Bearer <token>" header; the token is
+// generated on first use and kept in the keyring. CORS preflights are answered without it
+// so browser dashboards work; POSTs get 403 in read-only mode.
+//   GET  /devices                          -> list of devices
+//   POST /devices/{mac}/listening_mode     {"mode": "off" | "anc" | "transparency" | "adaptive"}
+class HttpApi : public QObject
+{
+    Q_OBJECT
+
+public:
+    using DevicesProvider = std::function<QJsonArray()>;
+
+    explicit HttpApi(DevicesProvider devices, QObject *parent = nullptr) : QObject(parent), m_devices(std::move(devices))
+    {
+        connect(&m_server, &QTcpServer::newConnection, this, &HttpApi::acceptConnections);
+    }
+
+    void setReadOnly(bool readOnly) { m_readOnly = readOnly; }
+
+    bool start(QSettings &settings, quint16 port)
+    {
+        loadToken(settings);
+        if (!m_server.listen(QHostAddress::LocalHost, port))
+        {
+            LOG_ERROR("Failed to start the HTTP API on port " << port << ": " << m_server.errorString());
+            return false;
+        }
+        LOG_INFO("HTTP API listening on http://127.0.0.1:" << port);
+        return true;
+    }
+
+signals:
+    void commandReceived(const QString &source, const QJsonObject &command);
+
+private:
+    static constexpr qsizetype MaxRequestSize = 16 * 1024;
+
+    // The keyring is asked in the background, requests get 503 until the token is known. The
+    // token is only written when it's new or still has to move out of the settings file.
+    void loadToken(QSettings &settings)
+    {
+        QSettings *file = &settings;
+        QByteArray fileToken = QByteArray::fromHex(settings.value("api/token").toByteArray());
+        Keyring::lookup("api-token", this, [this, file, fileToken](const QByteArray &stored) {
+            if (!stored.isEmpty())
+            {
+                m_token = QString::fromLatin1(stored.toHex());
+                file->remove("api/token");
+                return;
+            }
+
+            QByteArray token = fileToken;
+            if (token.isEmpty())
+            {
+                token.resize(32);
+                QRandomGenerator::system()->fillRange(reinterpret_cast<quint32 *>(token.data()), token.size() / 4);
+            }
+            m_token = QString::fromLatin1(token.toHex());
+
+            Keyring::store("api-token", token, this, [file, token](bool stored) {
+                if (stored)
+                {
+                    file->remove("api/token");
+                    LOG_INFO("HTTP API token is stored in the keyring (secret-tool lookup application librepods key api-token)");
+                }
+                else
+                {
+                    LOG_WARN("Keyring unavailable, keeping the HTTP API token in the settings file");
+                    file->setValue("api/token", token.toHex());
+                }
+            });
+        });
+    }
+
+    void acceptConnections()
+    {
+        while (QTcpSocket *client = m_server.nextPendingConnection())
+        {
+            connect(client, &QTcpSocket::disconnected, client, &QObject::deleteLater);
+            connect(client, &QObject::destroyed, this, [this, client]() { m_buffers.remove(client); });
+            connect(client, &QTcpSocket::readyRead, this, [this, client]() { readRequest(client); });
+        }
+    }
+
+    void readRequest(QTcpSocket *client)
+    {
+        QByteArray &buffer = m_buffers[client];
+        buffer += client->readAll();
+        if (buffer.size() > MaxRequestSize)
+        {
+            finish(client, 413, {{"error", "request too large"}});
+            return;
+        }
+
+        qsizetype headerEnd = buffer.indexOf("\r\n\r\n");
+        if (headerEnd < 0)
+            return;
+
+        const QList<QByteArray> lines = buffer.left(headerEnd).split('\n');
+        const QList<QByteArray> requestLine = lines.first().trimmed().split(' ');
+        qsizetype contentLength = 0;
+        QByteArray authorization;
+        for (qsizetype i = 1; i < lines.size(); ++i)
+        {
+            qsizetype colon = lines[i].indexOf(':');
+            QByteArray name = lines[i].left(colon).trimmed().toLower();
+            QByteArray value = lines[i].mid(colon + 1).trimmed();
+            if (name == "content-length")
+                contentLength = value.toLongLong();
+            else if (name == "authorization")
+                authorization = value;
+        }
+
+        QByteArray body = buffer.mid(headerEnd + 4);
+        if (body.size() < contentLength)
+            return; // Wait for the rest of the body
+
+        if (requestLine.size() < 2)
+        {
+            finish(client, 400, {{"error", "malformed request"}});
+            return;
+        }
+        if (m_token.isEmpty())
+        {
+            finish(client, 503, {{"error", "starting up, try again"}});
+            return;
+        }
+        if (requestLine[0] == "OPTIONS")
+        {
+            finish(client, 204, QJsonValue());
+            return;
+        }
+        if (!sameToken(authorization, "Bearer " + m_token.toLatin1()))
+        {
+            finish(client, 401, {{"error", "missing or invalid token"}});
+            return;
+        }
+        handle(client, requestLine[0], QString::fromUtf8(requestLine[1]), body.left(contentLength));
+    }
+
+    void handle(QTcpSocket *client, const QByteArray &method, const QString &path, const QByteArray &body)
+    {
+        static const QRegularExpression listeningModePath("^/devices/([0-9A-Fa-f:_]{17})/listening_mode$");
+
+        if (path == "/devices")
+        {
+            if (method != "GET")
+            {
+                finish(client, 405, {{"error", "method not allowed"}});
+                return;
+            }
+            finish(client, 200, m_devices());
+            return;
+        }
+
+        QRegularExpressionMatch match = listeningModePath.match(path);
+        if (!match.hasMatch())
+        {
+            finish(client, 404, {{"error", "not found"}});
+            return;
+        }
+        if (method != "POST")
+        {
+            finish(client, 405, {{"error", "method not allowed"}});
+            return;
+        }
+        if (m_readOnly)
+        {
+            finish(client, 403, {{"error", "LibrePods is in read-only mode"}});
+            return;
+        }
+
+        QString address = match.captured(1).replace('_', ':').toUpper();
+        QJsonObject device;
+        for (const QJsonValue &value : m_devices())
+        {
+            if (value.toObject().value("address").toString().toUpper() == address)
+                device = value.toObject();
+        }
+        if (device.isEmpty())
+        {
+            finish(client, 404, {{"error", "unknown device"}});
+            return;
+        }
+        if (!device.value("connected").toBool())
+        {
+            finish(client, 409, {{"error", "device is not connected"}});
+            return;
+        }
+
+        static const QStringList modes = {"off", "anc", "transparency", "adaptive"};
+        QString mode = QJsonDocument::fromJson(body).object().value("mode").toString();
+        if (!modes.contains(mode))
+        {
+            finish(client, 400, {{"error", "mode must be one of off, anc, transparency, adaptive"}});
+            return;
+        }
+
+        emit commandReceived("http", {{"command", "setNoiseControlMode"}, {"mode", modes.indexOf(mode)}, {"address", address}});
+        finish(client, 202, {{"address", address}, {"listening_mode", mode}});
+    }
+
+    void finish(QTcpSocket *client, int status, const QJsonValue &payload)
+    {
+        static const QHash<int, QByteArray> reasons = {
+            {200, "OK"}, {202, "Accepted"}, {204, "No Content"}, {400, "Bad Request"}, {401, "Unauthorized"},
+            {403, "Forbidden"}, {404, "Not Found"}, {405, "Method Not Allowed"}, {409, "Conflict"},
+            {413, "Payload Too Large"}, {503, "Service Unavailable"}};
+
+        QByteArray body;
+        if (payload.isArray())
+            body = QJsonDocument(payload.toArray()).toJson(QJsonDocument::Compact);
+        else if (payload.isObject())
+            body = QJsonDocument(payload.toObject()).toJson(QJsonDocument::Compact);
+        client->write("HTTP/1.1 " + QByteArray::number(status) + " " + reasons.value(status) + "\r\n"
+                      "Content-Type: application/json\r\n"
+                      "Access-Control-Allow-Origin: *\r\n"
+                      "Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n"
+                      "Access-Control-Allow-Headers: Authorization, Content-Type\r\n"
+                      "Access-Control-Max-Age: 600\r\n"
+                      "Content-Length: " + QByteArray::number(body.size()) + "\r\n"
+                      "Connection: close\r\n\r\n" + body);
+        m_buffers.remove(client);
+        client->disconnectFromHost();
+    }
+
+    // Constant time, so response times don't give the token away byte by byte
+    static bool sameToken(const QByteArray &given, const QByteArray &expected)
+    {
+        if (given.size() != expected.size())
+            return false;
+        char difference = 0;
+        for (qsizetype i = 0; i < given.size(); ++i)
+            difference |= given[i] ^ expected[i];
+        return difference == 0;
+    }
+
+    QTcpServer m_server;
+    DevicesProvider m_devices;
+    QString m_token;
+    bool m_readOnly = false;
+    QHash<QTcpSocket *, QByteArray> m_buffers;
+};
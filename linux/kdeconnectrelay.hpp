@@ -0,0 +1,144 @@
+#pragma once
+
+#include <QDBusConnection>
+#include <QDBusContext>
+#include <QDBusMessage>
+#include <QDBusPendingCallWatcher>
+#include <QObject>
+#include <QRegularExpression>
+#include <QStringList>
+#include <QVariantMap>
+
+#include <functional>
+
+#include "logger.h"
+
+// AirPods state from the Android app while the buds are with the phone. KDE Connect mirrors the
+// app's ongoing status notification (name as title, "L: 80% R: ⚡ 75% Case: 50%" as text) onto the
+// session bus, so nothing has to be added to KDE Connect on either side. The notification carries
+// no listening mode, only battery levels make it across.
+class KdeConnectRelay : public QObject, protected QDBusContext
+{
+    Q_OBJECT
+
+public:
+    explicit KdeConnectRelay(QObject *parent = nullptr) : QObject(parent)
+    {
+        QDBusConnection bus = QDBusConnection::sessionBus();
+        // Empty path matches the notifications plugin of every paired device
+        bus.connect(Service, QString(), NotificationsInterface, "notificationPosted", this, SLOT(onNotificationChanged(QString)));
+        bus.connect(Service, QString(), NotificationsInterface, "notificationUpdated", this, SLOT(onNotificationChanged(QString)));
+        bus.connect(Service, QString(), NotificationsInterface, "notificationRemoved", this, SLOT(onNotificationRemoved(QString)));
+        bus.connect(Service, QString(), NotificationsInterface, "allNotificationsRemoved", this, SLOT(onAllNotificationsRemoved()));
+
+        QDBusMessage devices = QDBusMessage::createMethodCall(Service, "/modules/kdeconnect", "org.kde.kdeconnect.daemon", "devices");
+        devices << true << true;
+        call(devices, [this](const QDBusMessage &reply) {
+            for (const QString &device : reply.arguments().value(0).toStringList())
+                readActive(notificationsPath(device));
+        });
+    }
+
+    // Empty while no phone reports the AirPods
+    QString name() const { return m_name; }
+    QString status() const { return m_status; }
+
+    // "L 80% · R ⚡75% · Case 50%" from the Android notification text, empty when it has no levels
+    static QString parseStatus(const QString &text)
+    {
+        static const QRegularExpression levelPattern("\\b(L|R|Case):\\s*(⚡)?\\s*(\\d+)%");
+        QStringList parts;
+        for (const QRegularExpressionMatch &match : levelPattern.globalMatch(text))
+            parts << match.captured(1) + " " + match.captured(2) + match.captured(3) + "%";
+        return parts.join(" · ");
+    }
+
+signals:
+    void statusChanged();
+
+private slots:
+    void onNotificationChanged(const QString &publicId)
+    {
+        read(message().path(), publicId);
+    }
+
+    void onNotificationRemoved(const QString &publicId)
+    {
+        if (message().path() == m_source && publicId == m_publicId)
+            clear();
+    }
+
+    void onAllNotificationsRemoved()
+    {
+        if (message().path() == m_source)
+            clear();
+    }
+
+private:
+    static constexpr const char *Service = "org.kde.kdeconnect";
+    static constexpr const char *NotificationsInterface = "org.kde.kdeconnect.device.notifications";
+    // app_name of the Android app
+    static constexpr const char *AndroidAppName = "LibrePods";
+
+    static QString notificationsPath(const QString &device)
+    {
+        return "/modules/kdeconnect/devices/" + device + "/notifications";
+    }
+
+    void call(const QDBusMessage &message, std::function<void(const QDBusMessage &)> handler)
+    {
+        auto *watcher = new QDBusPendingCallWatcher(QDBusConnection::sessionBus().asyncCall(message), this);
+        connect(watcher, &QDBusPendingCallWatcher::finished, this, [watcher, handler]() {
+            watcher->deleteLater();
+            if (watcher->reply().type() == QDBusMessage::ReplyMessage)
+                handler(watcher->reply());
+        });
+    }
+
+    // Picks up a notification that was posted before LibrePods started
+    void readActive(const QString &path)
+    {
+        QDBusMessage active = QDBusMessage::createMethodCall(Service, path, NotificationsInterface, "activeNotifications");
+        call(active, [this, path](const QDBusMessage &reply) {
+            for (const QString &publicId : reply.arguments().value(0).toStringList())
+                read(path, publicId);
+        });
+    }
+
+    void read(const QString &path, const QString &publicId)
+    {
+        QDBusMessage properties = QDBusMessage::createMethodCall(Service, path + "/" + publicId, "org.freedesktop.DBus.Properties", "GetAll");
+        properties << QString("org.kde.kdeconnect.device.notifications.notification");
+        call(properties, [this, path, publicId](const QDBusMessage &reply) {
+            QVariantMap notification = qdbus_cast<QVariantMap>(reply.arguments().value(0));
+            if (notification.value("appName").toString() != AndroidAppName)
+                return;
+            QString status = parseStatus(notification.value("text").toString());
+            if (status.isEmpty())
+                return;
+
+            m_source = path;
+            m_publicId = publicId;
+            if (status == m_status && notification.value("title").toString() == m_name)
+                return;
+            m_name = notification.value("title").toString();
+            m_status = status;
+            LOG_DEBUG("Battery relayed from the phone: " << m_status);
+            emit statusChanged();
+        });
+    }
+
+    void clear()
+    {
+        m_source.clear();
+        m_publicId.clear();
+        m_name.clear();
+        m_status.clear();
+        emit statusChanged();
+    }
+
+    QString m_source;
+    QString m_publicId;
+    QString m_name;
+    QString m_status;
+};
@@ -0,0 +1,242 @@
+#pragma once
+
+#include <QByteArray>
+#include <QDBusArgument>
+#include <QDBusConnection>
+#include <QDBusMessage>
+#include <QDBusMetaType>
+#include <QDBusObjectPath>
+#include <QDBusPendingCallWatcher>
+#include <QDBusVariant>
+#include <QMap>
+#include <QObject>
+#include <QString>
+
+#include <functional>
+
+#include "logger.h"
+
+// Secrets in the Secret Service keyring (GNOME Keyring, KWallet, KeePassXC, ...), talked to
+// over D-Bus without blocking. Items keep the attributes and hex encoding secret-tool used,
+// so keys stored by older versions are still found. Callbacks only run while context lives.
+namespace Keyring
+{
+    using Attributes = QMap<QString, QString>;
+
+    // (oayays) of the Secret Service API
+    struct Secret
+    {
+        QDBusObjectPath session;
+        QByteArray parameters;
+        QByteArray value;
+        QString contentType;
+    };
+
+    inline QDBusArgument &operator<<(QDBusArgument &argument, const Secret &secret)
+    {
+        argument.beginStructure();
+        argument << secret.session << secret.parameters << secret.value << secret.contentType;
+        argument.endStructure();
+        return argument;
+    }
+
+    inline const QDBusArgument &operator>>(const QDBusArgument &argument, Secret &secret)
+    {
+        argument.beginStructure();
+        argument >> secret.session >> secret.parameters >> secret.value >> secret.contentType;
+        argument.endStructure();
+        return argument;
+    }
+}
+
+Q_DECLARE_METATYPE(Keyring::Secret)
+
+namespace Keyring
+{
+    namespace Detail
+    {
+        constexpr const char *Service = "org.freedesktop.secrets";
+        constexpr const char *ServicePath = "/org/freedesktop/secrets";
+        constexpr const char *ServiceInterface = "org.freedesktop.Secret.Service";
+        constexpr const char *DefaultCollection = "/org/freedesktop/secrets/aliases/default";
+
+        using ReplyHandler = std::function<void(const QDBusMessage &)>;
+
+        inline void call(const QDBusMessage &message, QObject *context, ReplyHandler handler)
+        {
+            static const bool registered = []() {
+                qDBusRegisterMetaType<Secret>();
+                qDBusRegisterMetaType<Attributes>();
+                return true;
+            }();
+            Q_UNUSED(registered);
+
+            auto *watcher = new QDBusPendingCallWatcher(QDBusConnection::sessionBus().asyncCall(message), context);
+            QObject::connect(watcher, &QDBusPendingCallWatcher::finished, context, [watcher, handler]() {
+                watcher->deleteLater();
+                handler(watcher->reply());
+            });
+        }
+
+        inline bool failed(const QDBusMessage &reply, int arguments)
+        {
+            return reply.type() != QDBusMessage::ReplyMessage || reply.arguments().size() < arguments;
+        }
+
+        inline Attributes attributes(const QString &key)
+        {
+            return {{"application", "librepods"}, {"key", key}};
+        }
+
+        // Waits for the keyring's own unlock dialog, "/" means no prompt was needed
+        class Prompt : public QObject
+        {
+            Q_OBJECT
+
+        public:
+            Prompt(const QDBusObjectPath &path, std::function<void(bool)> done, QObject *context)
+                : QObject(context), m_done(std::move(done))
+            {
+                QDBusConnection::sessionBus().connect(Service, path.path(), "org.freedesktop.Secret.Prompt", "Completed",
+                                                      this, SLOT(onCompleted(bool, QDBusVariant)));
+                QDBusMessage message = QDBusMessage::createMethodCall(Service, path.path(), "org.freedesktop.Secret.Prompt", "Prompt");
+                message << QString();
+                call(message, this, [this](const QDBusMessage &reply) {
+                    if (reply.type() == QDBusMessage::ErrorMessage)
+                        onCompleted(true, QDBusVariant());
+                });
+            }
+
+            static void run(const QDBusObjectPath &path, QObject *context, std::function<void(bool)> done)
+            {
+                if (path.path() == "/")
+                    done(true);
+                else
+                    new Prompt(path, std::move(done), context);
+            }
+
+        private slots:
+            void onCompleted(bool dismissed, const QDBusVariant &)
+            {
+                if (!m_done)
+                    return;
+                auto done = std::move(m_done);
+                m_done = nullptr;
+                deleteLater();
+                done(!dismissed);
+            }
+
+        private:
+            std::function<void(bool)> m_done;
+        };
+
+        // Plain transfer, the secrets only travel over the session bus
+        inline void openSession(QObject *context, std::function<void(const QDBusObjectPath &)> done)
+        {
+            QDBusMessage message = QDBusMessage::createMethodCall(Service, ServicePath, ServiceInterface, "OpenSession");
+            message << QString("plain") << QVariant::fromValue(QDBusVariant(QString()));
+            call(message, context, [done](const QDBusMessage &reply) {
+                done(failed(reply, 2) ? QDBusObjectPath() : reply.arguments().at(1).value<QDBusObjectPath>());
+            });
+        }
+
+        inline void readSecret(const QDBusObjectPath &item, const QDBusObjectPath &session, QObject *context,
+                               std::function<void(const QByteArray &)> done)
+        {
+            QDBusMessage message = QDBusMessage::createMethodCall(Service, item.path(), "org.freedesktop.Secret.Item", "GetSecret");
+            message << QVariant::fromValue(session);
+            call(message, context, [done](const QDBusMessage &reply) {
+                if (failed(reply, 1))
+                {
+                    done(QByteArray());
+                    return;
+                }
+                done(QByteArray::fromHex(qdbus_cast<Secret>(reply.arguments().at(0)).value.trimmed()));
+            });
+        }
+    }
+
+    inline void store(const QString &key, const QByteArray &secret, QObject *context, std::function<void(bool)> done)
+    {
+        using namespace Detail;
+        openSession(context, [key, secret, context, done](const QDBusObjectPath &session) {
+            if (session.path().isEmpty())
+            {
+                LOG_WARN("Secret Service is not available, cannot store " << key << " in the keyring");
+                done(false);
+                return;
+            }
+
+            QVariantMap properties{{"org.freedesktop.Secret.Item.Label", "LibrePods " + key},
+                                   {"org.freedesktop.Secret.Item.Attributes", QVariant::fromValue(attributes(key))}};
+            QDBusMessage message = QDBusMessage::createMethodCall(Service, DefaultCollection, "org.freedesktop.Secret.Collection", "CreateItem");
+            message << properties << QVariant::fromValue(Secret{session, QByteArray(), secret.toHex(), "text/plain"}) << true;
+            call(message, context, [key, context, done](const QDBusMessage &reply) {
+                if (failed(reply, 2))
+                {
+                    LOG_WARN("Failed to store " << key << " in the keyring: " << reply.errorMessage());
+                    done(false);
+                    return;
+                }
+                // A locked collection asks to be unlocked first, the item is created once it is
+                Prompt::run(reply.arguments().at(1).value<QDBusObjectPath>(), context, [key, done](bool unlocked) {
+                    if (!unlocked)
+                        LOG_WARN("Keyring stayed locked, cannot store " << key);
+                    done(unlocked);
+                });
+            });
+        });
+    }
+
+    // Empty when the key isn't stored or the keyring is unavailable
+    inline void lookup(const QString &key, QObject *context, std::function<void(const QByteArray &)> done)
+    {
+        using namespace Detail;
+        openSession(context, [key, context, done](const QDBusObjectPath &session) {
+            if (session.path().isEmpty())
+            {
+                done(QByteArray());
+                return;
+            }
+
+            QDBusMessage message = QDBusMessage::createMethodCall(Service, ServicePath, ServiceInterface, "SearchItems");
+            message << QVariant::fromValue(attributes(key));
+            call(message, context, [session, context, done](const QDBusMessage &reply) {
+                if (failed(reply, 2))
+                {
+                    done(QByteArray());
+                    return;
+                }
+                const auto unlocked = qdbus_cast<QList<QDBusObjectPath>>(reply.arguments().at(0));
+                const auto locked = qdbus_cast<QList<QDBusObjectPath>>(reply.arguments().at(1));
+                if (!unlocked.isEmpty())
+                {
+                    readSecret(unlocked.first(), session, context, done);
+                    return;
+                }
+                if (locked.isEmpty())
+                {
+                    done(QByteArray());
+                    return;
+                }
+
+                QDBusObjectPath item = locked.first();
+                QDBusMessage unlock = QDBusMessage::createMethodCall(Service, ServicePath, ServiceInterface, "Unlock");
+                unlock << QVariant::fromValue(QList<QDBusObjectPath>{item});
+                call(unlock, context, [item, session, context, done](const QDBusMessage &reply) {
+                    if (failed(reply, 2))
+                    {
+                        done(QByteArray());
+                        return;
+                    }
+                    Prompt::run(reply.arguments().at(1).value<QDBusObjectPath>(), context, [item, session, context, done](bool unlocked) {
+                        if (unlocked)
+                            readSecret(item, session, context, done);
+                        else
+                            done(QByteArray());
+                    });
+                });
+            });
+        });
+    }
+}
@@ -0,0 +1,102 @@
+#pragma once
+
+#include <QDBusConnection>
+#include <QDBusInterface>
+#include <QDBusObjectPath>
+#include <QDBusReply>
+#include <QObject>
+#include <QTimer>
+
+#include "logger.h"
+
+// One-shot coarse location lookup through GeoClue, used to remember where the
+// AirPods were when they disconnected
+class LastSeenLocator : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit LastSeenLocator(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_timeout.setSingleShot(true);
+        m_timeout.setInterval(30000);
+        connect(&m_timeout, &QTimer::timeout, this, [this]()
+                {
+                    LOG_WARN("GeoClue did not report a location in time");
+                    stop();
+                    emit locationFailed(); });
+    }
+
+    ~LastSeenLocator() override { stop(); }
+
+    void locate()
+    {
+        if (!m_clientPath.isEmpty())
+            return; // Already waiting for a location
+
+        QDBusInterface manager(GeoClueService, "/org/freedesktop/GeoClue2/Manager",
+                               "org.freedesktop.GeoClue2.Manager", QDBusConnection::systemBus());
+        QDBusReply<QDBusObjectPath> client = manager.call("GetClient");
+        if (!client.isValid())
+        {
+            LOG_WARN("GeoClue is not available: " << client.error().message());
+            emit locationFailed();
+            return;
+        }
+        m_clientPath = client.value().path();
+
+        QDBusInterface clientInterface(GeoClueService, m_clientPath, ClientInterface, QDBusConnection::systemBus());
+        clientInterface.setProperty("DesktopId", "me.kavishdevar.librepods");
+        clientInterface.setProperty("RequestedAccuracyLevel", CityAccuracy);
+
+        QDBusConnection::systemBus().connect(GeoClueService, m_clientPath, ClientInterface, "LocationUpdated",
+                                             this, SLOT(onLocationUpdated(QDBusObjectPath, QDBusObjectPath)));
+        QDBusReply<void> started = clientInterface.call("Start");
+        if (!started.isValid())
+        {
+            LOG_WARN("Failed to start GeoClue client: " << started.error().message());
+            stop();
+            emit locationFailed();
+            return;
+        }
+        m_timeout.start();
+    }
+
+signals:
+    void locationFound(double latitude, double longitude, double accuracy);
+    void locationFailed();
+
+private slots:
+    void onLocationUpdated(const QDBusObjectPath &, const QDBusObjectPath &location)
+    {
+        QDBusInterface locationInterface(GeoClueService, location.path(), "org.freedesktop.GeoClue2.Location",
+                                         QDBusConnection::systemBus());
+        double latitude = locationInterface.property("Latitude").toDouble();
+        double longitude = locationInterface.property("Longitude").toDouble();
+        double accuracy = locationInterface.property("Accuracy").toDouble();
+        stop();
+
+        LOG_DEBUG("GeoClue location: " << latitude << ", " << longitude << " (" << accuracy << " m)");
+        emit locationFound(latitude, longitude, accuracy);
+    }
+
+private:
+    static constexpr const char *GeoClueService = "org.freedesktop.GeoClue2";
+    static constexpr const char *ClientInterface = "org.freedesktop.GeoClue2.Client";
+    static constexpr uint CityAccuracy = 4; // GCLUE_ACCURACY_LEVEL_CITY
+
+    void stop()
+    {
+        m_timeout.stop();
+        if (m_clientPath.isEmpty())
+            return;
+
+        QDBusConnection::systemBus().disconnect(GeoClueService, m_clientPath, ClientInterface, "LocationUpdated",
+                                                this, SLOT(onLocationUpdated(QDBusObjectPath, QDBusObjectPath)));
+        QDBusInterface(GeoClueService, m_clientPath, ClientInterface, QDBusConnection::systemBus()).call("Stop");
+        m_clientPath.clear();
+    }
+
+    QString m_clientPath;
+    QTimer m_timeout;
+};
@@ -0,0 +1,109 @@
+#pragma once
+
+#include <QElapsedTimer>
+#include <QList>
+#include <QObject>
+#include <QTimer>
+
+#include <algorithm>
+#include <optional>
+
+#include "logger.h"
+#include "testtone.hpp"
+
+// Rough output latency from a tap-along test: a click track plays through the default sink and
+// the user taps in time with what they hear. People tap on the beat instead of reacting to it,
+// so the median offset between the clicks as written and the taps is close to the latency of
+// the whole path, player start-up included. Good enough to compare codecs, not for lip sync.
+class LatencyTest : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(bool running READ running NOTIFY runningChanged)
+    Q_PROPERTY(int taps READ taps NOTIFY tapsChanged)
+    Q_PROPERTY(int resultMs READ resultMs NOTIFY resultChanged)
+
+public:
+    static constexpr int Clicks = 12;
+    static constexpr int IntervalMs = 600;
+    static constexpr int LeadInMs = 1000;
+    // The first taps are spent finding the beat
+    static constexpr int IgnoredTaps = 2;
+    static constexpr int MinTaps = 4;
+    // Offsets are placed in this window, anything outside is a missed beat
+    static constexpr int EarliestMs = -100;
+
+    explicit LatencyTest(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_finishTimer.setSingleShot(true);
+        m_finishTimer.setInterval(LeadInMs + Clicks * IntervalMs + IntervalMs);
+        connect(&m_finishTimer, &QTimer::timeout, this, &LatencyTest::finish);
+    }
+
+    bool running() const { return m_finishTimer.isActive(); }
+    int taps() const { return m_taps.size(); }
+    // -1 until a test finished with enough taps
+    int resultMs() const { return m_result; }
+
+    // Tap times in ms since the track started, the estimate in ms or nothing with too few taps
+    static std::optional<int> estimate(const QList<qint64> &taps)
+    {
+        QList<qint64> offsets;
+        for (qsizetype i = IgnoredTaps; i < taps.size(); ++i)
+        {
+            qint64 sinceFirstClick = taps[i] - LeadInMs;
+            qint64 beat = (sinceFirstClick - EarliestMs) / IntervalMs;
+            if (sinceFirstClick < EarliestMs || beat >= Clicks)
+                continue;
+            offsets.append(sinceFirstClick - beat * IntervalMs);
+        }
+        if (offsets.size() < MinTaps)
+            return std::nullopt;
+
+        std::sort(offsets.begin(), offsets.end());
+        return static_cast<int>(offsets[offsets.size() / 2]);
+    }
+
+public slots:
+    void start()
+    {
+        if (running())
+            return;
+        if (!TestTone::playClickTrack(Clicks, IntervalMs, LeadInMs))
+            return;
+        m_clock.start();
+        m_taps.clear();
+        m_finishTimer.start();
+        emit tapsChanged();
+        emit runningChanged();
+    }
+
+    void tap()
+    {
+        if (!running())
+            return;
+        m_taps.append(m_clock.elapsed());
+        emit tapsChanged();
+    }
+
+signals:
+    void runningChanged();
+    void tapsChanged();
+    void resultChanged();
+
+private:
+    void finish()
+    {
+        m_result = estimate(m_taps).value_or(-1);
+        if (m_result < 0)
+            LOG_INFO("Latency test finished with too few taps (" << m_taps.size() << ")");
+        else
+            LOG_INFO("Estimated output latency: " << m_result << " ms");
+        emit runningChanged();
+        emit resultChanged();
+    }
+
+    QElapsedTimer m_clock;
+    QTimer m_finishTimer;
+    QList<qint64> m_taps;
+    int m_result = -1;
+};
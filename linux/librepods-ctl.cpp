@@ -2,6 +2,8 @@
 #include <QLocalSocket>
 #include <QTextStream>
 
+#include "localsocketauth.hpp"
+
 int main(int argc, char *argv[]) {
     QCoreApplication app(argc, argv);
 
@@ -11,21 +13,68 @@ int main(int argc, char *argv[]) {
                             << "  noise:off           Disable noise control\n"
                             << "  noise:anc           Enable Active Noise Cancellation\n"
                             << "  noise:transparency  Enable Transparency mode\n"
-                            << "  noise:adaptive      Enable Adaptive mode\n";
+                            << "  noise:adaptive      Enable Adaptive mode\n"
+                            << "  set-mode <off|anc|transparency|adaptive>\n"
+                            << "                      Switch the listening mode\n"
+                            << "  connect             Connect the last used AirPods to this computer\n"
+                            << "  status              Show daemon, adapter and connection status\n"
+                            << "  battery             Show the battery levels, one component per line\n"
+                            << "  list-devices        Print the known devices as JSON\n"
+                            << "  export-history [--json] [--from YYYY-MM-DD] [--to YYYY-MM-DD]\n"
+                            << "                      Print the stored battery history as CSV or JSON\n";
         return 1;
     }
 
+    QByteArray command(argv[1]);
+    if (command == "set-mode") {
+        QByteArray mode = argc > 2 ? QByteArray(argv[2]) : QByteArray();
+        if (mode != "off" && mode != "anc" && mode != "transparency" && mode != "adaptive") {
+            QTextStream(stderr) << "Usage: librepods-ctl set-mode <off|anc|transparency|adaptive>\n";
+            return 1;
+        }
+        command += " " + mode;
+    }
+
     QLocalSocket socket;
-    socket.connectToServer("app_server");
+    socket.setSocketOptions(QLocalSocket::AbstractNamespaceOption);
+    // LIBREPODS_UID reaches another user's instance, if their ipc/allowedGroups lets us in
+    bool uidSet = false;
+    uint uid = qEnvironmentVariable("LIBREPODS_UID").toUInt(&uidSet);
+    socket.connectToServer(uidSet ? LocalSocketAuth::serverName(uid) : LocalSocketAuth::serverName());
 
     if (!socket.waitForConnected(500)) {
         QTextStream(stderr) << "Could not connect to librepods (is it running?)\n";
         return 1;
     }
 
-    socket.write(QByteArray(argv[1]));
+    if (command == "export-history") {
+        QByteArray format = "csv", from = "-", to = "-";
+        for (int i = 2; i < argc; ++i) {
+            QByteArray arg(argv[i]);
+            if (arg == "--json")
+                format = "json";
+            else if (arg == "--from" && i + 1 < argc)
+                from = argv[++i];
+            else if (arg == "--to" && i + 1 < argc)
+                to = argv[++i];
+        }
+        command += " " + format + " " + from + " " + to;
+    }
+
+    socket.write(command);
     socket.flush();
     socket.waitForBytesWritten(200);
+
+    // Some commands answer with a report, read until librepods closes the connection
+    QByteArray reply;
+    while (socket.state() == QLocalSocket::ConnectedState && socket.waitForReadyRead(1000)) {
+        reply += socket.readAll();
+    }
+    reply += socket.readAll();
+    if (!reply.isEmpty()) {
+        QTextStream(stdout) << reply;
+    }
+
     socket.disconnectFromServer();
     return 0;
 }
@@ -0,0 +1,76 @@
+#pragma once
+
+#include <QList>
+#include <QString>
+#include <QStringList>
+
+#include <cerrno>
+#include <vector>
+
+#include <sys/socket.h>
+#include <unistd.h>
+
+// The control socket lives in the abstract namespace, which has no file permissions to
+// restrict access, so every connection is checked against the peer's credentials instead
+namespace LocalSocketAuth
+{
+    // Abstract names are shared by the whole machine, so each user gets their own
+    inline QString serverName(uint uid = getuid())
+    {
+        return QString("librepods-%1").arg(uid);
+    }
+
+    // Group IDs from a "ipc/allowedGroups" style list, invalid entries are skipped
+    inline QList<uint> parseGroups(const QStringList &entries)
+    {
+        QList<uint> groups;
+        for (const QString &entry : entries)
+        {
+            bool ok = false;
+            uint gid = entry.trimmed().toUInt(&ok);
+            if (ok)
+                groups.append(gid);
+        }
+        return groups;
+    }
+
+    // Primary and supplementary groups of the peer. Kernels older than 4.13 don't have
+    // SO_PEERGROUPS, only the primary group is known there.
+    inline QList<uint> peerGroups(int fd, gid_t primary)
+    {
+        QList<uint> groups{primary};
+#ifdef SO_PEERGROUPS
+        std::vector<gid_t> buffer(64);
+        socklen_t length = buffer.size() * sizeof(gid_t);
+        int result = getsockopt(fd, SOL_SOCKET, SO_PEERGROUPS, buffer.data(), &length);
+        if (result != 0 && errno == ERANGE)
+        {
+            // length now holds the size that's needed
+            buffer.resize(length / sizeof(gid_t));
+            result = getsockopt(fd, SOL_SOCKET, SO_PEERGROUPS, buffer.data(), &length);
+        }
+        if (result == 0)
+            for (socklen_t i = 0; i < length / sizeof(gid_t); ++i)
+                groups.append(buffer[i]);
+#endif
+        return groups;
+    }
+
+    // Same user is always allowed, others only when one of their groups is in the allowlist
+    inline bool isPeerAllowed(qintptr descriptor, const QList<uint> &allowedGroups)
+    {
+        int fd = static_cast<int>(descriptor);
+        struct ucred credentials {};
+        socklen_t length = sizeof(credentials);
+        if (getsockopt(fd, SOL_SOCKET, SO_PEERCRED, &credentials, &length) != 0)
+            return false;
+        if (credentials.uid == getuid())
+            return true;
+        if (allowedGroups.isEmpty())
+            return false;
+        for (uint gid : peerGroups(fd, credentials.gid))
+            if (allowedGroups.contains(gid))
+                return true;
+        return false;
+    }
+}
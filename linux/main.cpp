@@ -1,5 +1,4 @@
 #include <QSettings>
-#include <QLocalServer>
 #include <QLocalSocket>
 #include <QApplication>
 #include <QQmlApplicationEngine>
@@ -16,6 +15,19 @@
 #include <QLibraryInfo>
 #include <QDir>
 #include <QStandardPaths>
+#include <QJsonDocument>
+#include <QJsonArray>
+#include <QElapsedTimer>
+#include <QDateTime>
+#include <QMetaEnum>
+#include <QDBusConnection>
+#include <QDBusConnectionInterface>
+#include <QDBusReply>
+#include <QSystemTrayIcon>
+
+#include <algorithm>
+#include <functional>
+#include <utility>
 
 #include "airpods_packets.h"
 #include "logger.h"
@@ -30,67 +42,271 @@
 #include "ble/bleutils.h"
 #include "QRCodeImageProvider.hpp"
 #include "systemsleepmonitor.hpp"
+#include "storage.hpp"
+#include "systemdintegration.hpp"
+#include "pluginmanager.hpp"
+#include "scripthost.hpp"
+#include "httpapi.hpp"
+#include "dbusservice.hpp"
+#include "controlserver.hpp"
+#include "connectiontimeline.hpp"
+#include "conversationstats.hpp"
+#include "settingsauditlog.hpp"
+#include "lastseenlocator.hpp"
+#include "bluezbatteryprovider.hpp"
+#include "drainmonitor.hpp"
+#include "batteryhistory.hpp"
+#include "wearreminder.hpp"
+#include "headtracking.hpp"
+#include "testtone.hpp"
+#include "latencytest.hpp"
+#include "headsetpower.hpp"
+#include "callannouncer.hpp"
+#include "kdeconnectrelay.hpp"
+#include "soundtheme.hpp"
+#include "localsocketauth.hpp"
+#include "packetnames.hpp"
+#include "syntheticdevices.hpp"
+#include "modemapping.hpp"
+#include "ratecounter.hpp"
+#include "systemappearance.hpp"
+#include "devicestore.hpp"
 
 using namespace AirpodsTrayApp::Enums;
 
 Q_LOGGING_CATEGORY(librepods, "librepods")
 
+// Wires the AirPods connection to the window, the tray and the subsystems. Each feature keeps its
+// logic in its own header (controlserver.hpp, devicestore.hpp, httpapi.hpp, ...), only the glue lives here.
 class AirPodsTrayApp : public QObject {
     Q_OBJECT
     Q_PROPERTY(bool airpodsConnected READ areAirpodsConnected NOTIFY airPodsStatusChanged)
+    Q_PROPERTY(bool deviceLoading READ deviceLoading NOTIFY deviceLoadingChanged)
+    Q_PROPERTY(QString connectionConflict READ connectionConflict NOTIFY connectionConflictChanged)
+    Q_PROPERTY(QString lastDeviceAddress READ lastDeviceAddress NOTIFY lastDeviceAddressChanged)
+    Q_PROPERTY(QString undoDescription READ undoDescription NOTIFY undoChanged)
+    Q_PROPERTY(int pendingWrites READ pendingWrites NOTIFY pendingWritesChanged)
+    Q_PROPERTY(QStringList missingDependencies READ missingDependencies NOTIFY missingDependenciesChanged)
     Q_PROPERTY(int earDetectionBehavior READ earDetectionBehavior WRITE setEarDetectionBehavior NOTIFY earDetectionBehaviorChanged)
     Q_PROPERTY(bool crossDeviceEnabled READ crossDeviceEnabled WRITE setCrossDeviceEnabled NOTIFY crossDeviceEnabledChanged)
     Q_PROPERTY(AutoStartManager *autoStartManager READ autoStartManager CONSTANT)
     Q_PROPERTY(bool notificationsEnabled READ notificationsEnabled WRITE setNotificationsEnabled NOTIFY notificationsEnabledChanged)
+    Q_PROPERTY(bool trayShowsCaseBattery READ trayShowsCaseBattery WRITE setTrayShowsCaseBattery NOTIFY trayShowsCaseBatteryChanged)
+    Q_PROPERTY(bool modeOsdEnabled READ modeOsdEnabled WRITE setModeOsdEnabled NOTIFY modeOsdEnabledChanged)
+    Q_PROPERTY(int theme READ theme WRITE setTheme NOTIFY themeChanged)
+    Q_PROPERTY(int trayClickAction READ trayClickAction WRITE setTrayClickAction NOTIFY trayClickActionsChanged)
+    Q_PROPERTY(int trayDoubleClickAction READ trayDoubleClickAction WRITE setTrayDoubleClickAction NOTIFY trayClickActionsChanged)
     Q_PROPERTY(int retryAttempts READ retryAttempts WRITE setRetryAttempts NOTIFY retryAttemptsChanged)
     Q_PROPERTY(bool hideOnStart READ hideOnStart CONSTANT)
+    Q_PROPERTY(bool readOnly READ readOnly CONSTANT)
     Q_PROPERTY(DeviceInfo *deviceInfo READ deviceInfo CONSTANT)
     Q_PROPERTY(QString phoneMacStatus READ phoneMacStatus NOTIFY phoneMacStatusChanged)
     Q_PROPERTY(bool hearingAidEnabled READ hearingAidEnabled WRITE setHearingAidEnabled NOTIFY hearingAidEnabledChanged)
+    Q_PROPERTY(QString trayIconFontStatus READ trayIconFontStatus CONSTANT)
+    Q_PROPERTY(QVariantList bluezProperties READ bluezProperties NOTIFY bluezPropertiesChanged)
+    Q_PROPERTY(QStringList otherHosts READ otherHosts NOTIFY otherHostsChanged)
+    Q_PROPERTY(QVariantList audioDevices READ audioDevices NOTIFY audioDevicesChanged)
+    Q_PROPERTY(QVariantList knownAirPods READ knownAirPods NOTIFY knownAirPodsChanged)
+    Q_PROPERTY(HeadTracking *headTracking READ headTracking CONSTANT)
+    Q_PROPERTY(LatencyTest *latencyTest READ latencyTest CONSTANT)
+    Q_PROPERTY(HeadsetPower *headsetPower READ headsetPower CONSTANT)
+    Q_PROPERTY(bool adapterAvailable READ adapterAvailable NOTIFY adapterChanged)
+    Q_PROPERTY(bool showWindowOnConnect READ showWindowOnConnect WRITE setShowWindowOnConnect NOTIFY showWindowOnConnectChanged)
+    Q_PROPERTY(bool adapterPowered READ adapterPowered WRITE setAdapterPowered NOTIFY adapterChanged)
+    Q_PROPERTY(bool adapterDiscoverable READ adapterDiscoverable WRITE setAdapterDiscoverable NOTIFY adapterChanged)
+    Q_PROPERTY(bool adapterPairable READ adapterPairable WRITE setAdapterPairable NOTIFY adapterChanged)
+    Q_PROPERTY(ConnectionTimeline *timeline READ timeline CONSTANT)
+    Q_PROPERTY(SettingsAuditLog *settingsLog READ settingsLog CONSTANT)
+    Q_PROPERTY(ConversationStats *conversationStats READ conversationStats CONSTANT)
+    Q_PROPERTY(BatteryHistory *batteryHistory READ batteryHistory CONSTANT)
+    Q_PROPERTY(SyntheticDevices *syntheticDevices READ syntheticDevices CONSTANT)
+    Q_PROPERTY(bool outOfRangeAlert READ outOfRangeAlert WRITE setOutOfRangeAlert NOTIFY outOfRangeAlertChanged)
+    Q_PROPERTY(bool restoreVolume READ restoreVolume WRITE setRestoreVolume NOTIFY restoreVolumeChanged)
+    Q_PROPERTY(bool switchDefaultOutput READ switchDefaultOutput WRITE setSwitchDefaultOutput NOTIFY defaultOutputSettingsChanged)
+    Q_PROPERTY(bool moveStreamsToDefaultOutput READ moveStreamsToDefaultOutput WRITE setMoveStreamsToDefaultOutput NOTIFY defaultOutputSettingsChanged)
+    Q_PROPERTY(bool outOfRangeAlertSound READ outOfRangeAlertSound WRITE setOutOfRangeAlertSound NOTIFY outOfRangeAlertSoundChanged)
+    Q_PROPERTY(QVariantMap eventSounds READ eventSounds NOTIFY eventSoundsChanged)
+    Q_PROPERTY(int wearReminderInterval READ wearReminderInterval WRITE setWearReminderInterval NOTIFY wearReminderChanged)
+    Q_PROPERTY(QString wearReminderQuietStart READ wearReminderQuietStart WRITE setWearReminderQuietStart NOTIFY wearReminderChanged)
+    Q_PROPERTY(QString wearReminderQuietEnd READ wearReminderQuietEnd WRITE setWearReminderQuietEnd NOTIFY wearReminderChanged)
+    Q_PROPERTY(int outOfEarPlaybackDelay READ outOfEarPlaybackDelay WRITE setOutOfEarPlaybackDelay NOTIFY outOfEarPlaybackChanged)
+    Q_PROPERTY(bool outOfEarPlaybackPause READ outOfEarPlaybackPause WRITE setOutOfEarPlaybackPause NOTIFY outOfEarPlaybackChanged)
+    Q_PROPERTY(bool lowBatteryAnnouncement READ lowBatteryAnnouncement WRITE setLowBatteryAnnouncement NOTIFY lowBatteryAnnouncementChanged)
+    Q_PROPERTY(bool lastSeenEnabled READ lastSeenEnabled WRITE setLastSeenEnabled NOTIFY lastSeenEnabledChanged)
+    Q_PROPERTY(QVariantMap lastSeen READ lastSeen NOTIFY lastSeenChanged)
+    Q_PROPERTY(QStringList recentPackets READ recentPackets NOTIFY recentPacketsChanged)
+    Q_PROPERTY(QVariantMap diagnostics READ diagnostics NOTIFY diagnosticsChanged)
+    Q_PROPERTY(QVariantList audioCodecs READ audioCodecs NOTIFY audioCodecsChanged)
+    Q_PROPERTY(QString activeAudioCodec READ activeAudioCodec NOTIFY audioCodecsChanged)
+    Q_PROPERTY(int deviceVolume READ deviceVolume NOTIFY volumesChanged)
+    Q_PROPERTY(int hostVolume READ hostVolume NOTIFY volumesChanged)
+    Q_PROPERTY(bool volumeLock READ volumeLock WRITE setVolumeLock NOTIFY volumeLockChanged)
+    Q_PROPERTY(int conversationReleaseDelay READ conversationReleaseDelay WRITE setConversationReleaseDelay NOTIFY conversationReleaseDelayChanged)
+    Q_PROPERTY(bool announceCalls READ announceCalls WRITE setAnnounceCalls NOTIFY announceCallsChanged)
+    Q_PROPERTY(QString phoneDeviceName READ phoneDeviceName NOTIFY phoneStatusChanged)
+    Q_PROPERTY(QString phoneBatteryStatus READ phoneBatteryStatus NOTIFY phoneStatusChanged)
 
 public:
-    AirPodsTrayApp(bool debugMode, bool hideOnStart, QQmlApplicationEngine *parent = nullptr)
-        : QObject(parent), debugMode(debugMode), m_settings(new QSettings("AirPodsTrayApp", "AirPodsTrayApp"))
-        , m_autoStartManager(new AutoStartManager(this)), m_hideOnStart(hideOnStart), parent(parent)
+    enum TrayAction
+    {
+        OpenWindow,
+        ToggleNoiseCancellation,
+        RefreshBattery,
+        NoAction
+    };
+    Q_ENUM(TrayAction)
+
+    AirPodsTrayApp(bool debugMode, bool hideOnStart, bool headless, bool readOnly, QQmlApplicationEngine *parent = nullptr)
+        : QObject(parent), debugMode(debugMode), m_settings(new QSettings(Storage::settingsFilePath(), QSettings::IniFormat))
+        , m_autoStartManager(new AutoStartManager(this)), m_hideOnStart(hideOnStart), m_headless(headless), parent(parent)
         , m_deviceInfo(new DeviceInfo(this)), m_bleManager(new BleManager(this))
-        , m_systemSleepMonitor(new SystemSleepMonitor(this))
+        , m_systemSleepMonitor(new SystemSleepMonitor(this)), m_timeline(new ConnectionTimeline(this))
+        , m_settingsLog(new SettingsAuditLog(this)), m_lastSeenLocator(new LastSeenLocator(this))
+        , m_headTracking(new HeadTracking(this)), m_conversationStats(new ConversationStats(this))
+        , m_batteryProvider(new BlueZBatteryProvider(this)), m_batteryHistory(new BatteryHistory(this))
+        , m_wearReminder(new WearReminder(this)), m_latencyTest(new LatencyTest(this)), m_headsetPower(new HeadsetPower(this))
+        , m_callAnnouncer(new CallAnnouncer(this)), m_kdeConnectRelay(new KdeConnectRelay(this))
     {
         QLoggingCategory::setFilterRules(QString("librepods.debug=%1").arg(debugMode ? "true" : "false"));
         LOG_INFO("Initializing LibrePods");
+        m_uptime.start();
+
+        // general/readOnly in the settings file locks a shared machine without touching the command line
+        m_readOnly = readOnly || m_settings->value("general/readOnly", false).toBool();
+        if (m_readOnly)
+            LOG_INFO("Read-only mode, commands to the AirPods are disabled");
+
+        if (debugMode)
+            m_syntheticDevices = new SyntheticDevices(this);
+
+        if (!m_headless)
+        {
+            m_defaultPalette = QGuiApplication::palette();
+            m_appearance = new SystemAppearance(this);
+            connect(m_appearance, &SystemAppearance::changed, this, &AirPodsTrayApp::applyTheme);
+            applyTheme();
+        }
 
         // Initialize tray icon and connect signals
         trayManager = new TrayIconManager(this);
         trayManager->setNotificationsEnabled(loadNotificationsEnabled());
-        connect(trayManager, &TrayIconManager::trayClicked, this, &AirPodsTrayApp::onTrayIconActivated);
+        trayManager->setShowCaseBattery(m_settings->value("tray/showCaseBattery", false).toBool());
+        trayManager->setIconFontFamily(m_settings->value("tray/iconFont").toString());
+        trayManager->setReadOnly(m_readOnly);
+        trayManager->setDoubleClickEnabled(trayDoubleClickAction() != NoAction);
+        connect(trayManager, &TrayIconManager::trayClicked, this, [this]()
+                { withChangeSource("tray", [&]() { runTrayAction(static_cast<TrayAction>(trayClickAction())); }); });
+        connect(trayManager, &TrayIconManager::trayDoubleClicked, this, [this]()
+                { withChangeSource("tray", [&]() { runTrayAction(static_cast<TrayAction>(trayDoubleClickAction())); }); });
         connect(trayManager, &TrayIconManager::openApp, this, &AirPodsTrayApp::onOpenApp);
         connect(trayManager, &TrayIconManager::openSettings, this, &AirPodsTrayApp::onOpenSettings);
-        connect(trayManager, &TrayIconManager::noiseControlChanged, this, &AirPodsTrayApp::setNoiseControlMode);
-        connect(trayManager, &TrayIconManager::conversationalAwarenessToggled, this, &AirPodsTrayApp::setConversationalAwareness);
+        connect(trayManager, &TrayIconManager::noiseControlChanged, this, [this](NoiseControlMode mode)
+                { withChangeSource("tray", [&]() { setNoiseControlMode(mode); }); });
+        connect(trayManager, &TrayIconManager::conversationalAwarenessToggled, this, [this](bool enabled)
+                { withChangeSource("tray", [&]() { setConversationalAwareness(enabled); }); });
+        connect(trayManager, &TrayIconManager::allowOffOptionToggled, this, [this](bool allowed)
+                { withChangeSource("tray", [&]() { setAllowOffOption(allowed); }); });
         connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, trayManager, &TrayIconManager::updateBatteryStatus);
+        connect(m_kdeConnectRelay, &KdeConnectRelay::statusChanged, this, [this]()
+                {
+                    trayManager->setPhoneStatus(m_kdeConnectRelay->status());
+                    emit phoneStatusChanged();
+                });
         connect(m_deviceInfo, &DeviceInfo::noiseControlModeChanged, trayManager, &TrayIconManager::updateNoiseControlState);
         connect(m_deviceInfo, &DeviceInfo::conversationalAwarenessChanged, trayManager, &TrayIconManager::updateConversationalAwareness);
+        connect(m_deviceInfo, &DeviceInfo::allowOffOptionChanged, trayManager, &TrayIconManager::updateAllowOffOption);
+        connect(m_deviceInfo, &DeviceInfo::modelChanged, this, [this]()
+                { trayManager->setModelIcon(m_deviceInfo->model() == AirPodsModel::Unknown ? QString() : m_deviceInfo->podIcon()); });
         connect(trayManager, &TrayIconManager::notificationsEnabledChanged, this, &AirPodsTrayApp::saveNotificationsEnabled);
         connect(trayManager, &TrayIconManager::notificationsEnabledChanged, this, &AirPodsTrayApp::notificationsEnabledChanged);
+        connect(trayManager, &TrayIconManager::showCaseBatteryChanged, this, [this](bool enabled)
+                {
+                    m_settings->setValue("tray/showCaseBattery", enabled);
+                    emit trayShowsCaseBatteryChanged(enabled); });
+        connect(m_deviceInfo, &DeviceInfo::bluetoothAddressChanged, this, [this](const QString &address)
+                {
+                    emit outOfRangeAlertChanged(loadOutOfRangeAlert(address));
+                    emit defaultOutputSettingsChanged();
+                    m_deviceInfo->setIgnoreCase(!address.isEmpty() && m_settings->value(ignoreCaseKey(address), false).toBool());
+                    m_deviceInfo->setCapabilities(address.isEmpty() ? DeviceCapabilities()
+                                                                     : DeviceCapabilities::fromStringList(m_settings->value(capabilitiesKey(address)).toStringList())); });
+        connect(m_lastSeenLocator, &LastSeenLocator::locationFound, this, &AirPodsTrayApp::saveLastSeenLocation);
 
         // Initialize MediaController and connect signals
         mediaController = new MediaController(this);
         connect(mediaController, &MediaController::mediaStateChanged, this, &AirPodsTrayApp::handleMediaStateChange);
+        connect(mediaController, &MediaController::conversationDuckingStarted, m_conversationStats, &ConversationStats::duckingStarted);
+        connect(mediaController, &MediaController::conversationDuckingEnded, m_conversationStats, &ConversationStats::duckingEnded);
         mediaController->followMediaChanges();
+        mediaController->setPreferredCodec(m_settings->value("audio/codec").toString());
+        mediaController->setConversationReleaseDelay(m_settings->value("conversationalAwareness/releaseDelay", 0).toInt());
+        connect(mediaController, &MediaController::airPodsOutputActivated, this, &AirPodsTrayApp::switchDefaultOutputIfEnabled);
+        connect(mediaController, &MediaController::airPodsOutputActivated, this, &AirPodsTrayApp::restoreDeviceVolume);
+        checkRuntimeDependencies();
+
+        connect(&m_volumeSyncTimer, &QTimer::timeout, this, &AirPodsTrayApp::refreshVolumes);
+        m_volumeSyncTimer.setInterval(1000);
 
         monitor = new BluetoothMonitor(this);
         connect(monitor, &BluetoothMonitor::deviceConnected, this, &AirPodsTrayApp::bluezDeviceConnected);
         connect(monitor, &BluetoothMonitor::deviceDisconnected, this, &AirPodsTrayApp::bluezDeviceDisconnected);
+        connect(monitor, &BluetoothMonitor::transportVolumeChanged, this, &AirPodsTrayApp::onTransportVolumeChanged);
+        connect(monitor, &BluetoothMonitor::adapterPropertiesChanged, this, &AirPodsTrayApp::refreshAdapterProperties);
+        refreshAdapterProperties();
+        connect(monitor, &BluetoothMonitor::devicePropertiesChanged, this, [this](const QString &address)
+        {
+            if (m_watchBluezProperties && address.compare(m_deviceInfo->bluetoothAddress(), Qt::CaseInsensitive) == 0)
+                refreshBluezProperties();
+            refreshAudioDeviceIfListed(address);
+        });
+        connect(monitor, &BluetoothMonitor::deviceBatteryChanged, this, &AirPodsTrayApp::refreshAudioDeviceIfListed);
 
         connect(m_bleManager, &BleManager::deviceFound, this, &AirPodsTrayApp::bleDeviceFound);
         connect(m_deviceInfo->getBattery(), &Battery::primaryChanged, this, &AirPodsTrayApp::primaryChanged);
+        connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, this, &AirPodsTrayApp::updateBlueZBattery);
+        connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, this, &AirPodsTrayApp::checkDrainRates);
+        connect(m_batteryHistory, &BatteryHistory::markerAdded, this, &AirPodsTrayApp::onBatteryMarkerAdded);
+        auto updateHeadsetPower = [this]()
+        {
+            m_headsetPower->update(isModelHeadset(m_deviceInfo->model()), areAirpodsConnected(),
+                                   m_deviceInfo->getEarDetection()->oneOrMorePodsInEar());
+        };
+        connect(m_deviceInfo->getEarDetection(), &EarDetection::statusChanged, this, updateHeadsetPower);
+        connect(m_deviceInfo, &DeviceInfo::modelChanged, this, updateHeadsetPower);
+        connect(this, &AirPodsTrayApp::airPodsStatusChanged, this, updateHeadsetPower);
+        connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, this, &AirPodsTrayApp::checkLowBattery);
+        connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, this, &AirPodsTrayApp::recordBatteryHistory);
+        connect(m_deviceInfo->getEarDetection(), &EarDetection::statusChanged, this, [this]()
+                { m_wearReminder->setInEar(areAirpodsConnected() && m_deviceInfo->getEarDetection()->oneOrMorePodsInEar()); });
+        connect(m_wearReminder, &WearReminder::reminderDue, this, &AirPodsTrayApp::showWearReminder);
+        m_outOfEarPlaybackTimer = new QTimer(this);
+        m_outOfEarPlaybackTimer->setInterval(5000);
+        connect(m_outOfEarPlaybackTimer, &QTimer::timeout, this, &AirPodsTrayApp::checkOutOfEarPlayback);
+        connect(m_deviceInfo->getEarDetection(), &EarDetection::statusChanged, this, &AirPodsTrayApp::updateOutOfEarPlaybackWatch);
+        applyWearReminderSettings();
+        connect(m_deviceInfo, &DeviceInfo::noiseControlModeChangedInt, this, [this](int mode)
+        {
+            static const QStringList modes = {"Off", "Noise Cancellation", "Transparency", "Adaptive"};
+            m_timeline->record(m_deviceInfo->bluetoothAddress(), "Listening mode", modes.value(mode));
+
+            // The initial state dump and the reset on disconnect aren't changes worth announcing
+            if (modeOsdEnabled() && areAirpodsConnected() && !m_deviceLoading)
+            {
+                const QStringList names = {tr("Off"), tr("Noise Cancellation"), tr("Transparency"), tr("Adaptive")};
+                emit modeOsdRequested(names.value(mode));
+            }
+        });
         connect(m_systemSleepMonitor, &SystemSleepMonitor::systemGoingToSleep, this, &AirPodsTrayApp::onSystemGoingToSleep);
         connect(m_systemSleepMonitor, &SystemSleepMonitor::systemWakingUp, this, &AirPodsTrayApp::onSystemWakingUp);
 
         // Load settings
         CrossDevice.isEnabled = loadCrossDeviceEnabled();
+        setVolumeLock(m_settings->value("audio/lockVolume", false).toBool());
         setEarDetectionBehavior(loadEarDetectionSettings());
         setRetryAttempts(loadRetryAttempts());
 
+        setupExtensions();
+
         monitor->checkAlreadyConnectedDevices();
         LOG_INFO("AirPodsTrayApp initialized");
 
@@ -116,6 +332,7 @@ public:
 
         initializeDBus();
         initializeBluetooth();
+        autoConnectKnownAirPods();
     }
 
     ~AirPodsTrayApp() {
@@ -132,11 +349,158 @@ public:
     AutoStartManager *autoStartManager() const { return m_autoStartManager; }
     bool notificationsEnabled() const { return trayManager->notificationsEnabled(); }
     void setNotificationsEnabled(bool enabled) { trayManager->setNotificationsEnabled(enabled); }
+    bool trayShowsCaseBattery() const { return trayManager->showCaseBattery(); }
+    bool modeOsdEnabled() const { return m_settings->value("osd/modeChanges", false).toBool(); }
+    void setModeOsdEnabled(bool enabled)
+    {
+        if (enabled == modeOsdEnabled())
+            return;
+        m_settings->setValue("osd/modeChanges", enabled);
+        emit modeOsdEnabledChanged(enabled);
+    }
+    // 0 follows the system color scheme, 1 is light and 2 dark
+    int theme() const { return m_settings->value("appearance/theme", 0).toInt(); }
+    void setTheme(int theme)
+    {
+        if (theme == this->theme())
+            return;
+        static const QStringList names = {"Auto", "Light", "Dark"};
+        auditChange("Theme", names.value(this->theme()), names.value(theme));
+        m_settings->setValue("appearance/theme", theme);
+        applyTheme();
+        emit themeChanged();
+    }
+    int trayClickAction() const { return m_settings->value("tray/clickAction", OpenWindow).toInt(); }
+    int trayDoubleClickAction() const { return m_settings->value("tray/doubleClickAction", NoAction).toInt(); }
+    void setTrayClickAction(int action)
+    {
+        m_settings->setValue("tray/clickAction", action);
+        emit trayClickActionsChanged();
+    }
+    void setTrayDoubleClickAction(int action)
+    {
+        m_settings->setValue("tray/doubleClickAction", action);
+        trayManager->setDoubleClickEnabled(action != NoAction);
+        emit trayClickActionsChanged();
+    }
+    void setTrayShowsCaseBattery(bool enabled) { trayManager->setShowCaseBattery(enabled); }
     int retryAttempts() const { return m_retryAttempts; }
     bool hideOnStart() const { return m_hideOnStart; }
+    bool readOnly() const { return m_readOnly; }
     DeviceInfo *deviceInfo() const { return m_deviceInfo; }
     QString phoneMacStatus() const { return m_phoneMacStatus; }
     bool hearingAidEnabled() const { return m_deviceInfo->hearingAidEnabled(); }
+    QString trayIconFontStatus() const { return trayManager->iconFontStatus(); }
+    QVariantList bluezProperties() const { return m_bluezProperties; }
+    QStringList otherHosts() const
+    {
+        // Address of the adapter as BlueZ reported it in refreshAdapterProperties()
+        QString localAddress = m_adapterProperties.value("Address").toString();
+        QStringList hosts;
+        for (const auto &host : m_connectedHosts)
+        {
+            if (host.address.compare(localAddress, Qt::CaseInsensitive) != 0)
+                hosts << m_hostTypes.value(host.address, host.address);
+        }
+        return hosts;
+    }
+    // Filled by refreshAudioDevices(), which the Other Devices page calls when it opens
+    QVariantList audioDevices() const { return m_audioDevices; }
+    // Paired AirPods in startup order, each with its autoConnect setting
+    QVariantList knownAirPods() const { return m_knownAirPods; }
+    ConnectionTimeline *timeline() const { return m_timeline; }
+    BatteryHistory *batteryHistory() const { return m_batteryHistory; }
+    ControlServer *controlServer() const { return m_controlServer; }
+    // Only available with --debug
+    SyntheticDevices *syntheticDevices() const { return m_syntheticDevices; }
+    SettingsAuditLog *settingsLog() const { return m_settingsLog; }
+    ConversationStats *conversationStats() const { return m_conversationStats; }
+    HeadTracking *headTracking() const { return m_headTracking; }
+    LatencyTest *latencyTest() const { return m_latencyTest; }
+    HeadsetPower *headsetPower() const { return m_headsetPower; }
+    bool deviceLoading() const { return m_deviceLoading; }
+    QString connectionConflict() const { return m_connectionConflict; }
+    QString lastDeviceAddress() const { return m_settings->value("device/lastAddress").toString(); }
+    QString currentOrLastAddress() const
+    {
+        return m_deviceInfo->bluetoothAddress().isEmpty() ? lastDeviceAddress() : m_deviceInfo->bluetoothAddress();
+    }
+    QString undoDescription() const { return m_undoQueue.isEmpty() ? QString() : m_undoQueue.last().description; }
+    int pendingWrites() const { return m_pendingWrites.size(); }
+    // Empty once dismissed, until a dependency that wasn't missing before goes missing
+    QStringList missingDependencies() const
+    {
+        QStringList dismissed = m_settings->value("general/dismissedDependencies").toStringList();
+        for (const QString &reason : m_missingDependencies)
+            if (!dismissed.contains(reason))
+                return m_missingDependencies;
+        return {};
+    }
+    bool adapterAvailable() const { return !m_adapterProperties.isEmpty(); }
+    bool showWindowOnConnect() const { return m_settings->value("window/showOnConnect", false).toBool(); }
+    bool adapterPowered() const { return m_adapterProperties.value("Powered").toBool(); }
+    bool adapterDiscoverable() const { return m_adapterProperties.value("Discoverable").toBool(); }
+    bool adapterPairable() const { return m_adapterProperties.value("Pairable").toBool(); }
+    bool outOfRangeAlert() const { return loadOutOfRangeAlert(m_deviceInfo->bluetoothAddress()); }
+    bool switchDefaultOutput() const
+    {
+        QString address = m_deviceInfo->bluetoothAddress();
+        return !address.isEmpty() && m_settings->value(defaultOutputKey(address), false).toBool();
+    }
+    bool restoreVolume() const { return m_settings->value("audio/restoreVolume", true).toBool(); }
+    bool moveStreamsToDefaultOutput() const { return m_settings->value("defaultOutput/moveStreams", false).toBool(); }
+    bool outOfRangeAlertSound() const { return m_settings->value("lostMode/playSound", true).toBool(); }
+    bool lastSeenEnabled() const { return m_settings->value("lastSeen/enabled", false).toBool(); }
+    // Sound theme names for the connected, disconnected and lowBattery events, empty plays nothing
+    QVariantMap eventSounds() const
+    {
+        QVariantMap sounds;
+        for (const QString &event : {QStringLiteral("connected"), QStringLiteral("disconnected"), QStringLiteral("lowBattery")})
+            sounds.insert(event, m_settings->value("sounds/" + event).toString());
+        return sounds;
+    }
+    QVariantMap lastSeen() const
+    {
+        if (!m_settings->contains("lastSeen/time"))
+            return {};
+        return {{"name", m_settings->value("lastSeen/name")},
+                {"time", m_settings->value("lastSeen/time").toDateTime().toString("yyyy-MM-dd HH:mm")},
+                {"latitude", m_settings->value("lastSeen/latitude")},
+                {"longitude", m_settings->value("lastSeen/longitude")}};
+    }
+
+    // Runs a setter on behalf of something other than the window, for the settings change log
+    template <typename Apply>
+    void withChangeSource(const QString &source, Apply &&apply)
+    {
+        QString previous = std::exchange(m_changeSource, source);
+        apply();
+        m_changeSource = previous;
+    }
+    QVariantList audioCodecs() const { return m_audioCodecs; }
+    QStringList recentPackets() const { return m_recentPackets; }
+    QVariantMap diagnostics() const { return m_diagnostics; }
+    QString activeAudioCodec() const { return m_activeAudioCodec; }
+    int deviceVolume() const { return m_deviceVolume; }
+    int hostVolume() const { return m_hostVolume; }
+    bool volumeLock() const { return m_volumeSyncTimer.isActive(); }
+    int conversationReleaseDelay() const { return mediaController->conversationReleaseDelay(); }
+    bool announceCalls() const { return m_settings->value("calls/announce", false).toBool(); }
+    // What the Android app reports through KDE Connect, empty when no phone has the AirPods
+    QString phoneDeviceName() const { return m_kdeConnectRelay->name(); }
+    QString phoneBatteryStatus() const { return m_kdeConnectRelay->status(); }
+
+    QJsonArray knownDevicesJson() const
+    {
+        QJsonArray synthetic = m_syntheticDevices ? m_syntheticDevices->toJson() : QJsonArray();
+        if (m_deviceInfo->bluetoothAddress().isEmpty())
+            return synthetic;
+
+        QJsonArray devices{m_deviceInfo->apiJson(areAirpodsConnected())};
+        for (const QJsonValue &value : synthetic)
+            devices.append(value);
+        return devices;
+    }
 
 private:
     bool debugMode;
@@ -151,6 +515,168 @@ private:
 
     void initializeDBus() { }
 
+    // Read-only mode also covers changes that don't go through the AirPods, to the adapter, codec and settings
+    bool blockedByReadOnly(const char *action) const
+    {
+        if (m_readOnly)
+            LOG_WARN("Read-only mode, blocked " << action);
+        return m_readOnly;
+    }
+
+    // Manual themes override the system color scheme, the system accent color is kept either way
+    void applyTheme()
+    {
+        if (!m_appearance)
+            return;
+
+        SystemAppearance::ColorScheme scheme = m_appearance->colorScheme();
+        if (theme() == 1)
+            scheme = SystemAppearance::PreferLight;
+        else if (theme() == 2)
+            scheme = SystemAppearance::PreferDark;
+
+        QPalette palette = m_defaultPalette;
+        if (scheme == SystemAppearance::PreferDark)
+            palette = SystemAppearance::darkPalette();
+        else if (scheme == SystemAppearance::PreferLight)
+            palette = QPalette(QColor(0xef, 0xef, 0xef));
+
+        QColor accent = m_appearance->accentColor();
+        if (accent.isValid())
+        {
+            palette.setColor(QPalette::Highlight, accent);
+#if QT_VERSION >= QT_VERSION_CHECK(6, 6, 0)
+            palette.setColor(QPalette::Accent, accent);
+#endif
+        }
+        QGuiApplication::setPalette(palette);
+    }
+
+    // VmRSS from /proc, 0 where that isn't available
+    static qint64 residentMemoryKb()
+    {
+        QFile status("/proc/self/status");
+        if (!status.open(QIODevice::ReadOnly | QIODevice::Text))
+            return 0;
+        static const QRegularExpression rss("^VmRSS:\\s+(\\d+) kB", QRegularExpression::MultilineOption);
+        return rss.match(QString::fromLatin1(status.readAll())).captured(1).toLongLong();
+    }
+
+    // Device events are published to both external plugins and scripts, which share the same commands
+    void publishEvent(const QString &event, const QJsonObject &data)
+    {
+        m_pluginManager->sendEvent(event, data);
+        m_scriptHost->dispatch(event, data);
+        if (m_dbusService)
+            m_dbusService->refresh();
+    }
+
+    void setupExtensions()
+    {
+        m_pluginManager = new PluginManager(this);
+        m_scriptHost = new ScriptHost(this);
+        connect(m_pluginManager, &PluginManager::commandReceived, this, &AirPodsTrayApp::handleExtensionCommand);
+        connect(m_scriptHost, &ScriptHost::commandReceived, this, &AirPodsTrayApp::handleExtensionCommand);
+
+        connect(this, &AirPodsTrayApp::airPodsStatusChanged, this, [this]()
+        {
+            publishEvent("connection", {{"connected", areAirpodsConnected()},
+                                        {"address", m_deviceInfo->bluetoothAddress()},
+                                        {"name", m_deviceInfo->deviceName()}});
+        });
+        connect(m_deviceInfo, &DeviceInfo::batteryStatusChanged, this, [this]()
+        {
+            Battery *battery = m_deviceInfo->getBattery();
+            publishEvent("battery", {{"left", battery->getState(Battery::Component::Left).level},
+                                     {"right", battery->getState(Battery::Component::Right).level},
+                                     {"case", battery->getState(Battery::Component::Case).level}});
+        });
+        connect(m_deviceInfo, &DeviceInfo::noiseControlModeChangedInt, this, [this](int mode)
+        {
+            publishEvent("noiseControlMode", {{"mode", mode}});
+        });
+        connect(m_deviceInfo, &DeviceInfo::conversationalAwarenessChanged, this, [this](bool enabled)
+        {
+            publishEvent("conversationalAwareness", {{"enabled", enabled}});
+        });
+        connect(m_callAnnouncer, &CallAnnouncer::incomingCall, this, &AirPodsTrayApp::announceCall);
+        connect(m_deviceInfo->getEarDetection(), &EarDetection::statusChanged, this, [this]()
+        {
+            QMetaEnum statusEnum = QMetaEnum::fromType<EarDetection::EarDetectionStatus>();
+            EarDetection *earDetection = m_deviceInfo->getEarDetection();
+            publishEvent("earDetection", {{"primary", statusEnum.valueToKey(static_cast<int>(earDetection->getprimaryStatus()))},
+                                          {"secondary", statusEnum.valueToKey(static_cast<int>(earDetection->getsecondaryStatus()))}});
+        });
+
+        m_pluginManager->loadPlugins();
+        m_scriptHost->loadScripts();
+
+        if (m_settings->value("api/enabled", false).toBool())
+        {
+            m_httpApi = new HttpApi([this]() { return knownDevicesJson(); }, this);
+            connect(m_httpApi, &HttpApi::commandReceived, this, &AirPodsTrayApp::handleExtensionCommand);
+            m_httpApi->setReadOnly(m_readOnly);
+            m_httpApi->start(*m_settings, m_settings->value("api/port", 8765).toUInt());
+        }
+
+        if (m_settings->value("dbus/enabled", true).toBool())
+        {
+            m_dbusService = new DBusService([this]() { return knownDevicesJson(); }, this);
+            connect(m_dbusService, &DBusService::commandReceived, this, &AirPodsTrayApp::handleExtensionCommand);
+            m_dbusService->start();
+        }
+
+        m_controlServer = new ControlServer({[this]() { return statusReport(); },
+                                             [this]() { return batteryReport(); },
+                                             [this]() { return knownDevicesJson(); },
+                                             [this](const QDate &from, const QDate &to, bool json)
+                                             { return m_batteryHistory->exportData(from, to, json); }},
+                                            this);
+        connect(m_controlServer, &ControlServer::commandReceived, this, &AirPodsTrayApp::handleExtensionCommand);
+        connect(m_controlServer, &ControlServer::reopenRequested, this, &AirPodsTrayApp::onOpenApp);
+        connect(m_controlServer, &ControlServer::connectRequested, this, [this]()
+                {
+                    if (!areAirpodsConnected())
+                        toggleDeviceConnection(); });
+        m_controlServer->setReadOnly(m_readOnly);
+        m_controlServer->start(*m_settings);
+    }
+
+    void handleExtensionCommand(const QString &source, const QJsonObject &command)
+    {
+        withChangeSource(source, [&]() { applyExtensionCommand(source, command); });
+    }
+
+    void applyExtensionCommand(const QString &source, const QJsonObject &command)
+    {
+        QString name = command.value("command").toString();
+        LOG_DEBUG(source << " sent command " << name);
+
+        if (name == "setNoiseControlMode")
+        {
+            // Commands for a synthetic device stay with it
+            QString address = command.value("address").toString();
+            if (!m_syntheticDevices || !m_syntheticDevices->setMode(address, command.value("mode").toInt()))
+                setNoiseControlModeInt(command.value("mode").toInt());
+        }
+        else if (name == "setConversationalAwareness")
+        {
+            setConversationalAwareness(command.value("enabled").toBool());
+        }
+        else if (name == "setAdaptiveNoiseLevel")
+        {
+            setAdaptiveNoiseLevel(command.value("level").toInt());
+        }
+        else if (name == "notify")
+        {
+            trayManager->showNotification(command.value("title").toString(), command.value("message").toString());
+        }
+        else
+        {
+            LOG_WARN(source << " sent unknown command: " << name);
+        }
+    }
+
     bool isAirPodsDevice(const QBluetoothDeviceInfo &device)
     {
         return device.serviceUuids().contains(QBluetoothUuid("74ec2172-0bad-4d01-8f77-997b2be0722a"));
@@ -173,6 +699,142 @@ private:
         }
     }
 
+    // Destructive actions register how to revert them, offered in a toast until the entry expires
+    void pushUndo(const QString &description, std::function<void()> revert)
+    {
+        if (m_applyingUndo)
+            return;
+
+        m_undoQueue.append({description, std::move(revert), QDateTime::currentDateTime()});
+        if (m_undoQueue.size() > MaxUndoEntries)
+            m_undoQueue.removeFirst();
+        QTimer::singleShot(UndoTimeoutMs, Qt::PreciseTimer, this, [this]()
+                           {
+                               QDateTime cutoff = QDateTime::currentDateTime().addMSecs(-UndoTimeoutMs);
+                               qsizetype before = m_undoQueue.size();
+                               m_undoQueue.removeIf([&cutoff](const UndoEntry &entry) { return entry.time <= cutoff; });
+                               if (m_undoQueue.size() != before)
+                                   emit undoChanged(); });
+        emit undoChanged();
+    }
+
+    void setDeviceLoading(bool loading)
+    {
+        if (m_deviceLoading == loading)
+            return;
+        m_deviceLoading = loading;
+        emit deviceLoadingChanged(loading);
+
+        // Send what was changed while the channel was down once the AirPods reported their state
+        if (!loading && m_handshakeAcknowledged && !m_pendingWrites.isEmpty())
+        {
+            m_appliedPendingWrites = 0;
+            flushPendingWrites();
+        }
+    }
+
+    // Control commands sent while the channel is down are kept for a while, a newer command
+    // for the same setting replaces the queued one
+    void queuePendingWrite(const QByteArray &packet, const QString &logMessage)
+    {
+        QByteArray identifier = packet.left(ControlCommand::HEADER.size() + 1);
+        m_pendingWrites.removeIf([&identifier](const PendingWrite &write) { return write.packet.startsWith(identifier); });
+        m_pendingWrites.append({packet, logMessage, QDateTime::currentDateTime()});
+        if (m_pendingWrites.size() > MaxPendingWrites)
+            m_pendingWrites.removeFirst();
+        LOG_INFO("Channel is down, queued " << packet.toHex() << " until the AirPods reconnect");
+
+        QTimer::singleShot(PendingWriteTimeoutMs, this, [this]()
+                           {
+                               QDateTime cutoff = QDateTime::currentDateTime().addMSecs(-PendingWriteTimeoutMs);
+                               qsizetype before = m_pendingWrites.size();
+                               m_pendingWrites.removeIf([&cutoff](const PendingWrite &write) { return write.time <= cutoff; });
+                               qsizetype expired = before - m_pendingWrites.size();
+                               if (expired == 0)
+                                   return;
+                               LOG_WARN("Dropped " << expired << " queued commands, the AirPods didn't reconnect in time");
+                               trayManager->showNotification(tr("Changes not applied"),
+                                                             tr("%n change(s) couldn't be sent because the AirPods didn't reconnect", "", static_cast<int>(expired)));
+                               emit pendingWritesChanged(); });
+        emit pendingWritesChanged();
+    }
+
+    // One command at a time, so a burst of queued toggles doesn't flood the freshly opened channel
+    void flushPendingWrites()
+    {
+        if (m_pendingWrites.isEmpty() || !areAirpodsConnected())
+            return;
+
+        PendingWrite write = m_pendingWrites.takeFirst();
+        emit pendingWritesChanged();
+        if (writePacketToSocket(write.packet, write.logMessage))
+            m_appliedPendingWrites++;
+
+        if (!m_pendingWrites.isEmpty())
+        {
+            QTimer::singleShot(PendingWriteIntervalMs, this, &AirPodsTrayApp::flushPendingWrites);
+        }
+        else if (m_appliedPendingWrites > 0)
+        {
+            trayManager->showNotification(tr("Changes applied"),
+                                          tr("%n change(s) made while the AirPods were disconnected were applied", "", m_appliedPendingWrites));
+        }
+    }
+
+    void setConnectionConflict(const QString &conflict)
+    {
+        if (m_connectionConflict == conflict)
+            return;
+        m_connectionConflict = conflict;
+        if (!conflict.isEmpty())
+        {
+            LOG_WARN("AACP channel conflict: " << conflict);
+            trayManager->showNotification(tr("Can't control AirPods"), conflict);
+        }
+        emit connectionConflictChanged();
+    }
+
+    // The initial state dump is done once the AirPods sent both their metadata and battery levels
+    void finishLoadingIfComplete()
+    {
+        if (!m_deviceInfo->deviceName().isEmpty() && !m_deviceInfo->batteryStatus().isEmpty())
+            setDeviceLoading(false);
+    }
+
+    // Brings up the device page unless the window is already open, so a page the user is on isn't replaced
+    void showWindowForConnectedDevice()
+    {
+        QObject *rootObject = parent->rootObjects().isEmpty() ? nullptr : parent->rootObjects().first();
+        if (rootObject && rootObject->property("visible").toBool())
+            return;
+        onOpenApp();
+    }
+
+    void setAdapterProperty(const QString &name, bool value)
+    {
+        LOG_INFO("Setting adapter " << name << " to " << value);
+        monitor->setAdapterProperty(name, value);
+        // Refresh either way so a rejected change snaps the switch back
+        refreshAdapterProperties();
+    }
+
+    static QString noiseControlModeName(NoiseControlMode mode)
+    {
+        return QMetaEnum::fromType<NoiseControlMode>().valueToKey(static_cast<int>(mode));
+    }
+
+    void auditChange(const QString &setting, const QVariant &oldValue, const QVariant &newValue)
+    {
+        m_settingsLog->record(m_changeSource, setting, oldValue, newValue);
+    }
+
+    void recordSocketError(const QString &error)
+    {
+        m_socketErrorCount++;
+        m_lastSocketError = error;
+        m_lastSocketErrorTime = QDateTime::currentDateTime();
+    }
+
     void disconnectDevice(const QString &devicePath) {
         LOG_INFO("Disconnecting device at " << devicePath);
     }
@@ -193,8 +855,14 @@ public slots:
             return;
         }
         LOG_INFO("Setting noise control mode to: " << mode);
-        QByteArray packet = AirPodsPackets::NoiseControl::getPacketForMode(mode);
-        writePacketToSocket(packet, "Noise control mode packet written: ");
+        QByteArray packet = m_modeMapping.packetFor(mode);
+        if (writePacketToSocket(packet, "Noise control mode packet written: "))
+        {
+            // The AirPods echo the new mode back, don't log that as a change made by the buds
+            m_pendingNoiseControlMode = mode;
+            auditChange("Listening mode", noiseControlModeName(m_deviceInfo->noiseControlMode()), noiseControlModeName(mode));
+            trayManager->recordModeChange(mode, m_changeSource);
+        }
     }
     void setNoiseControlModeInt(int mode)
     {
@@ -212,7 +880,9 @@ public slots:
         QByteArray packet = enabled ? AirPodsPackets::ConversationalAwareness::ENABLED
                                     : AirPodsPackets::ConversationalAwareness::DISABLED;
 
-        writePacketToSocket(packet, "Conversational awareness packet written: ");
+        if (!writePacketToSocket(packet, "Conversational awareness packet written: "))
+            return;
+        auditChange("Conversational awareness", m_deviceInfo->conversationalAwareness(), enabled);
         m_deviceInfo->setConversationalAwareness(enabled);
     }
 
@@ -230,6 +900,7 @@ public slots:
 
         if (writePacketToSocket(packet, "One Bud ANC mode packet written: "))
         {
+            auditChange("One bud ANC mode", m_deviceInfo->oneBudANCMode(), enabled);
             m_deviceInfo->setOneBudANCMode(enabled);
         }
         else
@@ -238,6 +909,149 @@ public slots:
         }
     }
 
+    void setAllowOffOption(bool allowed)
+    {
+        if (m_deviceInfo->allowOffOption() == allowed)
+        {
+            return;
+        }
+
+        LOG_INFO("Setting allow Off option to: " << (allowed ? "enabled" : "disabled"));
+        QByteArray packet = allowed ? AirPodsPackets::AllowOffOption::ENABLED
+                                    : AirPodsPackets::AllowOffOption::DISABLED;
+        if (writePacketToSocket(packet, "Allow Off option packet written: "))
+        {
+            auditChange("Allow Off option", m_deviceInfo->allowOffOption(), allowed);
+            m_deviceInfo->setAllowOffOption(allowed);
+        }
+        else
+        {
+            // Keep the tray checkbox in sync with the actual state
+            trayManager->updateAllowOffOption(m_deviceInfo->allowOffOption());
+        }
+    }
+
+    void setCallControlsFlipped(bool flipped)
+    {
+        if (m_deviceInfo->callControlsReported() && m_deviceInfo->callControlsFlipped() == flipped)
+        {
+            return;
+        }
+
+        LOG_INFO("Setting call controls to: " << (flipped ? "press once to hang up" : "press once to mute"));
+        QByteArray packet = flipped ? AirPodsPackets::CallManagement::FLIPPED
+                                    : AirPodsPackets::CallManagement::DEFAULT;
+        if (writePacketToSocket(packet, "Call management packet written: "))
+        {
+            auditChange("Call controls flipped", m_deviceInfo->callControlsFlipped(), flipped);
+            m_deviceInfo->setCallControlsFlipped(flipped);
+        }
+    }
+
+    void setHeadTrackingActive(bool active)
+    {
+        if (active == m_headTracking->active())
+            return;
+        QByteArray packet = active ? AirPodsPackets::HeadTracking::START : AirPodsPackets::HeadTracking::STOP;
+        if (writePacketToSocket(packet, active ? "Start head tracking packet written: " : "Stop head tracking packet written: ") || !active)
+            m_headTracking->setActive(active);
+    }
+
+    void playTestTone(double pan) { TestTone::play(pan); }
+
+    void setShowWindowOnConnect(bool enabled)
+    {
+        if (enabled == showWindowOnConnect())
+            return;
+        m_settings->setValue("window/showOnConnect", enabled);
+        emit showWindowOnConnectChanged(enabled);
+    }
+
+    void setAdapterPowered(bool powered)
+    {
+        if (!blockedByReadOnly("switching the adapter power"))
+            setAdapterProperty("Powered", powered);
+    }
+    void setAdapterDiscoverable(bool discoverable)
+    {
+        if (!blockedByReadOnly("changing adapter discoverability"))
+            setAdapterProperty("Discoverable", discoverable);
+    }
+    void setAdapterPairable(bool pairable)
+    {
+        if (!blockedByReadOnly("changing adapter pairability"))
+            setAdapterProperty("Pairable", pairable);
+    }
+
+    void refreshAudioDevices()
+    {
+        m_audioDevices = monitor->audioDevices();
+        emit audioDevicesChanged();
+    }
+
+    void setAudioDeviceConnected(const QString &address, bool connected)
+    {
+        monitor->setDeviceConnected(address, connected);
+    }
+
+    void refreshKnownAirPods()
+    {
+        const QStringList priority = m_settings->value("autoConnect/priority").toStringList();
+        auto rank = [&priority](const QVariant &device)
+        {
+            qsizetype index = priority.indexOf(device.toMap().value("address").toString());
+            return index < 0 ? priority.size() : index;
+        };
+
+        QVariantList devices = monitor->pairedAirPods();
+        std::stable_sort(devices.begin(), devices.end(), [&rank](const QVariant &a, const QVariant &b)
+                         { return rank(a) < rank(b); });
+        for (QVariant &device : devices)
+        {
+            QVariantMap properties = device.toMap();
+            properties["autoConnect"] = m_settings->value(autoConnectKey(properties.value("address").toString()), false).toBool();
+            auto model = static_cast<AirPodsModel>(m_settings->value(modelKey(properties.value("address").toString()),
+                                                                     static_cast<int>(AirPodsModel::Unknown)).toInt());
+            properties["icon"] = getModelIcon(model).first;
+            device = properties;
+        }
+        m_knownAirPods = devices;
+        emit knownAirPodsChanged();
+    }
+
+    void setAutoConnect(const QString &address, bool enabled)
+    {
+        if (enabled == m_settings->value(autoConnectKey(address), false).toBool())
+            return;
+        auditChange("Connect " + address + " at startup", !enabled, enabled);
+        m_settings->setValue(autoConnectKey(address), enabled);
+        refreshKnownAirPods();
+    }
+
+    // Moves the AirPods one place earlier (-1) or later (1) in the startup order
+    void moveKnownAirPods(const QString &address, int offset)
+    {
+        QStringList order;
+        for (const QVariant &device : std::as_const(m_knownAirPods))
+            order << device.toMap().value("address").toString();
+        qsizetype from = order.indexOf(address);
+        qsizetype to = from + offset;
+        if (from < 0 || to < 0 || to >= order.size())
+            return;
+        order.move(from, to);
+        m_settings->setValue("autoConnect/priority", order);
+        refreshKnownAirPods();
+    }
+
+    void refreshAdapterProperties()
+    {
+        m_adapterProperties = monitor->adapterProperties();
+        emit adapterChanged();
+        emit otherHostsChanged();
+    }
+
+    void playFitCheckTone(double pan) { TestTone::playFitCheck(pan); }
+
     void setRetryAttempts(int attempts)
     {
         if (m_retryAttempts != attempts)
@@ -266,11 +1080,25 @@ public slots:
         if (m_deviceInfo->adaptiveNoiseLevel() != level && m_deviceInfo->adaptiveModeActive())
         {
             QByteArray packet = AirPodsPackets::AdaptiveNoise::getPacket(level);
-            writePacketToSocket(packet, "Adaptive noise level packet written: ");
+            if (!writePacketToSocket(packet, "Adaptive noise level packet written: "))
+                return;
+            auditChange("Adaptive noise level", m_deviceInfo->adaptiveNoiseLevel(), level);
             m_deviceInfo->setAdaptiveNoiseLevel(level);
         }
     }
 
+    void setChimeVolume(int volume)
+    {
+        volume = qBound(0, volume, 100);
+        if (m_deviceInfo->chimeVolume() != volume)
+        {
+            if (!writePacketToSocket(AirPodsPackets::ChimeVolume::getPacket(volume), "Chime volume packet written: "))
+                return;
+            auditChange("Tone volume", m_deviceInfo->chimeVolume(), volume);
+            m_deviceInfo->setChimeVolume(volume);
+        }
+    }
+
     void renameAirPods(const QString &newName)
     {
         if (newName.isEmpty())
@@ -293,6 +1121,9 @@ public slots:
         if (writePacketToSocket(packet, "Rename packet written: "))
         {
             LOG_INFO("Sent rename command for new name: " << newName);
+            auditChange("Name", m_deviceInfo->deviceName(), newName);
+            pushUndo(tr("Renamed to %1").arg(newName), [this, oldName = m_deviceInfo->deviceName()]()
+                     { renameAirPods(oldName); });
             m_deviceInfo->setDeviceName(newName);
         }
         else
@@ -301,41 +1132,173 @@ public slots:
         }
     }
 
-    void setEarDetectionBehavior(int behavior)
+    void undo()
     {
-        if (behavior == earDetectionBehavior())
-        {
-            LOG_INFO("Ear detection behavior is already set to: " << behavior);
+        if (m_undoQueue.isEmpty())
             return;
-        }
 
-        mediaController->setEarDetectionBehavior(static_cast<MediaController::EarDetectionBehavior>(behavior));
-        saveEarDetectionSettings();
-        emit earDetectionBehaviorChanged(behavior);
+        UndoEntry entry = m_undoQueue.takeLast();
+        LOG_INFO("Undoing: " << entry.description);
+        m_applyingUndo = true;
+        entry.revert();
+        m_applyingUndo = false;
+        emit undoChanged();
     }
 
-    void setCrossDeviceEnabled(bool enabled)
+    void dismissUndo()
     {
-        if (CrossDevice.isEnabled == enabled)
-        {
-            LOG_INFO("Cross-device feature is already " << (enabled ? "enabled" : "disabled"));
-            return;
-        }
-
-        CrossDevice.isEnabled = enabled;
-        saveCrossDeviceEnabled();
-        connectToPhone();
-        emit crossDeviceEnabledChanged(enabled);
+        m_undoQueue.clear();
+        emit undoChanged();
     }
 
-    void setPhoneMac(const QString &mac)
+    // Seconds audio may play to the AirPods while neither bud is worn before warning, 0 is off
+    int outOfEarPlaybackDelay() const { return m_settings->value("outOfEarPlayback/warnAfter", 30).toInt(); }
+    bool outOfEarPlaybackPause() const { return m_settings->value("outOfEarPlayback/pause", false).toBool(); }
+    void setOutOfEarPlaybackDelay(int seconds)
     {
-        if (mac.isEmpty()) {
-            LOG_WARN("Empty MAC provided, ignoring");
-            m_phoneMacStatus = QStringLiteral("No MAC provided (ignoring)");
-            emit phoneMacStatusChanged();
+        if (seconds == outOfEarPlaybackDelay())
             return;
-        }
+        auditChange("Out of ear playback warning", outOfEarPlaybackDelay(), seconds);
+        m_settings->setValue("outOfEarPlayback/warnAfter", seconds);
+        updateOutOfEarPlaybackWatch();
+        emit outOfEarPlaybackChanged();
+    }
+    void setOutOfEarPlaybackPause(bool pause)
+    {
+        if (pause == outOfEarPlaybackPause())
+            return;
+        auditChange("Pause playback out of ear", outOfEarPlaybackPause(), pause);
+        m_settings->setValue("outOfEarPlayback/pause", pause);
+        emit outOfEarPlaybackChanged();
+    }
+
+    int wearReminderInterval() const { return m_settings->value("wearReminder/interval", 0).toInt(); }
+    QString wearReminderQuietStart() const { return m_settings->value("wearReminder/quietStart").toString(); }
+    QString wearReminderQuietEnd() const { return m_settings->value("wearReminder/quietEnd").toString(); }
+    void setWearReminderInterval(int minutes)
+    {
+        if (minutes == wearReminderInterval())
+            return;
+        auditChange("Wear reminder interval", wearReminderInterval(), minutes);
+        m_settings->setValue("wearReminder/interval", minutes);
+        applyWearReminderSettings();
+    }
+    // HH:mm, empty for no quiet hours
+    void setWearReminderQuietStart(const QString &time)
+    {
+        m_settings->setValue("wearReminder/quietStart", time.trimmed());
+        applyWearReminderSettings();
+    }
+    void setWearReminderQuietEnd(const QString &time)
+    {
+        m_settings->setValue("wearReminder/quietEnd", time.trimmed());
+        applyWearReminderSettings();
+    }
+
+    bool lowBatteryAnnouncement() const { return m_settings->value("lowBattery/announce", false).toBool(); }
+    void setLowBatteryAnnouncement(bool enabled)
+    {
+        if (enabled == lowBatteryAnnouncement())
+            return;
+        auditChange("Announce low battery", lowBatteryAnnouncement(), enabled);
+        m_settings->setValue("lowBattery/announce", enabled);
+        emit lowBatteryAnnouncementChanged(enabled);
+    }
+
+    void setEventSound(const QString &event, const QString &sound)
+    {
+        if (!eventSounds().contains(event) || eventSounds().value(event).toString() == sound.trimmed())
+            return;
+        auditChange("Sound for " + event, eventSounds().value(event), sound.trimmed());
+        m_settings->setValue("sounds/" + event, sound.trimmed());
+        emit eventSoundsChanged();
+    }
+
+    void previewEventSound(const QString &sound) { SoundTheme::play(sound.trimmed()); }
+
+    void setIgnoreCaseBattery(bool ignore)
+    {
+        QString address = m_deviceInfo->bluetoothAddress();
+        if (address.isEmpty() || ignore == m_deviceInfo->ignoreCase())
+            return;
+        auditChange("Ignore case battery", m_deviceInfo->ignoreCase(), ignore);
+        m_settings->setValue(ignoreCaseKey(address), ignore);
+        m_deviceInfo->setIgnoreCase(ignore);
+    }
+
+    // Next listening mode in the order the AirPods cycle through them, skipping Off unless allowed
+    void cycleNoiseControlMode()
+    {
+        int mode = m_deviceInfo->noiseControlModeInt();
+        do
+        {
+            mode = (mode + 1) % (static_cast<int>(NoiseControlMode::MaxValue) + 1);
+        } while (mode == static_cast<int>(NoiseControlMode::Off) && !m_deviceInfo->allowOffOption());
+        setNoiseControlModeInt(mode);
+    }
+
+    // Asks the AirPods to send their full state again
+    void refreshDeviceState()
+    {
+        writePacketToSocket(AirPodsPackets::Connection::REQUEST_NOTIFICATIONS, "Request notifications packet written: ");
+    }
+
+    void toggleDeviceConnection()
+    {
+        QString address = areAirpodsConnected() ? m_deviceInfo->bluetoothAddress() : lastDeviceAddress();
+        if (!address.isEmpty())
+            monitor->setDeviceConnected(address, !areAirpodsConnected());
+    }
+
+    void dismissDependencyBanner()
+    {
+        m_settings->setValue("general/dismissedDependencies", m_missingDependencies);
+        emit missingDependenciesChanged();
+    }
+
+    void retryConnection()
+    {
+        if (!m_conflictDevice.isValid())
+            return;
+        setConnectionConflict(QString());
+        connectToDevice(m_conflictDevice);
+    }
+
+    void setEarDetectionBehavior(int behavior)
+    {
+        if (behavior == earDetectionBehavior())
+        {
+            LOG_INFO("Ear detection behavior is already set to: " << behavior);
+            return;
+        }
+
+        mediaController->setEarDetectionBehavior(static_cast<MediaController::EarDetectionBehavior>(behavior));
+        saveEarDetectionSettings();
+        emit earDetectionBehaviorChanged(behavior);
+    }
+
+    void setCrossDeviceEnabled(bool enabled)
+    {
+        if (CrossDevice.isEnabled == enabled)
+        {
+            LOG_INFO("Cross-device feature is already " << (enabled ? "enabled" : "disabled"));
+            return;
+        }
+
+        CrossDevice.isEnabled = enabled;
+        saveCrossDeviceEnabled();
+        connectToPhone();
+        emit crossDeviceEnabledChanged(enabled);
+    }
+
+    void setPhoneMac(const QString &mac)
+    {
+        if (mac.isEmpty()) {
+            LOG_WARN("Empty MAC provided, ignoring");
+            m_phoneMacStatus = QStringLiteral("No MAC provided (ignoring)");
+            emit phoneMacStatusChanged();
+            return;
+        }
 
         // Basic MAC address validation (accepts formats like AA:BB:CC:DD:EE:FF, AABBCCDDEEFF, AA-BB-CC-DD-EE-FF)
         QRegularExpression re("^([0-9A-Fa-f]{2}([-:]?)){5}[0-9A-Fa-f]{2}$");
@@ -379,25 +1342,308 @@ public slots:
         QByteArray packet = enabled ? AirPodsPackets::HearingAid::ENABLED
                                     : AirPodsPackets::HearingAid::DISABLED;
 
-        writePacketToSocket(packet, "Hearing aid packet written: ");
+        if (!writePacketToSocket(packet, "Hearing aid packet written: "))
+            return;
+        auditChange("Hearing aid", m_deviceInfo->hearingAidEnabled(), enabled);
         m_deviceInfo->setHearingAidEnabled(enabled);
     }
 
+    void refreshAudioCodecs()
+    {
+        m_audioCodecs.clear();
+        for (const auto &codec : mediaController->availableCodecs())
+        {
+            m_audioCodecs.append(QVariantMap{{"name", codec.first}, {"description", codec.second}});
+        }
+        m_activeAudioCodec = mediaController->activeCodec();
+        emit audioCodecsChanged();
+    }
+
+    void setAudioCodec(const QString &codec)
+    {
+        if (codec == m_activeAudioCodec || blockedByReadOnly("switching the audio codec"))
+            return;
+
+        m_settings->setValue("audio/codec", codec);
+        mediaController->setPreferredCodec(codec);
+        if (!mediaController->setCodec(codec))
+        {
+            LOG_ERROR("Failed to switch A2DP codec to " << codec);
+        }
+        // The sound server renegotiates asynchronously
+        QTimer::singleShot(1000, this, &AirPodsTrayApp::refreshAudioCodecs);
+    }
+
+    // Reads both volumes; with the lock enabled a host side change is written through to the AirPods,
+    // except in read-only mode
+    void refreshVolumes()
+    {
+        int host = mediaController->airPodsVolume();
+        if (!m_readOnly && volumeLock() && host >= 0 && m_hostVolume >= 0 && qAbs(host - m_hostVolume) > 1 && qAbs(host - m_deviceVolume) > 1)
+        {
+            LOG_DEBUG("Host volume changed to " << host << "%, syncing AirPods volume");
+            monitor->setTransportVolume(m_deviceInfo->bluetoothAddress(), host * 127 / 100);
+        }
+        m_hostVolume = host;
+
+        int transport = monitor->transportVolume(m_deviceInfo->bluetoothAddress());
+        m_deviceVolume = transport < 0 ? -1 : qRound(transport * 100 / 127.0);
+        emit volumesChanged();
+    }
+
+    void setVolumeLock(bool locked)
+    {
+        if (locked == volumeLock())
+            return;
+
+        m_settings->setValue("audio/lockVolume", locked);
+        if (locked)
+            m_volumeSyncTimer.start();
+        else
+            m_volumeSyncTimer.stop();
+        emit volumeLockChanged(locked);
+    }
+
+    void setAnnounceCalls(bool enabled)
+    {
+        if (enabled == announceCalls())
+            return;
+        auditChange("Announce calls", announceCalls(), enabled);
+        m_settings->setValue("calls/announce", enabled);
+        emit announceCallsChanged(enabled);
+    }
+
+    void setConversationReleaseDelay(int ms)
+    {
+        ms = qBound(0, ms, 10000);
+        if (ms == conversationReleaseDelay())
+            return;
+
+        auditChange("Conversation release delay", conversationReleaseDelay(), ms);
+        m_settings->setValue("conversationalAwareness/releaseDelay", ms);
+        mediaController->setConversationReleaseDelay(ms);
+        emit conversationReleaseDelayChanged(ms);
+    }
+
+    // Only query BlueZ while the Advanced section on the settings page is open
+    void setBluezPropertiesWatched(bool watched)
+    {
+        m_watchBluezProperties = watched;
+        if (watched)
+            refreshBluezProperties();
+    }
+
+    void refreshBluezProperties()
+    {
+        QVariantMap properties = monitor->deviceProperties(m_deviceInfo->bluetoothAddress());
+        m_bluezProperties.clear();
+        for (auto it = properties.constBegin(); it != properties.constEnd(); ++it)
+        {
+            QString value;
+            if (it.value().typeId() == qMetaTypeId<QDBusArgument>())
+                value = QStringLiteral("<%1>").arg(it.value().value<QDBusArgument>().currentSignature());
+            else if (it.value().typeId() == QMetaType::QStringList)
+                value = it.value().toStringList().join("\n");
+            else
+                value = it.value().toString();
+            m_bluezProperties.append(QVariantMap{{"name", it.key()}, {"value", value}});
+        }
+        emit bluezPropertiesChanged();
+    }
+
+    bool exportDevices(const QUrl &fileUrl, bool includeSerials, bool includeKeys)
+    {
+        QFile file(fileUrl.toLocalFile());
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate))
+        {
+            LOG_ERROR("Failed to open export file: " << file.errorString());
+            return false;
+        }
+
+        QMap<QString, QString> pairedNames;
+        for (const QVariant &device : monitor->pairedAirPods())
+            pairedNames.insert(device.toMap().value("address").toString(), device.toMap().value("name").toString());
+        QJsonObject root = DeviceStore::exportJson(*m_settings, pairedNames, currentOrLastAddress(),
+                                                   m_deviceInfo->toJson(includeSerials, includeKeys));
+        file.write(QJsonDocument(root).toJson());
+        LOG_INFO("Exported " << root.value("devices").toArray().size() << " known devices to " << file.fileName()
+                             << (includeSerials ? " with serials" : " without serials")
+                             << (includeKeys ? " and with keys" : " and without keys"));
+        return true;
+    }
+
+    bool importDevices(const QUrl &fileUrl)
+    {
+        if (blockedByReadOnly("importing devices"))
+            return false;
+
+        QFile file(fileUrl.toLocalFile());
+        if (!file.open(QIODevice::ReadOnly))
+        {
+            LOG_ERROR("Failed to open import file: " << file.errorString());
+            return false;
+        }
+
+        QJsonParseError error;
+        QJsonDocument document = QJsonDocument::fromJson(file.readAll(), &error);
+        if (error.error != QJsonParseError::NoError || !document.isObject())
+        {
+            LOG_ERROR("Invalid devices file: " << error.errorString());
+            return false;
+        }
+        if (document.object().value("devices").toArray().isEmpty())
+        {
+            LOG_WARN("No devices found in " << file.fileName());
+            return false;
+        }
+
+        QString current = currentOrLastAddress();
+        DeviceStore::ImportResult result = DeviceStore::importJson(*m_settings, document.object(), current);
+        if (!result.infoAddress.isEmpty())
+        {
+            m_deviceInfo->fromJson(result.info);
+            m_deviceInfo->saveToSettings(*m_settings);
+            if (current.isEmpty())
+            {
+                m_settings->setValue("device/lastAddress", result.infoAddress);
+                emit lastDeviceAddressChanged();
+            }
+        }
+        refreshKnownAirPods();
+
+        LOG_INFO("Imported " << result.imported << " known devices from " << file.fileName() << ", skipped " << result.skipped.size());
+        if (!result.skipped.isEmpty())
+        {
+            LOG_WARN("Skipped device entries without a valid address: " << result.skipped.join(", "));
+            trayManager->showNotification(tr("Devices imported"),
+                                          tr("Imported %1 devices, skipped %2 without a valid address: %3")
+                                              .arg(result.imported)
+                                              .arg(result.skipped.size())
+                                              .arg(result.skipped.join(", ")));
+        }
+        return result.imported > 0;
+    }
+
+    // Polled by the diagnostics page while it's open
+    void refreshDiagnostics()
+    {
+        qint64 seconds = m_uptime.elapsed() / 1000;
+        m_diagnostics = {
+            {"uptime", QString("%1h %2m %3s").arg(seconds / 3600).arg((seconds / 60) % 60).arg(seconds % 60)},
+            {"packetsPerMinute", m_packetRate.perMinute(m_uptime.elapsed())},
+            {"reconnects", qMax(0, m_connectionCount - 1)},
+            {"writeQueue", pendingWrites()},
+            {"channelErrors", m_socketErrorCount},
+            {"lastPacket", m_lastPacketTime.isValid() ? m_lastPacketTime.toString("HH:mm:ss") : tr("never")},
+            {"memoryMb", residentMemoryKb() / 1024.0},
+        };
+        emit diagnosticsChanged();
+    }
+
+    QString statusReport() const
+    {
+        auto formatTime = [](const QDateTime &time) {
+            return time.isValid() ? time.toString(Qt::ISODate) : QStringLiteral("never");
+        };
+
+        qint64 seconds = m_uptime.elapsed() / 1000;
+        QString uptime = QString("%1h %2m %3s").arg(seconds / 3600).arg((seconds / 60) % 60).arg(seconds % 60);
+
+        QBluetoothLocalDevice localDevice;
+        QString adapterState;
+        if (!localDevice.isValid())
+            adapterState = "unavailable";
+        else if (localDevice.hostMode() == QBluetoothLocalDevice::HostPoweredOff)
+            adapterState = "powered off";
+        else
+            adapterState = "powered on (" + localDevice.address().toString() + ")";
+
+        QStringList lines;
+        lines << "Uptime: " + uptime;
+        lines << "Adapter: " + adapterState;
+        if (areAirpodsConnected())
+        {
+            lines << "Connected device: " + m_deviceInfo->deviceName() + " (" + m_deviceInfo->bluetoothAddress() + ")";
+            lines << "Battery: " + m_deviceInfo->batteryStatus();
+            lines << "Capabilities: " + m_deviceInfo->capabilities().toStringList().join(", ");
+        }
+        else
+        {
+            lines << "Connected device: none";
+        }
+        lines << "Last packet: " + formatTime(m_lastPacketTime);
+        lines << "Phone: " + QString(phoneSocket && phoneSocket->isOpen() ? "connected" : "not connected");
+        lines << QString("Channel errors: %1").arg(m_socketErrorCount);
+        if (!m_lastSocketError.isEmpty())
+        {
+            lines << "Last error: " + m_lastSocketError + " at " + formatTime(m_lastSocketErrorTime);
+        }
+        return lines.join('\n') + '\n';
+    }
+
+    // One line per reported component, for scripts and status bars
+    QString batteryReport() const
+    {
+        if (!areAirpodsConnected())
+            return "Not connected\n";
+
+        const QList<QPair<Battery::Component, QString>> components = {
+            {Battery::Component::Left, "Left"}, {Battery::Component::Right, "Right"},
+            {Battery::Component::Case, "Case"}, {Battery::Component::Headset, "Headset"}};
+        QStringList lines;
+        for (const auto &[component, name] : components)
+        {
+            Battery::BatteryState state = m_deviceInfo->getBattery()->getState(component);
+            if (state.status == Battery::BatteryStatus::Disconnected)
+                continue;
+            lines << QString("%1: %2%%3").arg(name).arg(state.level)
+                         .arg(state.status == Battery::BatteryStatus::Charging ? " (charging)" : "");
+        }
+        return lines.isEmpty() ? "Unknown\n" : lines.join('\n') + '\n';
+    }
+
     bool writePacketToSocket(const QByteArray &packet, const QString &logMessage)
     {
+        // Read-only mode still sets up the connection so the state can be displayed
+        if (m_readOnly && packet != AirPodsPackets::Connection::HANDSHAKE &&
+            packet != AirPodsPackets::Connection::SET_SPECIFIC_FEATURES &&
+            packet != AirPodsPackets::Connection::REQUEST_NOTIFICATIONS)
+        {
+            LOG_WARN("Read-only mode, not sending: " << packet.toHex());
+            return false;
+        }
+
         if (socket && socket->isOpen())
         {
             socket->write(packet);
-            LOG_DEBUG(logMessage << packet.toHex());
+            LOG_DEBUG(logMessage << packet.toHex() << PacketNames::describe(packet));
+            notePacket("→", packet);
             return true;
         }
         else
         {
+            if (packet.startsWith(ControlCommand::HEADER) && packet.size() > ControlCommand::HEADER.size() &&
+                !lastDeviceAddress().isEmpty())
+            {
+                queuePendingWrite(packet, logMessage);
+                return false;
+            }
             LOG_ERROR("Socket is not open, cannot write packet");
             return false;
         }
     }
 
+    // Last packets in both directions for the developer tools
+    void notePacket(const QString &direction, const QByteArray &packet)
+    {
+        QString name = PacketNames::describe(packet);
+        m_recentPackets.prepend(QString("%1 %2 %3").arg(QTime::currentTime().toString("HH:mm:ss"), direction,
+                                                        name.isEmpty() ? QString::fromLatin1(packet.toHex()) : name));
+        if (m_recentPackets.size() > MaxRecentPackets)
+            m_recentPackets.removeLast();
+        emit recentPacketsChanged();
+    }
+
     bool loadCrossDeviceEnabled() { return m_settings->value("crossdevice/enabled", false).toBool(); }
     void saveCrossDeviceEnabled() { m_settings->setValue("crossdevice/enabled", CrossDevice.isEnabled); }
 
@@ -407,11 +1653,33 @@ public slots:
     bool loadNotificationsEnabled() const { return m_settings->value("notifications/enabled", true).toBool(); }
     void saveNotificationsEnabled(bool enabled) { m_settings->setValue("notifications/enabled", enabled); }
 
+    void saveLastSeenLocation(double latitude, double longitude)
+    {
+        m_settings->setValue("lastSeen/name", m_lastSeenDeviceName);
+        m_settings->setValue("lastSeen/time", QDateTime::currentDateTime());
+        m_settings->setValue("lastSeen/latitude", latitude);
+        m_settings->setValue("lastSeen/longitude", longitude);
+        emit lastSeenChanged();
+    }
+
+    static QString outOfRangeAlertKey(const QString &address) { return DeviceStore::key("lostMode", address); }
+    static QString volumeKey(const QString &address) { return DeviceStore::key("volume", address); }
+    static QString defaultOutputKey(const QString &address) { return DeviceStore::key("defaultOutput", address); }
+    static QString ignoreCaseKey(const QString &address) { return DeviceStore::key("ignoreCase", address); }
+    static QString capabilitiesKey(const QString &address) { return DeviceStore::key("capabilities", address); }
+    static QString autoConnectKey(const QString &address) { return DeviceStore::key("autoConnect", address); }
+    static QString modelKey(const QString &address) { return DeviceStore::key("model", address); }
+    bool loadOutOfRangeAlert(const QString &address) const
+    {
+        return !address.isEmpty() && m_settings->value(outOfRangeAlertKey(address), false).toBool();
+    }
+
     int loadRetryAttempts() const { return m_settings->value("bluetooth/retryAttempts", 3).toInt(); }
     void saveRetryAttempts(int attempts) { m_settings->setValue("bluetooth/retryAttempts", attempts); }
 
     void onSystemGoingToSleep()
     {
+        m_systemSleeping = true;
         if (m_bleManager->isScanning())
         {
             LOG_INFO("Stopping BLE scan before going to sleep");
@@ -421,6 +1689,7 @@ public slots:
     void onSystemWakingUp()
     {
         LOG_INFO("System is waking up, starting ble scan");
+        m_systemSleeping = false;
         m_bleManager->startScan();
 
         // Check if AirPods are already connected and activate A2DP profile
@@ -437,97 +1706,494 @@ public slots:
             });
         }
 
-        // Also check for already connected devices via BlueZ
-        monitor->checkAlreadyConnectedDevices();
+        // Also check for already connected devices via BlueZ
+        monitor->checkAlreadyConnectedDevices();
+    }
+
+private slots:
+    // Pinned AirPods are tried in order until one connects, nothing happens if BlueZ already has one
+    void autoConnectKnownAirPods()
+    {
+        refreshKnownAirPods();
+        QStringList candidates;
+        for (const QVariant &device : std::as_const(m_knownAirPods))
+        {
+            QVariantMap properties = device.toMap();
+            if (properties.value("connected").toBool())
+                return;
+            if (properties.value("autoConnect").toBool())
+                candidates << properties.value("address").toString();
+        }
+        connectInOrder(candidates);
+    }
+
+    // The connection itself is picked up through BluetoothMonitor::deviceConnected
+    void connectInOrder(QStringList candidates)
+    {
+        if (candidates.isEmpty())
+            return;
+        QString address = candidates.takeFirst();
+        LOG_INFO("Connecting to " << address << " at startup");
+        m_timeline->record(address, "Auto-connect");
+        monitor->setDeviceConnected(address, true, [this, candidates](bool connected)
+                                    {
+                                        if (!connected)
+                                            connectInOrder(candidates);
+                                    });
+    }
+
+    // BlueZ signals changes for every device it sees, RSSI included, only listed ones are re-read
+    void refreshAudioDeviceIfListed(const QString &address)
+    {
+        bool listed = std::any_of(m_audioDevices.begin(), m_audioDevices.end(), [&address](const QVariant &device)
+                                  { return device.toMap().value("address").toString().compare(address, Qt::CaseInsensitive) == 0; });
+        if (listed)
+            refreshAudioDevices();
+    }
+
+    void runTrayAction(TrayAction action)
+    {
+        switch (action)
+        {
+        case OpenWindow:
+            onTrayIconActivated();
+            break;
+        case ToggleNoiseCancellation:
+            setNoiseControlMode(m_deviceInfo->noiseControlMode() == NoiseControlMode::NoiseCancellation
+                                    ? NoiseControlMode::Transparency
+                                    : NoiseControlMode::NoiseCancellation);
+            break;
+        case RefreshBattery:
+            refreshDeviceState();
+            break;
+        case NoAction:
+            break;
+        }
+    }
+
+    void onTrayIconActivated()
+    {
+        if (m_headless)
+        {
+            LOG_INFO("Running headless, not opening the window");
+            return;
+        }
+        // The main window is the root object, other top-level windows are the OSD and the pop-out
+        QQuickWindow *window = parent->rootObjects().isEmpty() ? nullptr : qobject_cast<QQuickWindow *>(parent->rootObjects().first());
+        if (window)
+        {
+            window->show();
+            window->raise();
+            window->requestActivate();
+        }
+        else
+        {
+            loadMainModule();
+        }
+    }
+
+    void onOpenApp()
+    {
+        if (m_headless)
+        {
+            LOG_INFO("Running headless, not opening the window");
+            return;
+        }
+        QObject *rootObject = parent->rootObjects().isEmpty() ? nullptr : parent->rootObjects().first();
+        if (rootObject) {
+            QMetaObject::invokeMethod(rootObject, "reopen", Q_ARG(QVariant, "app"));
+        }
+        else
+        {
+            loadMainModule();
+        }
+    }
+
+    void onOpenSettings()
+    {
+        if (m_headless)
+        {
+            LOG_INFO("Running headless, not opening the settings");
+            return;
+        }
+        QObject *rootObject = parent->rootObjects().isEmpty() ? nullptr : parent->rootObjects().first();
+        if (rootObject) {
+            QMetaObject::invokeMethod(rootObject, "reopen", Q_ARG(QVariant, "settings"));
+        }
+        else
+        {
+            loadMainModule();
+        }
+    }
+
+    void sendHandshake() {
+        LOG_INFO("Connected to device, sending initial packets");
+        writePacketToSocket(AirPodsPackets::Connection::HANDSHAKE, "Handshake packet written: ");
+    }
+
+    void bluezDeviceConnected(const QString &address, const QString &name)
+    {
+        QBluetoothDeviceInfo device(QBluetoothAddress(address), name, 0);
+        connectToDevice(device);
+
+        // After system reboot, AirPods might be connected but A2DP profile not active
+        // Attempt to activate A2DP profile after a delay to ensure connection is established
+        QTimer::singleShot(2000, this, [this, address]()
+        {
+            if (!address.isEmpty())
+            {
+                QString formattedAddress = address;
+                formattedAddress = formattedAddress.replace(":", "_");
+                mediaController->setConnectedDeviceMacAddress(formattedAddress);
+                mediaController->activateA2dpProfile();
+                LOG_INFO("A2DP profile activation attempted for newly connected device");
+            }
+        });
+    }
+
+    void onDeviceDisconnected(const QBluetoothAddress &address)
+    {
+        LOG_INFO("Device disconnected: " << address.toString());
+        m_timeline->record(address.toString(), "Disconnected");
+        if (socket)
+        {
+            LOG_WARN("Socket is still open, closing it");
+            socket->close();
+            socket = nullptr;
+        }
+        if (phoneSocket && phoneSocket->isOpen())
+        {
+            phoneSocket->write(AirPodsPackets::Connection::AIRPODS_DISCONNECTED);
+            LOG_DEBUG("AIRPODS_DISCONNECTED packet written: " << AirPodsPackets::Connection::AIRPODS_DISCONNECTED.toHex());
+        }
+
+        // A bud that was still in an ear when the link dropped has probably fallen out somewhere
+        bool alertOutOfRange = loadOutOfRangeAlert(m_deviceInfo->bluetoothAddress()) && !m_systemSleeping &&
+                               m_deviceInfo->getEarDetection()->oneOrMorePodsInEar();
+        QString lostDeviceName = m_deviceInfo->deviceName();
+        if (lastSeenEnabled())
+        {
+            m_lastSeenDeviceName = lostDeviceName;
+            m_lastSeenLocator->locate();
+        }
+
+        m_headTracking->setActive(false);
+        setDeviceLoading(false);
+        dismissUndo(); // Nothing to revert on a device that's gone
+        m_connectedHosts.clear();
+        m_hostTypes.clear();
+        emit otherHostsChanged();
+        m_batteryProvider->remove(address.toString());
+        m_drainMonitor.reset();
+        m_lowBatteryPlayed = false;
+        m_announcedThreshold = INT_MAX;
+        m_wearReminder->setInEar(false);
+        updateOutOfEarPlaybackWatch();
+        m_volumeRestored = false;
+        if (m_defaultOutputSwitched)
+        {
+            mediaController->restoreDefaultOutput(moveStreamsToDefaultOutput());
+            m_defaultOutputSwitched = false;
+        }
+        playEventSound("disconnected");
+
+        // Clear the device name and model
+        m_deviceInfo->reset();
+        m_bleManager->startScan();
+        emit airPodsStatusChanged();
+
+        // Show system notification
+        if (alertOutOfRange)
+        {
+            LOG_WARN("Connection lost while in ear, raising out of range alert");
+            trayManager->showPersistentAlert(
+                tr("AirPods Out of Range"),
+                tr("%1 disconnected while in use. Check that they haven't fallen out.").arg(lostDeviceName),
+                outOfRangeAlertSound());
+        }
+        else
+        {
+            trayManager->showNotification(
+                tr("AirPods Disconnected"),
+                tr("Your AirPods have been disconnected"));
+        }
+        trayManager->resetTrayIcon();
+    }
+
+    // BlueZ only knows a single level per device, report the emptiest bud that's available
+    void updateBlueZBattery()
+    {
+        if (!areAirpodsConnected())
+            return;
+
+        Battery *battery = m_deviceInfo->getBattery();
+        int level = -1;
+        for (Battery::Component component : {Battery::Component::Left, Battery::Component::Right, Battery::Component::Headset})
+        {
+            Battery::BatteryState state = battery->getState(component);
+            if (state.status != Battery::BatteryStatus::Disconnected && state.level > 0 && (level < 0 || state.level < level))
+                level = state.level;
+        }
+        if (level >= 0)
+            m_batteryProvider->update(monitor->findAdapterPath(), m_deviceInfo->bluetoothAddress(), static_cast<uchar>(level));
+    }
+
+    // Sets the volume the device had when it was last used. Until then the reported volume is
+    // whatever the AirPods came up with and isn't remembered.
+    void restoreDeviceVolume()
+    {
+        if (m_volumeRestored || !areAirpodsConnected())
+            return;
+        QString address = m_deviceInfo->bluetoothAddress();
+        QTimer::singleShot(1000, this, [this, address]()
+                           {
+                               if (m_volumeRestored || address != m_deviceInfo->bluetoothAddress())
+                                   return;
+                               m_volumeRestored = true;
+                               int saved = m_settings->value(volumeKey(address), -1).toInt();
+                               if (!restoreVolume() || saved < 0)
+                                   return;
+                               if (mediaController->setAirPodsSinkVolume(saved))
+                                   LOG_INFO("Restored volume of " << address << " to " << saved << "%");
+                               else
+                                   LOG_WARN("Could not restore the volume of " << address); });
+    }
+
+    // Once per connection, so switching back by hand isn't undone on the next ear insertion
+    void switchDefaultOutputIfEnabled()
+    {
+        if (!switchDefaultOutput() || m_defaultOutputSwitched || !areAirpodsConnected())
+            return;
+        // The sink shows up shortly after the profile change
+        QTimer::singleShot(1000, this, [this]()
+                           {
+                               if (!m_defaultOutputSwitched && areAirpodsConnected() &&
+                                   mediaController->switchDefaultOutputToAirPods(moveStreamsToDefaultOutput()))
+                                   m_defaultOutputSwitched = true; });
     }
 
-private slots:
-    void onTrayIconActivated()
+    // Polls the sink only while both buds are out, that's when music playing into the case goes unnoticed
+    void updateOutOfEarPlaybackWatch()
     {
-        QQuickWindow *window = qobject_cast<QQuickWindow *>(
-            QGuiApplication::topLevelWindows().constFirst());
-        if (window)
+        bool watch = outOfEarPlaybackDelay() > 0 && areAirpodsConnected() &&
+                     !m_deviceInfo->getEarDetection()->oneOrMorePodsInEar();
+        if (watch && !m_outOfEarPlaybackTimer->isActive())
         {
-            window->show();
-            window->raise();
-            window->requestActivate();
+            m_outOfEarSince.start();
+            m_outOfEarPlaybackTimer->start();
+        }
+        else if (!watch)
+        {
+            m_outOfEarPlaybackTimer->stop();
+            m_outOfEarPlaybackWarned = false;
+            trayManager->setWarningBadge(false);
         }
     }
 
-    void onOpenApp()
+    void checkOutOfEarPlayback()
     {
-        QObject *rootObject = parent->rootObjects().first();
-        if (rootObject) {
-            QMetaObject::invokeMethod(rootObject, "reopen", Q_ARG(QVariant, "app"));
+        if (!mediaController->isAudioPlayingToAirPods())
+        {
+            // Restart the count so a later stream gets the full grace period again
+            m_outOfEarSince.start();
+            m_outOfEarPlaybackWarned = false;
+            trayManager->setWarningBadge(false);
+            return;
+        }
+        if (m_outOfEarPlaybackWarned || m_outOfEarSince.elapsed() < outOfEarPlaybackDelay() * 1000LL)
+            return;
+
+        m_outOfEarPlaybackWarned = true;
+        LOG_WARN("Audio is playing to the AirPods while neither bud is in an ear");
+        m_timeline->record(m_deviceInfo->bluetoothAddress(), "Playing out of ear");
+        trayManager->setWarningBadge(true);
+        if (outOfEarPlaybackPause())
+        {
+            mediaController->pause();
+            trayManager->showNotification(tr("Playback paused"), tr("Audio was playing to your AirPods while neither was in an ear."));
         }
         else
         {
-            loadMainModule();
+            trayManager->showNotification(tr("AirPods not in use"), tr("Audio is playing to your AirPods while neither is in an ear."));
         }
     }
 
-    void onOpenSettings()
+    // Features depending on services that may not be running degrade quietly otherwise, so the
+    // window lists what's missing and why
+    void checkRuntimeDependencies()
     {
-        QObject *rootObject = parent->rootObjects().first();
-        if (rootObject) {
-            QMetaObject::invokeMethod(rootObject, "reopen", Q_ARG(QVariant, "settings"));
-        }
-        else
+        m_missingDependencies.clear();
+
+        if (!mediaController->isAudioServerAvailable())
+            m_missingDependencies << tr("No PulseAudio or PipeWire server: volume, codec, output switching and media pausing are unavailable.");
+
+        QDBusConnectionInterface *bus = QDBusConnection::sessionBus().interface();
+        auto serviceAvailable = [bus](const QString &service)
         {
-            loadMainModule();
-        }
+            if (!bus)
+                return false;
+            if (bus->isServiceRegistered(service))
+                return true;
+            QDBusReply<QStringList> activatable = bus->call("ListActivatableNames");
+            return activatable.isValid() && activatable.value().contains(service);
+        };
+
+        if (!serviceAvailable("org.freedesktop.Notifications"))
+            m_missingDependencies << tr("No notification daemon: battery, connection and reminder notifications won't be shown.");
+
+        if (!bus || !bus->isServiceRegistered("org.kde.StatusNotifierWatcher") || !QSystemTrayIcon::isSystemTrayAvailable())
+            m_missingDependencies << tr("No StatusNotifier watcher: the tray icon is hidden, battery levels are shown as a notification instead.");
+
+        for (const QString &reason : m_missingDependencies)
+            LOG_WARN("Missing runtime dependency: " << reason);
+        emit missingDependenciesChanged();
     }
 
-    void sendHandshake() {
-        LOG_INFO("Connected to device, sending initial packets");
-        writePacketToSocket(AirPodsPackets::Connection::HANDSHAKE, "Handshake packet written: ");
+    void applyWearReminderSettings()
+    {
+        m_wearReminder->setIntervalMinutes(wearReminderInterval());
+        m_wearReminder->setQuietHours(QTime::fromString(wearReminderQuietStart(), "HH:mm"),
+                                      QTime::fromString(wearReminderQuietEnd(), "HH:mm"));
+        emit wearReminderChanged();
     }
 
-    void bluezDeviceConnected(const QString &address, const QString &name)
+    void showWearReminder(int minutesWorn)
     {
-        QBluetoothDeviceInfo device(QBluetoothAddress(address), name, 0);
-        connectToDevice(device);
+        QString duration = minutesWorn % 60 == 0 ? tr("%n hour(s)", "", minutesWorn / 60) : tr("%n minute(s)", "", minutesWorn);
+        trayManager->showNotification(tr("Time for a break"),
+                                      tr("You've been wearing your AirPods for %1, consider a break or swapping a bud to charge.").arg(duration));
+    }
 
-        // After system reboot, AirPods might be connected but A2DP profile not active
-        // Attempt to activate A2DP profile after a delay to ensure connection is established
-        QTimer::singleShot(2000, this, [this, address]()
+    void recordBatteryHistory()
+    {
+        if (!areAirpodsConnected())
+            return;
+
+        m_batteryHistory->record(m_deviceInfo->bluetoothAddress(), *m_deviceInfo->getBattery(), m_deviceInfo->ignoreCase());
+    }
+
+    void playEventSound(const QString &event)
+    {
+        QString sound = eventSounds().value(event).toString();
+        if (!sound.isEmpty())
+            SoundTheme::play(sound);
+    }
+
+    // Plays the low battery sound once when a discharging bud drops below 20%, and announces
+    // each of the thresholds through the AirPods once. Both re-arm when no bud is low anymore.
+    void checkLowBattery()
+    {
+        static constexpr int thresholds[] = {20, 10, 5};
+        Battery *battery = m_deviceInfo->getBattery();
+        Battery::Component lowest = Battery::Component::Left;
+        int lowestLevel = INT_MAX;
+        for (Battery::Component component : {Battery::Component::Left, Battery::Component::Right, Battery::Component::Headset})
         {
-            if (!address.isEmpty())
+            Battery::BatteryState state = battery->getState(component);
+            if (state.status == Battery::BatteryStatus::Discharging && state.level > 0 && state.level < lowestLevel)
             {
-                QString formattedAddress = address;
-                formattedAddress = formattedAddress.replace(":", "_");
-                mediaController->setConnectedDeviceMacAddress(formattedAddress);
-                mediaController->activateA2dpProfile();
-                LOG_INFO("A2DP profile activation attempted for newly connected device");
+                lowest = component;
+                lowestLevel = state.level;
             }
-        });
+        }
+        if (lowestLevel >= thresholds[0])
+        {
+            m_lowBatteryPlayed = false;
+            m_announcedThreshold = INT_MAX;
+            return;
+        }
+        if (!areAirpodsConnected())
+            return;
+
+        if (!m_lowBatteryPlayed)
+        {
+            m_lowBatteryPlayed = true;
+            playEventSound("lowBattery");
+        }
+
+        int crossed = INT_MAX;
+        for (int threshold : thresholds)
+        {
+            if (lowestLevel < threshold)
+                crossed = threshold;
+        }
+        if (crossed < m_announcedThreshold)
+        {
+            m_announcedThreshold = crossed;
+            if (lowBatteryAnnouncement())
+                announceLowBattery(lowest, lowestLevel);
+        }
     }
 
-    void onDeviceDisconnected(const QBluetoothAddress &address)
+    // Spoken when speech-dispatcher is installed, otherwise a short tone. Skipped while the
+    // AirPods aren't the default output, it would only be heard on the speakers.
+    void announceLowBattery(Battery::Component component, int level)
     {
-        LOG_INFO("Device disconnected: " << address.toString());
-        if (socket)
+        if (mediaController->airPodsVolume() < 0)
         {
-            LOG_WARN("Socket is still open, closing it");
-            socket->close();
-            socket = nullptr;
+            LOG_DEBUG("AirPods aren't the default output, not announcing low battery");
+            return;
         }
-        if (phoneSocket && phoneSocket->isOpen())
+
+        QString which = component == Battery::Component::Left    ? tr("Left AirPod")
+                        : component == Battery::Component::Right ? tr("Right AirPod")
+                                                                 : tr("AirPods");
+        QString text = tr("%1 battery at %2 percent").arg(which).arg(level);
+        LOG_INFO("Announcing low battery: " << text);
+        if (QStandardPaths::findExecutable("spd-say").isEmpty() || !QProcess::startDetached("spd-say", {text}))
+            TestTone::play(component == Battery::Component::Left ? -1 : component == Battery::Component::Right ? 1 : 0);
+    }
+
+    // A new or calibrated battery makes the drain comparison so far meaningless
+    void onBatteryMarkerAdded(const QString &address, const QString &component, const QString &kind)
+    {
+        m_timeline->record(address, "Battery marker", component + " " + kind);
+        if (address == m_deviceInfo->bluetoothAddress())
+            m_drainMonitor.reset();
+    }
+
+    void checkDrainRates()
+    {
+        Battery *battery = m_deviceInfo->getBattery();
+        bool bothWorn = m_deviceInfo->isLeftPodInEar() && m_deviceInfo->isRightPodInEar() &&
+                        battery->getState(Battery::Component::Left).status == Battery::BatteryStatus::Discharging &&
+                        battery->getState(Battery::Component::Right).status == Battery::BatteryStatus::Discharging;
+        if (!bothWorn)
         {
-            phoneSocket->write(AirPodsPackets::Connection::AIRPODS_DISCONNECTED);
-            LOG_DEBUG("AIRPODS_DISCONNECTED packet written: " << AirPodsPackets::Connection::AIRPODS_DISCONNECTED.toHex());
+            m_drainMonitor.interrupt();
+            return;
         }
 
-        // Clear the device name and model
-        m_deviceInfo->reset();
-        m_bleManager->startScan();
-        emit airPodsStatusChanged();
+        auto divergence = m_drainMonitor.addSample(QDateTime::currentMSecsSinceEpoch(), battery->getLeftPodLevel(),
+                                                   battery->getRightPodLevel());
+        if (!divergence)
+            return;
 
-        // Show system notification
-        trayManager->showNotification(
-            tr("AirPods Disconnected"),
-            tr("Your AirPods have been disconnected"));
-        trayManager->resetTrayIcon();
+        bool leftFaster = divergence->faster == Battery::Component::Left;
+        QString message = tr("%1 bud draining %2x faster than %3")
+                              .arg(leftFaster ? tr("Left") : tr("Right"))
+                              .arg(divergence->ratio, 0, 'f', 1)
+                              .arg(leftFaster ? tr("right") : tr("left"));
+        LOG_WARN("Uneven battery drain: " << message);
+        m_timeline->record(m_deviceInfo->bluetoothAddress(), "Battery warning", message);
+        trayManager->showNotification(tr("Uneven battery drain"), message);
+    }
+
+    void onTransportVolumeChanged(const QString &address, int volume)
+    {
+        if (address.compare(m_deviceInfo->bluetoothAddress(), Qt::CaseInsensitive) != 0)
+            return;
+
+        m_deviceVolume = qRound(volume * 100 / 127.0);
+        if (m_volumeRestored)
+            m_settings->setValue(volumeKey(m_deviceInfo->bluetoothAddress()), m_deviceVolume);
+        if (!m_readOnly && volumeLock() && m_hostVolume >= 0 && qAbs(m_hostVolume - m_deviceVolume) > 1)
+        {
+            LOG_DEBUG("AirPods volume changed to " << m_deviceVolume << "%, syncing host volume");
+            if (mediaController->setAirPodsVolume(m_deviceVolume))
+                m_hostVolume = m_deviceVolume;
+        }
+        emit volumesChanged();
     }
 
     void bluezDeviceDisconnected(const QString &address, const QString &name)
@@ -542,54 +2208,59 @@ private slots:
 
     void parseMetadata(const QByteArray &data)
     {
-        // Verify the data starts with the METADATA header
-        if (!data.startsWith(AirPodsPackets::Parse::METADATA))
+        auto metadata = AirPodsPackets::Metadata::parse(data);
+        if (!metadata)
         {
-            LOG_ERROR("Invalid metadata packet: Incorrect header");
+            LOG_ERROR("Invalid metadata packet");
             return;
         }
 
-        int pos = AirPodsPackets::Parse::METADATA.size(); // Start after the header
+        m_deviceInfo->setDeviceName(metadata->name);
+        m_deviceInfo->setModelNumber(metadata->modelNumber);
+        m_deviceInfo->setManufacturer(metadata->manufacturer);
+        m_deviceInfo->setFirmwareVersion(metadata->firmwareVersion);
+        m_deviceInfo->setSerialNumber(metadata->serialNumber);
 
-        // Check if there is enough data to skip the initial bytes (based on example structure)
-        if (data.size() < pos + 6)
+        m_deviceInfo->setModel(parseModelNumber(m_deviceInfo->modelNumber()));
+        emit modelChanged();
+        // Lets the known devices list show the model while these AirPods are away
+        if (!m_deviceInfo->bluetoothAddress().isEmpty())
         {
-            LOG_ERROR("Metadata packet too short to parse initial bytes");
-            return;
+            m_settings->setValue(modelKey(m_deviceInfo->bluetoothAddress()), static_cast<int>(m_deviceInfo->model()));
+            refreshKnownAirPods();
         }
-        pos += 6; // Skip 6 bytes after the header as per example structure
+        // Kept for the devices export, which needs them while the AirPods are away
+        m_deviceInfo->saveToSettings(*m_settings);
 
-        auto extractString = [&data, &pos]() -> QString
+        if (!metadata->firmwareVersion.isEmpty())
         {
-            if (pos >= data.size())
-            {
-                return QString();
-            }
-            int start = pos;
-            while (pos < data.size() && data.at(pos) != '\0')
-            {
-                ++pos;
-            }
-            QString str = QString::fromUtf8(data.mid(start, pos - start));
-            if (pos < data.size())
-            {
-                ++pos; // Move past the null terminator
-            }
-            return str;
-        };
-
-        m_deviceInfo->setDeviceName(extractString());
-        m_deviceInfo->setModelNumber(extractString());
-        m_deviceInfo->setManufacturer(extractString());
+            recordFirmwareVersion(metadata->firmwareVersion);
+        }
 
-        m_deviceInfo->setModel(parseModelNumber(m_deviceInfo->modelNumber()));
-        emit modelChanged();
+        m_modeMapping = ModeMapping::load(m_deviceInfo->modelNumber(), m_deviceInfo->firmwareVersion());
+        if (m_modeMapping.isOverridden())
+            LOG_INFO("Using listening mode values from " << ModeMapping::filePath());
 
         // Log extracted metadata
         LOG_INFO("Parsed AirPods metadata:");
         LOG_INFO("Device Name: " << m_deviceInfo->deviceName());
         LOG_INFO("Model Number: " << m_deviceInfo->modelNumber());
         LOG_INFO("Manufacturer: " << m_deviceInfo->manufacturer());
+        LOG_INFO("Firmware Version: " << m_deviceInfo->firmwareVersion());
+    }
+
+    // Remembers the firmware per device so updates installed in between connections end up in the timeline
+    void recordFirmwareVersion(const QString &version)
+    {
+        QString address = m_deviceInfo->bluetoothAddress();
+        QString key = "firmware/" + QString(address).replace(":", "_");
+        QString previous = m_settings->value(key).toString();
+        if (!previous.isEmpty() && previous != version)
+        {
+            LOG_INFO("Firmware updated from " << previous << " to " << version);
+            m_timeline->record(address, "Firmware updated", QString("%1 → %2").arg(previous, version));
+        }
+        m_settings->setValue(key, version);
     }
 
     QString getEarStatus(char value)
@@ -620,8 +2291,28 @@ private slots:
         socket = localSocket;
 
         // Connection handler
-        auto handleConnection = [this, localSocket]()
+        auto handleConnection = [this, device, localSocket]()
         {
+            m_timeline->record(localSocket->peerAddress().toString(), "Connected");
+            m_connectionCount++;
+            playEventSound("connected");
+            if (lastDeviceAddress() != localSocket->peerAddress().toString())
+            {
+                m_settings->setValue("device/lastAddress", localSocket->peerAddress().toString());
+                emit lastDeviceAddressChanged();
+            }
+            // If the channel opens but the handshake is never answered, something else is talking to the AirPods
+            QTimer::singleShot(5000, localSocket, [this, device, localSocket]()
+                               {
+                                   if (socket != localSocket || m_handshakeAcknowledged)
+                                       return;
+                                   m_conflictDevice = device;
+                                   setConnectionConflict(tr("The AirPods didn't answer the handshake. Another AAP client, such as a second LibrePods instance, is probably using them. Close it and retry."));
+                                   setDeviceLoading(false); });
+            m_handshakeAcknowledged = false;
+            setDeviceLoading(true);
+            // Older firmware may never send some of the initial state, don't keep the page loading forever
+            QTimer::singleShot(5000, this, [this]() { setDeviceLoading(false); });
             connect(localSocket, &QBluetoothSocket::readyRead, this, [this, localSocket]()
                     {
             QByteArray data = localSocket->readAll();
@@ -634,12 +2325,23 @@ private slots:
         auto handleError = [this, device, localSocket](QBluetoothSocket::SocketError error)
         {
             LOG_ERROR("Socket error: " << error << ", " << localSocket->errorString());
+            recordSocketError(localSocket->errorString());
+            m_timeline->record(device.address().toString(), "Error", localSocket->errorString());
+
+            // EBUSY from BlueZ means the channel is already open in another process, retrying won't help
+            if (localSocket->errorString().contains("busy", Qt::CaseInsensitive))
+            {
+                m_conflictDevice = device;
+                setConnectionConflict(tr("The AirPods control channel is in use by another process, such as a second LibrePods instance or another AAP tool. Close it and retry."));
+                return;
+            }
 
             static int retryCount = 0;
             if (retryCount < m_retryAttempts)
             {
                 retryCount++;
                 LOG_INFO("Retrying connection (attempt " << retryCount << ")");
+                m_timeline->record(device.address().toString(), "Reconnecting", QString("Attempt %1").arg(retryCount));
                 QTimer::singleShot(1500, this, [this, device]()
                                    { connectToDevice(device); });
             }
@@ -647,6 +2349,8 @@ private slots:
             {
                 LOG_ERROR("Failed to connect after 3 attempts");
                 retryCount = 0;
+                m_conflictDevice = device;
+                setConnectionConflict(tr("Couldn't open the AirPods control channel: %1").arg(localSocket->errorString()));
             }
         };
 
@@ -659,16 +2363,34 @@ private slots:
         notifyAndroidDevice();
     }
 
-    void parseData(const QByteArray &data)
+    // Stored right away, so the controls for it show as soon as the device reconnects
+    void learnCapability(AirPodsPackets::Frame frame, const QByteArray &data)
     {
-        LOG_DEBUG("Received: " << data.toHex());
+        bool changed = frame == AirPodsPackets::Frame::FeaturesAck ? m_deviceInfo->setFeaturesAck(data)
+                                                                  : m_deviceInfo->addCapability(DeviceCapabilities::forFrame(frame));
+        QString address = m_deviceInfo->bluetoothAddress();
+        if (changed && !address.isEmpty())
+            m_settings->setValue(capabilitiesKey(address), m_deviceInfo->capabilities().toStringList());
+    }
 
-        if (data.startsWith(AirPodsPackets::Parse::HANDSHAKE_ACK))
+    void parseData(const QByteArray &data)
+    {
+        LOG_DEBUG("Received: " << data.toHex() << PacketNames::describe(data));
+        notePacket("←", data);
+        m_lastPacketTime = QDateTime::currentDateTime();
+        m_packetRate.add(m_uptime.elapsed());
+
+        using AirPodsPackets::Frame;
+        Frame frame = AirPodsPackets::classify(data);
+        learnCapability(frame, data);
+        switch (frame)
         {
+        case Frame::HandshakeAck:
+            m_handshakeAcknowledged = true;
+            setConnectionConflict(QString());
             writePacketToSocket(AirPodsPackets::Connection::SET_SPECIFIC_FEATURES, "Set specific features packet written: ");
-        }
-        else if (data.startsWith(AirPodsPackets::Parse::FEATURES_ACK))
-        {
+            break;
+        case Frame::FeaturesAck:
             writePacketToSocket(AirPodsPackets::Connection::REQUEST_NOTIFICATIONS, "Request notifications packet written: ");
 
             QTimer::singleShot(2000, this, [this]() {
@@ -676,9 +2398,26 @@ private slots:
                     writePacketToSocket(AirPodsPackets::Connection::REQUEST_NOTIFICATIONS, "Request notifications packet written: ");
                 }
             });
+            break;
+        case Frame::HeadTracking:
+            if (m_headTracking->active())
+                m_headTracking->process(AirPodsPackets::HeadTracking::parse(data).value());
+            break;
+        case Frame::ConnectedDevices:
+            m_connectedHosts = AirPodsPackets::ConnectedDevices::parse(data).value();
+            LOG_INFO("AirPods are connected to " << m_connectedHosts.size() << " host(s)");
+            emit otherHostsChanged();
+            break;
+        case Frame::HostType:
+        {
+            auto hostType = AirPodsPackets::ConnectedDevices::parseDeviceType(data).value();
+            LOG_DEBUG("Host " << hostType.first << " is a " << hostType.second);
+            m_hostTypes.insert(hostType.first, hostType.second);
+            emit otherHostsChanged();
+            break;
         }
         // Magic Cloud Keys Response
-        else if (data.startsWith(AirPodsPackets::MagicPairing::MAGIC_CLOUD_KEYS_HEADER))
+        case Frame::MagicCloudKeys:
         {
             auto keys = AirPodsPackets::MagicPairing::parseMagicCloudKeysPacket(data);
             LOG_INFO("Received Magic Cloud Keys:");
@@ -689,53 +2428,77 @@ private slots:
             m_deviceInfo->setMagicAccIRK(keys.magicAccIRK);
             m_deviceInfo->setMagicAccEncKey(keys.magicAccEncKey);
             m_deviceInfo->saveToSettings(*m_settings);
+            break;
         }
         // Get CA state
-        else if (data.startsWith(AirPodsPackets::ConversationalAwareness::HEADER)) {
+        case Frame::ConversationalAwareness:
             if (auto result = AirPodsPackets::ConversationalAwareness::parseState(data))
             {
+                if (result.value() != m_deviceInfo->conversationalAwareness())
+                    m_settingsLog->record("AirPods", "Conversational awareness", m_deviceInfo->conversationalAwareness(), result.value());
                 m_deviceInfo->setConversationalAwareness(result.value());
                 LOG_INFO("Conversational awareness state received: " << m_deviceInfo->conversationalAwareness());
             }
-        }
+            break;
         // Hearing Aid state
-        else if (data.startsWith(AirPodsPackets::HearingAid::HEADER)) {
+        case Frame::HearingAid:
             if (auto result = AirPodsPackets::HearingAid::parseState(data))
             {
                 m_deviceInfo->setHearingAidEnabled(result.value());
                 LOG_INFO("Hearing aid state received: " << m_deviceInfo->hearingAidEnabled());
             }
-        }
+            break;
         // Noise Control Mode
-        else if (data.size() == 11 && data.startsWith(AirPodsPackets::NoiseControl::HEADER))
+        case Frame::NoiseControl:
         {
-            if (auto value = AirPodsPackets::NoiseControl::parseMode(data))
+            auto value = m_modeMapping.parse(data);
+            if (!value)
+            {
+                LOG_WARN("Listening mode value 0x" << QString::number(static_cast<quint8>(data.at(7)), 16)
+                         << " isn't known for firmware " << m_deviceInfo->firmwareVersion()
+                         << ", add an override to " << ModeMapping::filePath() << " if this firmware uses other values");
+            }
+            else
             {
+                if (m_pendingNoiseControlMode == value)
+                    m_pendingNoiseControlMode.reset();
+                else if (m_pendingNoiseControlMode)
+                {
+                    LOG_WARN("Requested listening mode " << noiseControlModeName(m_pendingNoiseControlMode.value())
+                             << " but the AirPods report " << noiseControlModeName(value.value())
+                             << ", the mode values for firmware " << m_deviceInfo->firmwareVersion() << " may differ");
+                    m_pendingNoiseControlMode.reset();
+                }
+                else if (value.value() != m_deviceInfo->noiseControlMode())
+                {
+                    // Changed on the AirPods themselves, usually by a stem press
+                    m_settingsLog->record("AirPods", "Listening mode", noiseControlModeName(m_deviceInfo->noiseControlMode()),
+                                          noiseControlModeName(value.value()));
+                    trayManager->recordModeChange(value.value(), "AirPods");
+                }
                 m_deviceInfo->setNoiseControlMode(value.value());
                 LOG_INFO("Noise control mode received: " << m_deviceInfo->noiseControlMode());
             }
+            break;
         }
         // Ear Detection
-        else if (data.size() == 8 && data.startsWith(AirPodsPackets::Parse::EAR_DETECTION))
-        {
+        case Frame::EarDetection:
             m_deviceInfo->getEarDetection()->parseData(data);
             mediaController->handleEarDetection(m_deviceInfo->getEarDetection());
-        }
+            break;
         // Battery Status
-        else if ((data.size() == 22 || data.size() == 12) && data.startsWith(AirPodsPackets::Parse::BATTERY_STATUS))
-        {
+        case Frame::Battery:
             m_deviceInfo->getBattery()->parsePacket(data);
             m_deviceInfo->updateBatteryStatus();
+            finishLoadingIfComplete();
             LOG_INFO("Battery status: " << m_deviceInfo->batteryStatus());
-        }
+            break;
         // Conversational Awareness Data
-        else if (data.size() == 10 && data.startsWith(AirPodsPackets::ConversationalAwareness::DATA_HEADER))
-        {
+        case Frame::ConversationalAwarenessData:
             LOG_INFO("Received conversational awareness data");
             mediaController->handleConversationalAwareness(data);
-        }
-        else if (data.startsWith(AirPodsPackets::Parse::METADATA))
-        {
+            break;
+        case Frame::Metadata:
             parseMetadata(data);
             initiateMagicPairing();
             mediaController->setConnectedDeviceMacAddress(m_deviceInfo->bluetoothAddress().replace(":", "_"));
@@ -745,17 +2508,46 @@ private slots:
             }
             m_bleManager->stopScan();
             emit airPodsStatusChanged();
-        }
-        else if (data.startsWith(AirPodsPackets::OneBudANCMode::HEADER)) {
+
+            finishLoadingIfComplete();
+
+            // Metadata is the first packet after connecting, the device is identified from here on
+            if (showWindowOnConnect())
+            {
+                showWindowForConnectedDevice();
+            }
+            break;
+        case Frame::AllowOffOption:
+            if (auto value = AirPodsPackets::AllowOffOption::parseState(data))
+            {
+                m_deviceInfo->setAllowOffOption(value.value());
+                LOG_INFO("Allow Off option received: " << m_deviceInfo->allowOffOption());
+            }
+            break;
+        case Frame::CallManagement:
+            if (auto flipped = AirPodsPackets::CallManagement::parseFlipped(data))
+            {
+                m_deviceInfo->setCallControlsFlipped(flipped);
+                LOG_INFO("Call controls flipped received: " << flipped.value());
+            }
+            break;
+        case Frame::ChimeVolume:
+            if (auto volume = AirPodsPackets::ChimeVolume::parseVolume(data))
+            {
+                m_deviceInfo->setChimeVolume(volume.value());
+                LOG_INFO("Chime volume received: " << m_deviceInfo->chimeVolume());
+            }
+            break;
+        case Frame::OneBudANCMode:
             if (auto value = AirPodsPackets::OneBudANCMode::parseState(data))
             {
                 m_deviceInfo->setOneBudANCMode(value.value());
                 LOG_INFO("One Bud ANC mode received: " << m_deviceInfo->oneBudANCMode());
             }
-        }
-        else
-        {
+            break;
+        case Frame::Unknown:
             LOG_DEBUG("Unrecognized packet format: " << data.toHex());
+            break;
         }
     }
 
@@ -790,6 +2582,7 @@ private slots:
 
         connect(phoneSocket, QOverload<QBluetoothSocket::SocketError>::of(&QBluetoothSocket::errorOccurred), this, [this](QBluetoothSocket::SocketError error) {
             LOG_ERROR("Phone socket error: " << error << ", " << phoneSocket->errorString());
+            recordSocketError("Phone: " + phoneSocket->errorString());
         });
 
         phoneSocket->connectToService(phoneAddress, QBluetoothUuid("1abbb9a4-10e4-4000-a75c-8953c5471342"));
@@ -884,6 +2677,16 @@ private slots:
         }
     }
 
+    // Only into buds that are being worn, nobody else should hear who is calling
+    void announceCall(const QString &number, const QString &contactName)
+    {
+        if (!announceCalls() || !areAirpodsConnected() || !m_deviceInfo->getEarDetection()->oneOrMorePodsInEar())
+            return;
+        LOG_INFO("Announcing incoming call");
+        m_timeline->record(m_deviceInfo->bluetoothAddress(), "Incoming call announced");
+        CallAnnouncer::announce(CallAnnouncer::announcement(number, contactName));
+    }
+
 public:
     void handleMediaStateChange(MediaController::MediaState state) {
         if (state == MediaController::MediaState::Playing) {
@@ -948,7 +2751,70 @@ public:
         }
     }
 
+    void setOutOfRangeAlert(bool enabled)
+    {
+        QString address = m_deviceInfo->bluetoothAddress();
+        if (address.isEmpty() || enabled == outOfRangeAlert())
+            return;
+        m_settings->setValue(outOfRangeAlertKey(address), enabled);
+        emit outOfRangeAlertChanged(enabled);
+    }
+
+    void setSwitchDefaultOutput(bool enabled)
+    {
+        QString address = m_deviceInfo->bluetoothAddress();
+        if (address.isEmpty() || enabled == switchDefaultOutput())
+            return;
+        auditChange("Switch default output", switchDefaultOutput(), enabled);
+        m_settings->setValue(defaultOutputKey(address), enabled);
+        emit defaultOutputSettingsChanged();
+        if (enabled)
+            switchDefaultOutputIfEnabled();
+    }
+
+    void setRestoreVolume(bool enabled)
+    {
+        if (enabled == restoreVolume())
+            return;
+        auditChange("Restore volume", restoreVolume(), enabled);
+        m_settings->setValue("audio/restoreVolume", enabled);
+        emit restoreVolumeChanged(enabled);
+    }
+
+    void setMoveStreamsToDefaultOutput(bool enabled)
+    {
+        if (enabled == moveStreamsToDefaultOutput())
+            return;
+        auditChange("Move streams to default output", moveStreamsToDefaultOutput(), enabled);
+        m_settings->setValue("defaultOutput/moveStreams", enabled);
+        emit defaultOutputSettingsChanged();
+    }
+
+    void setLastSeenEnabled(bool enabled)
+    {
+        if (enabled == lastSeenEnabled())
+            return;
+        m_settings->setValue("lastSeen/enabled", enabled);
+        if (!enabled)
+        {
+            m_settings->remove("lastSeen");
+            m_settings->setValue("lastSeen/enabled", false);
+            emit lastSeenChanged();
+        }
+        emit lastSeenEnabledChanged(enabled);
+    }
+
+    void setOutOfRangeAlertSound(bool enabled)
+    {
+        if (enabled == outOfRangeAlertSound())
+            return;
+        m_settings->setValue("lostMode/playSound", enabled);
+        emit outOfRangeAlertSoundChanged(enabled);
+    }
+
     void loadMainModule() {
+        if (m_headless)
+            return;
         parent->load(QUrl(QStringLiteral("qrc:/linux/Main.qml")));
     }
 
@@ -965,10 +2831,45 @@ signals:
     void earDetectionBehaviorChanged(int behavior);
     void crossDeviceEnabledChanged(bool enabled);
     void notificationsEnabledChanged(bool enabled);
+    void trayShowsCaseBatteryChanged(bool enabled);
+    void themeChanged();
+    void trayClickActionsChanged();
+    void modeOsdEnabledChanged(bool enabled);
+    void modeOsdRequested(const QString &modeName);
+    void outOfRangeAlertChanged(bool enabled);
+    void defaultOutputSettingsChanged();
+    void restoreVolumeChanged(bool enabled);
+    void outOfRangeAlertSoundChanged(bool enabled);
+    void eventSoundsChanged();
+    void lowBatteryAnnouncementChanged(bool enabled);
+    void wearReminderChanged();
+    void outOfEarPlaybackChanged();
+    void lastSeenEnabledChanged(bool enabled);
+    void lastSeenChanged();
     void retryAttemptsChanged(int attempts);
     void oneBudANCModeChanged(bool enabled);
     void phoneMacStatusChanged();
     void hearingAidEnabledChanged(bool enabled);
+    void bluezPropertiesChanged();
+    void otherHostsChanged();
+    void audioDevicesChanged();
+    void knownAirPodsChanged();
+    void adapterChanged();
+    void deviceLoadingChanged(bool loading);
+    void connectionConflictChanged();
+    void lastDeviceAddressChanged();
+    void undoChanged();
+    void pendingWritesChanged();
+    void missingDependenciesChanged();
+    void recentPacketsChanged();
+    void diagnosticsChanged();
+    void showWindowOnConnectChanged(bool enabled);
+    void audioCodecsChanged();
+    void volumesChanged();
+    void volumeLockChanged(bool locked);
+    void conversationReleaseDelayChanged(int ms);
+    void announceCallsChanged(bool enabled);
+    void phoneStatusChanged();
 
 private:
     QBluetoothSocket *socket = nullptr;
@@ -982,13 +2883,114 @@ private:
     AutoStartManager *m_autoStartManager;
     int m_retryAttempts = 3;
     bool m_hideOnStart = false;
+    bool m_headless = false;
+    bool m_readOnly = false;
     DeviceInfo *m_deviceInfo;
     BleManager *m_bleManager;
     SystemSleepMonitor *m_systemSleepMonitor = nullptr;
+    ConnectionTimeline *m_timeline = nullptr;
+    SettingsAuditLog *m_settingsLog = nullptr;
+    bool m_systemSleeping = false;
+    QList<AirPodsPackets::ConnectedDevices::Device> m_connectedHosts;
+    HeadTracking *m_headTracking = nullptr;
+    ConversationStats *m_conversationStats = nullptr;
+    BlueZBatteryProvider *m_batteryProvider = nullptr;
+    BatteryHistory *m_batteryHistory = nullptr;
+    SyntheticDevices *m_syntheticDevices = nullptr;
+    SystemAppearance *m_appearance = nullptr;
+    QPalette m_defaultPalette;
+    WearReminder *m_wearReminder = nullptr;
+    LatencyTest *m_latencyTest = nullptr;
+    HeadsetPower *m_headsetPower = nullptr;
+    CallAnnouncer *m_callAnnouncer = nullptr;
+    KdeConnectRelay *m_kdeConnectRelay = nullptr;
+    QTimer *m_outOfEarPlaybackTimer = nullptr;
+    QElapsedTimer m_outOfEarSince;
+    bool m_outOfEarPlaybackWarned = false;
+    bool m_defaultOutputSwitched = false;
+    bool m_volumeRestored = false;
+    DrainMonitor m_drainMonitor;
+    bool m_lowBatteryPlayed = false;
+    int m_announcedThreshold = INT_MAX;
+    QVariantMap m_adapterProperties;
+    QVariantList m_audioDevices;
+    QVariantList m_knownAirPods;
+    bool m_deviceLoading = false;
+    QString m_connectionConflict;
+    QBluetoothDeviceInfo m_conflictDevice;
+    bool m_handshakeAcknowledged = false;
+    QStringList m_missingDependencies;
+
+    struct UndoEntry
+    {
+        QString description;
+        std::function<void()> revert;
+        QDateTime time;
+    };
+    static constexpr int MaxUndoEntries = 10;
+    static constexpr int UndoTimeoutMs = 8000;
+    QList<UndoEntry> m_undoQueue;
+    struct PendingWrite
+    {
+        QByteArray packet;
+        QString logMessage;
+        QDateTime time;
+    };
+    static constexpr int MaxPendingWrites = 10;
+    static constexpr int PendingWriteTimeoutMs = 60000;
+    static constexpr int PendingWriteIntervalMs = 200;
+    QList<PendingWrite> m_pendingWrites;
+    int m_appliedPendingWrites = 0;
+    static constexpr int MaxRecentPackets = 20;
+    QStringList m_recentPackets;
+    bool m_applyingUndo = false;
+    QMap<QString, QString> m_hostTypes;
+    LastSeenLocator *m_lastSeenLocator = nullptr;
+    QString m_lastSeenDeviceName;
+    QString m_changeSource = "window";
+    std::optional<NoiseControlMode> m_pendingNoiseControlMode;
+    ModeMapping m_modeMapping;
+    PluginManager *m_pluginManager = nullptr;
+    ScriptHost *m_scriptHost = nullptr;
+    HttpApi *m_httpApi = nullptr;
+    DBusService *m_dbusService = nullptr;
+    ControlServer *m_controlServer = nullptr;
     QString m_phoneMacStatus;
+    QElapsedTimer m_uptime;
+    QDateTime m_lastPacketTime;
+    int m_socketErrorCount = 0;
+    RateCounter m_packetRate;
+    int m_connectionCount = 0;
+    QVariantMap m_diagnostics;
+    QString m_lastSocketError;
+    QDateTime m_lastSocketErrorTime;
+    QVariantList m_bluezProperties;
+    bool m_watchBluezProperties = false;
+    QVariantList m_audioCodecs;
+    QString m_activeAudioCodec;
+    int m_deviceVolume = -1;
+    int m_hostVolume = -1;
+    QTimer m_volumeSyncTimer;
 };
 
 int main(int argc, char *argv[]) {
+    if (argc > 1 && QString(argv[1]) == "install-service") {
+        QCoreApplication app(argc, argv);
+        return Systemd::installUserService() ? 0 : 1;
+    }
+
+    // Headless mode only keeps the Bluetooth handling, tray and local server running.
+    // Without a display fall back to the offscreen platform so it also works on servers.
+    bool headless = false;
+    for (int i = 1; i < argc; ++i) {
+        if (QString(argv[i]) == "--headless")
+            headless = true;
+    }
+    if (headless && qEnvironmentVariableIsEmpty("DISPLAY") && qEnvironmentVariableIsEmpty("WAYLAND_DISPLAY")
+        && qEnvironmentVariableIsEmpty("QT_QPA_PLATFORM")) {
+        qputenv("QT_QPA_PLATFORM", "offscreen");
+    }
+
     QApplication app(argc, argv);
 
     // Load translations
@@ -1010,14 +3012,9 @@ int main(int argc, char *argv[]) {
         }
     }
 
-    QLocalServer::removeServer("app_server");
-
-    QFile stale("/tmp/app_server");
-    if (stale.exists())
-        stale.remove();
-
     QLocalSocket socket_check;
-    socket_check.connectToServer("app_server");
+    socket_check.setSocketOptions(QLocalSocket::AbstractNamespaceOption);
+    socket_check.connectToServer(LocalSocketAuth::serverName());
 
     if (socket_check.waitForConnected(300)) {
         LOG_INFO("Another instance already running! Reopening window...");
@@ -1034,18 +3031,25 @@ int main(int argc, char *argv[]) {
 
     bool debugMode = false;
     bool hideOnStart = false;
+    bool readOnly = false;
     for (int i = 1; i < argc; ++i) {
         if (QString(argv[i]) == "--debug")
             debugMode = true;
 
         if (QString(argv[i]) == "--hide")
             hideOnStart = true;
+
+        if (QString(argv[i]) == "--read-only")
+            readOnly = true;
+
+        if (QString(argv[i]) == "--config-dir" && i + 1 < argc)
+            Storage::setConfigDirOverride(QString::fromLocal8Bit(argv[++i]));
     }
 
     QQmlApplicationEngine engine;
     qmlRegisterType<Battery>("me.kavishdevar.Battery", 1, 0, "Battery");
     qmlRegisterType<DeviceInfo>("me.kavishdevar.DeviceInfo", 1, 0, "DeviceInfo");
-    AirPodsTrayApp *trayApp = new AirPodsTrayApp(debugMode, hideOnStart, &engine);
+    AirPodsTrayApp *trayApp = new AirPodsTrayApp(debugMode, hideOnStart, headless, readOnly, &engine);
     engine.rootContext()->setContextProperty("airPodsTrayApp", trayApp);
 
     // Expose PHONE_MAC_ADDRESS environment variable to QML for placeholder in settings
@@ -1060,77 +3064,19 @@ int main(int argc, char *argv[]) {
     engine.addImageProvider("qrcode", new QRCodeImageProvider());
     trayApp->loadMainModule();
 
-    QLocalServer server;
-    QLocalServer::removeServer("app_server");
-
-    if (!server.listen("app_server"))
-    {
-        LOG_ERROR("Unable to start the listening server");
-        LOG_DEBUG("Server error: " << server.errorString());
-    }
-    else
-    {
-        LOG_DEBUG("Server started, waiting for connections...");
+    // Tell systemd we are up once the event loop runs, and keep the watchdog fed from it
+    QTimer::singleShot(0, &app, []() { Systemd::notify("READY=1"); });
+    QTimer watchdogTimer;
+    if (int interval = Systemd::watchdogInterval()) {
+        LOG_DEBUG("systemd watchdog enabled, pinging every " << interval << "ms");
+        QObject::connect(&watchdogTimer, &QTimer::timeout, []() { Systemd::notify("WATCHDOG=1"); });
+        watchdogTimer.start(interval);
     }
-    QObject::connect(&server, &QLocalServer::newConnection, [&]() {
-        QLocalSocket* socket = server.nextPendingConnection();
-        // Handles Proper Connection
-        QObject::connect(socket, &QLocalSocket::readyRead, [socket, &engine, &trayApp]() {
-            QString msg = socket->readAll();
-            // Check if the message is "reopen", if so, trigger onOpenApp function
-            if (msg == "reopen") {
-                LOG_INFO("Reopening app window");
-                QObject *rootObject = engine.rootObjects().first();
-                if (rootObject) {
-                    QMetaObject::invokeMethod(rootObject, "reopen", Q_ARG(QVariant, "app"));
-                }
-                else
-                {
-                    trayApp->loadMainModule();
-                }
-            }
-            else if (msg == "noise:off") {
-                trayApp->setNoiseControlModeInt(0);
-            }
-            else if (msg == "noise:anc") {
-                trayApp->setNoiseControlModeInt(1);
-            }
-            else if (msg == "noise:transparency") {
-                trayApp->setNoiseControlModeInt(2);
-            }
-            else if (msg == "noise:adaptive") {
-                trayApp->setNoiseControlModeInt(3);
-            }
-            else
-            {
-                LOG_ERROR("Unknown message received: " << msg);
-            }
-            socket->disconnectFromServer();
-        });
-        // Handles connection errors
-        QObject::connect(socket, &QLocalSocket::errorOccurred, [socket]() {
-            LOG_ERROR("Failed to connect to the duplicate app instance");
-            LOG_DEBUG("Connection error: " << socket->errorString());
-        });
-
-        // Handle server-level errors
-        QObject::connect(&server, &QLocalServer::serverError, [&]() {
-            LOG_ERROR("Server failed to accept a new connection");
-            LOG_DEBUG("Server error: " << server.errorString());
-        });
-    });
 
     QObject::connect(&app, &QCoreApplication::aboutToQuit, [&]() {
         LOG_DEBUG("Application quitting. Cleaning up local server...");
-
-        if (server.isListening()) {
-            server.close();
-        }
-
-        QLocalServer::removeServer("app_server");
-        QFile stale("/tmp/app_server");
-        if (stale.exists())
-            stale.remove();
+        Systemd::notify("STOPPING=1");
+        trayApp->controlServer()->close();
     });
     return app.exec();
 }
@@ -17,6 +17,10 @@ MediaController::MediaController(QObject *parent) : QObject(parent) {
   {
     LOG_ERROR("Failed to initialize PulseAudio controller");
   }
+
+  m_conversationReleaseTimer.setSingleShot(true);
+  m_conversationReleaseTimer.setInterval(0);
+  connect(&m_conversationReleaseTimer, &QTimer::timeout, this, &MediaController::restoreConversationVolume);
 }
 
 void MediaController::handleEarDetection(EarDetection *earDetection)
@@ -111,6 +115,7 @@ void MediaController::handleConversationalAwareness(const QByteArray &data) {
     switch (flag) {
     case 0x01:
         LOG_INFO("Conversational awareness event: voice detected");
+        m_conversationReleaseTimer.stop(); // Still talking, stay ducked
 
         if (initialVolume == -1 && isActiveOutputDeviceAirPods()) {
             QString sink = m_pulseAudio->getDefaultSink();
@@ -123,11 +128,15 @@ void MediaController::handleConversationalAwareness(const QByteArray &data) {
             int target = initialVolume * 0.20;
             m_pulseAudio->setSinkVolume(sink, target);
             LOG_INFO("Volume lowered to " << target << "%");
+            emit conversationDuckingStarted();
         }
         break;
 
     case 0x08:
         LOG_INFO("Conversational awareness disabled");
+        m_conversationReleaseTimer.stop();
+        if (initialVolume != -1)
+            emit conversationDuckingEnded();
         initialVolume = -1;
         break;
 
@@ -138,16 +147,25 @@ void MediaController::handleConversationalAwareness(const QByteArray &data) {
     default:
         LOG_INFO("Conversational awareness event: voice ended");
 
-        if (initialVolume != -1 && isActiveOutputDeviceAirPods()) {
-            QString sink = m_pulseAudio->getDefaultSink();
-            m_pulseAudio->setSinkVolume(sink, initialVolume);
-            LOG_INFO("Volume restored to " << initialVolume << "%");
-            initialVolume = -1;
+        if (initialVolume != -1 && !m_conversationReleaseTimer.isActive()) {
+            if (m_conversationReleaseTimer.interval() > 0)
+                LOG_DEBUG("Restoring volume in " << m_conversationReleaseTimer.interval() << " ms");
+            m_conversationReleaseTimer.start();
         }
         break;
     }
 }
 
+void MediaController::restoreConversationVolume() {
+    if (initialVolume != -1 && isActiveOutputDeviceAirPods()) {
+        QString sink = m_pulseAudio->getDefaultSink();
+        m_pulseAudio->setSinkVolume(sink, initialVolume);
+        LOG_INFO("Volume restored to " << initialVolume << "%");
+        initialVolume = -1;
+        emit conversationDuckingEnded();
+    }
+}
+
 
 bool MediaController::isA2dpProfileAvailable() {
   if (m_deviceOutputName.isEmpty()) {
@@ -227,6 +245,110 @@ void MediaController::activateA2dpProfile() {
     LOG_ERROR("Failed to activate A2DP profile: " << preferredProfile);
   }
   LOG_INFO("A2DP profile activated successfully");
+
+  if (!m_preferredCodec.isEmpty() && activeCodec() != m_preferredCodec) {
+    setCodec(m_preferredCodec);
+  }
+  emit airPodsOutputActivated();
+}
+
+bool MediaController::switchDefaultOutputToAirPods(bool moveStreams) {
+  QString sink = m_pulseAudio->getSinkNameForDevice(connectedDeviceMacAddress);
+  if (connectedDeviceMacAddress.isEmpty() || sink.isEmpty()) {
+    LOG_WARN("No AirPods sink found, cannot make it the default output");
+    return false;
+  }
+
+  QString current = m_pulseAudio->getDefaultSink();
+  if (current == sink) {
+    return true;
+  }
+  if (!m_pulseAudio->setDefaultSink(sink)) {
+    LOG_ERROR("Failed to make " << sink << " the default output");
+    return false;
+  }
+  // Keep the first previous default, in case this runs again before the AirPods disconnect
+  if (m_previousDefaultSink.isEmpty()) {
+    m_previousDefaultSink = current;
+  }
+  LOG_INFO("Default output switched from " << current << " to " << sink);
+  if (moveStreams) {
+    LOG_INFO("Moved " << m_pulseAudio->moveSinkInputs(sink) << " stream(s) to the AirPods");
+  }
+  return true;
+}
+
+void MediaController::restoreDefaultOutput(bool moveStreams) {
+  if (m_previousDefaultSink.isEmpty()) {
+    return;
+  }
+  QString previous = m_previousDefaultSink;
+  m_previousDefaultSink.clear();
+
+  if (!m_pulseAudio->setDefaultSink(previous)) {
+    LOG_WARN("Could not restore the default output " << previous);
+    return;
+  }
+  LOG_INFO("Default output restored to " << previous);
+  if (moveStreams) {
+    m_pulseAudio->moveSinkInputs(previous);
+  }
+}
+
+int MediaController::airPodsVolume() {
+  if (!isActiveOutputDeviceAirPods()) {
+    return -1;
+  }
+  return m_pulseAudio->getSinkVolume(m_pulseAudio->getDefaultSink());
+}
+
+bool MediaController::setAirPodsSinkVolume(int percent) {
+  QString sink = m_pulseAudio->getSinkNameForDevice(connectedDeviceMacAddress);
+  if (connectedDeviceMacAddress.isEmpty() || sink.isEmpty()) {
+    return false;
+  }
+  return m_pulseAudio->setSinkVolume(sink, percent);
+}
+
+bool MediaController::isAudioServerAvailable() const {
+  return m_pulseAudio->isInitialized();
+}
+
+bool MediaController::isAudioPlayingToAirPods() {
+  if (connectedDeviceMacAddress.isEmpty()) {
+    return false;
+  }
+  return m_pulseAudio->isSinkRunning(m_pulseAudio->getSinkNameForDevice(connectedDeviceMacAddress));
+}
+
+bool MediaController::setAirPodsVolume(int percent) {
+  if (!isActiveOutputDeviceAirPods()) {
+    return false;
+  }
+  return m_pulseAudio->setSinkVolume(m_pulseAudio->getDefaultSink(), percent);
+}
+
+QList<QPair<QString, QString>> MediaController::availableCodecs() {
+  return m_pulseAudio->listBluetoothCodecs(m_deviceOutputName);
+}
+
+QString MediaController::activeCodec() {
+  return m_pulseAudio->getBluetoothCodec(m_deviceOutputName);
+}
+
+bool MediaController::setCodec(const QString &codec) {
+  // Detects the message format of the sound server before switching
+  bool known = false;
+  for (const auto &entry : availableCodecs()) {
+    known = known || entry.first == codec;
+  }
+  if (!known) {
+    LOG_WARN("Codec " << codec << " is not available for " << m_deviceOutputName);
+    return false;
+  }
+
+  LOG_INFO("Switching A2DP codec to " << codec);
+  return m_pulseAudio->setBluetoothCodec(m_deviceOutputName, codec);
 }
 
 void MediaController::removeAudioOutputDevice() {
@@ -2,6 +2,7 @@
 #define MEDIACONTROLLER_H
 
 #include <QObject>
+#include <QTimer>
 #include "pulseaudiocontroller.h"
 
 class QProcess;
@@ -35,6 +36,9 @@ public:
   void followMediaChanges();
   bool isActiveOutputDeviceAirPods();
   void handleConversationalAwareness(const QByteArray &data);
+  // How long to wait after the AirPods report the end of speech before restoring the volume
+  void setConversationReleaseDelay(int ms) { m_conversationReleaseTimer.setInterval(ms); }
+  int conversationReleaseDelay() const { return m_conversationReleaseTimer.interval(); }
   void activateA2dpProfile();
   void removeAudioOutputDevice();
   void setConnectedDeviceMacAddress(const QString &macAddress);
@@ -42,6 +46,28 @@ public:
   QString getPreferredA2dpProfile();
   bool restartWirePlumber();
 
+  // Volume of the default sink in percent while it is the AirPods, -1 otherwise
+  int airPodsVolume();
+  bool setAirPodsVolume(int percent);
+  // Makes the AirPods the default output, remembering the previous default for restoreDefaultOutput()
+  bool switchDefaultOutputToAirPods(bool moveStreams);
+  void restoreDefaultOutput(bool moveStreams);
+
+  // Volume of the AirPods sink even while it isn't the default one
+  bool setAirPodsSinkVolume(int percent);
+
+  // Connected to a PulseAudio or pipewire-pulse server at startup
+  bool isAudioServerAvailable() const;
+
+  // A stream is playing to the AirPods sink, whether or not it's the default
+  bool isAudioPlayingToAirPods();
+
+  QList<QPair<QString, QString>> availableCodecs();
+  QString activeCodec();
+  bool setCodec(const QString &codec);
+  // Codec to switch to whenever the A2DP profile gets activated, empty keeps the sound server's choice
+  void setPreferredCodec(const QString &codec) { m_preferredCodec = codec; }
+
   void setEarDetectionBehavior(EarDetectionBehavior behavior);
   inline EarDetectionBehavior getEarDetectionBehavior() const { return earDetectionBehavior; }
 
@@ -51,20 +77,29 @@ public:
 
 Q_SIGNALS:
   void mediaStateChanged(MediaState state);
+  // Conversational awareness lowered / restored the volume
+  void conversationDuckingStarted();
+  void conversationDuckingEnded();
+  // The A2DP profile was activated, so the AirPods sink exists now
+  void airPodsOutputActivated();
 
 private:
   MediaState mediaStateFromPlayerctlOutput(const QString &output) const;
   QString getAudioDeviceName();
   QStringList getPlayingMediaPlayers();
+  void restoreConversationVolume();
 
   QStringList pausedByAppServices;
   int initialVolume = -1;
+  QTimer m_conversationReleaseTimer;
   QString connectedDeviceMacAddress;
   EarDetectionBehavior earDetectionBehavior = PauseWhenOneRemoved;
   QString m_deviceOutputName;
   PlayerStatusWatcher *playerStatusWatcher = nullptr;
   PulseAudioController *m_pulseAudio = nullptr;
   QString m_cachedA2dpProfile;
+  QString m_preferredCodec;
+  QString m_previousDefaultSink;
 };
 
 #endif // MEDIACONTROLLER_H
\ No newline at end of file
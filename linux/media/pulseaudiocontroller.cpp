@@ -1,6 +1,10 @@
 #include "pulseaudiocontroller.h"
 #include "logger.h"
 #include <QThread>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QRegularExpression>
 
 PulseAudioController::PulseAudioController(QObject *parent)
     : QObject(parent), m_mainloop(nullptr), m_context(nullptr), m_initialized(false)
@@ -172,6 +176,76 @@ bool PulseAudioController::setSinkVolume(const QString &sinkName, int volumePerc
     return success;
 }
 
+bool PulseAudioController::setDefaultSink(const QString &sinkName)
+{
+    if (!m_initialized || sinkName.isEmpty()) return false;
+
+    pa_threaded_mainloop_lock(m_mainloop);
+
+    auto successCallback = [](pa_context *c, int success, void *userdata) {
+        pa_threaded_mainloop *mainloop = static_cast<pa_threaded_mainloop*>(userdata);
+        pa_threaded_mainloop_signal(mainloop, 0);
+    };
+
+    pa_operation *op = pa_context_set_default_sink(m_context, sinkName.toUtf8().constData(), successCallback, m_mainloop);
+    bool success = waitForOperation(op);
+    if (op) pa_operation_unref(op);
+    pa_threaded_mainloop_unlock(m_mainloop);
+
+    return success;
+}
+
+int PulseAudioController::moveSinkInputs(const QString &sinkName)
+{
+    if (!m_initialized || sinkName.isEmpty()) return 0;
+
+    struct CallbackData {
+        QList<uint32_t> indexes;
+        pa_threaded_mainloop *mainloop;
+    } data;
+    data.mainloop = m_mainloop;
+
+    auto listCallback = [](pa_context *c, const pa_sink_input_info *info, int eol, void *userdata) {
+        CallbackData *d = static_cast<CallbackData*>(userdata);
+        if (eol > 0)
+        {
+            pa_threaded_mainloop_signal(d->mainloop, 0);
+            return;
+        }
+        if (info)
+        {
+            d->indexes.append(info->index);
+        }
+    };
+
+    auto successCallback = [](pa_context *c, int success, void *userdata) {
+        pa_threaded_mainloop *mainloop = static_cast<pa_threaded_mainloop*>(userdata);
+        pa_threaded_mainloop_signal(mainloop, 0);
+    };
+
+    pa_threaded_mainloop_lock(m_mainloop);
+    pa_operation *op = pa_context_get_sink_input_info_list(m_context, listCallback, &data);
+    if (op)
+    {
+        waitForOperation(op);
+        pa_operation_unref(op);
+    }
+
+    int moved = 0;
+    for (uint32_t index : data.indexes)
+    {
+        op = pa_context_move_sink_input_by_name(m_context, index, sinkName.toUtf8().constData(), successCallback, m_mainloop);
+        if (waitForOperation(op))
+        {
+            moved++;
+        }
+        if (op) pa_operation_unref(op);
+    }
+    pa_threaded_mainloop_unlock(m_mainloop);
+
+    return moved;
+}
+
 bool PulseAudioController::setCardProfile(const QString &cardName, const QString &profileName)
 {
     if (!m_initialized) return false;
@@ -236,6 +310,85 @@ QString PulseAudioController::getCardNameForDevice(const QString &macAddress)
     return data.cardName;
 }
 
+QString PulseAudioController::getSinkNameForDevice(const QString &macAddress)
+{
+    if (!m_initialized) return QString();
+
+    struct CallbackData {
+        QString sinkName;
+        QString targetMac;
+        pa_threaded_mainloop *mainloop;
+    } data;
+    data.targetMac = macAddress;
+    data.mainloop = m_mainloop;
+
+    auto callback = [](pa_context *c, const pa_sink_info *info, int eol, void *userdata) {
+        CallbackData *d = static_cast<CallbackData*>(userdata);
+        if (eol > 0)
+        {
+            pa_threaded_mainloop_signal(d->mainloop, 0);
+            return;
+        }
+        if (info)
+        {
+            QString name = QString::fromUtf8(info->name);
+            if (name.startsWith("bluez") && name.contains(d->targetMac))
+            {
+                d->sinkName = name;
+                pa_threaded_mainloop_signal(d->mainloop, 0);
+            }
+        }
+    };
+
+    pa_threaded_mainloop_lock(m_mainloop);
+    pa_operation *op = pa_context_get_sink_info_list(m_context, callback, &data);
+    if (op)
+    {
+        waitForOperation(op);
+        pa_operation_unref(op);
+    }
+    pa_threaded_mainloop_unlock(m_mainloop);
+
+    return data.sinkName;
+}
+
+bool PulseAudioController::isSinkRunning(const QString &sinkName)
+{
+    if (!m_initialized || sinkName.isEmpty()) return false;
+
+    struct CallbackData {
+        bool running;
+        pa_threaded_mainloop *mainloop;
+    } data;
+    data.running = false;
+    data.mainloop = m_mainloop;
+
+    auto callback = [](pa_context *c, const pa_sink_info *info, int eol, void *userdata) {
+        CallbackData *d = static_cast<CallbackData*>(userdata);
+        if (eol > 0)
+        {
+            pa_threaded_mainloop_signal(d->mainloop, 0);
+            return;
+        }
+        if (info)
+        {
+            d->running = info->state == PA_SINK_RUNNING;
+            pa_threaded_mainloop_signal(d->mainloop, 0);
+        }
+    };
+
+    pa_threaded_mainloop_lock(m_mainloop);
+    pa_operation *op = pa_context_get_sink_info_by_name(m_context, sinkName.toUtf8().constData(), callback, &data);
+    if (op)
+    {
+        waitForOperation(op);
+        pa_operation_unref(op);
+    }
+    pa_threaded_mainloop_unlock(m_mainloop);
+
+    return data.running;
+}
+
 bool PulseAudioController::isProfileAvailable(const QString &cardName, const QString &profileName)
 {
     if (!m_initialized) return false;
@@ -284,6 +437,96 @@ bool PulseAudioController::isProfileAvailable(const QString &cardName, const QSt
     return data.available;
 }
 
+bool PulseAudioController::sendCardMessage(const QString &cardName, const QString &message, const QString &params, QString *response)
+{
+    if (!m_initialized || cardName.isEmpty()) return false;
+
+    struct CallbackData {
+        bool success;
+        QString response;
+        pa_threaded_mainloop *mainloop;
+    } data;
+    data.success = false;
+    data.mainloop = m_mainloop;
+
+    auto callback = [](pa_context *c, int success, char *response, void *userdata) {
+        CallbackData *d = static_cast<CallbackData*>(userdata);
+        d->success = success;
+        d->response = QString::fromUtf8(response ? response : "");
+        pa_threaded_mainloop_signal(d->mainloop, 0);
+    };
+
+    QByteArray object = ("/card/" + cardName + "/bluez").toUtf8();
+    pa_threaded_mainloop_lock(m_mainloop);
+    pa_operation *op = pa_context_send_message_to_object(m_context, object.constData(), message.toUtf8().constData(),
+                                                         params.isEmpty() ? nullptr : params.toUtf8().constData(),
+                                                         callback, &data);
+    if (op)
+    {
+        waitForOperation(op);
+        pa_operation_unref(op);
+    }
+    pa_threaded_mainloop_unlock(m_mainloop);
+
+    if (!data.success)
+    {
+        LOG_WARN("Sound server rejected " << message << " for " << cardName);
+        return false;
+    }
+    if (response)
+    {
+        *response = data.response;
+    }
+    return true;
+}
+
+QList<QPair<QString, QString>> PulseAudioController::listBluetoothCodecs(const QString &cardName)
+{
+    QList<QPair<QString, QString>> codecs;
+    QString response;
+    if (!sendCardMessage(cardName, "list-codecs", QString(), &response))
+    {
+        return codecs;
+    }
+
+    // pipewire-pulse: [{"name":"sbc","description":"SBC"},...]
+    QJsonDocument json = QJsonDocument::fromJson(response.toUtf8());
+    m_jsonMessages = json.isArray();
+    if (m_jsonMessages)
+    {
+        for (const QJsonValue &codec : json.array())
+        {
+            codecs.append({codec.toObject().value("name").toString(), codec.toObject().value("description").toString()});
+        }
+        return codecs;
+    }
+
+    // PulseAudio: {{{sbc}{SBC}}{{aac}{AAC}}}
+    static const QRegularExpression entry("\\{\\{([^{}]*)\\}\\{([^{}]*)\\}\\}");
+    for (const QRegularExpressionMatch &match : entry.globalMatch(response))
+    {
+        codecs.append({match.captured(1), match.captured(2)});
+    }
+    return codecs;
+}
+
+QString PulseAudioController::getBluetoothCodec(const QString &cardName)
+{
+    QString response;
+    if (!sendCardMessage(cardName, "get-codec", QString(), &response))
+    {
+        return QString();
+    }
+    // Either "sbc" or {sbc}
+    return response.remove(QRegularExpression("[{}\"\\s]"));
+}
+
+bool PulseAudioController::setBluetoothCodec(const QString &cardName, const QString &codec)
+{
+    QString params = m_jsonMessages ? "\"" + codec + "\"" : "{" + codec + "}";
+    return sendCardMessage(cardName, "switch-codec", params);
+}
+
 bool PulseAudioController::waitForOperation(pa_operation *op)
 {
     if (!op) return false;
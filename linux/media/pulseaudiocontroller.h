@@ -3,6 +3,8 @@
 
 #include <QString>
 #include <QObject>
+#include <QList>
+#include <QPair>
 #include <pulse/pulseaudio.h>
 
 class PulseAudioController : public QObject
@@ -14,13 +16,26 @@ public:
     ~PulseAudioController();
 
     bool initialize();
+    bool isInitialized() const { return m_initialized; }
     QString getDefaultSink();
     int getSinkVolume(const QString &sinkName);
     bool setSinkVolume(const QString &sinkName, int volumePercent);
+    bool setDefaultSink(const QString &sinkName);
+    // Moves every playing stream to the sink, returns how many were moved
+    int moveSinkInputs(const QString &sinkName);
     bool setCardProfile(const QString &cardName, const QString &profileName);
     QString getCardNameForDevice(const QString &macAddress);
+    // Sink of a Bluetooth device, macAddress in the XX_XX_XX_XX_XX_XX form BlueZ uses in names
+    QString getSinkNameForDevice(const QString &macAddress);
+    // Whether any stream is currently playing to the sink
+    bool isSinkRunning(const QString &sinkName);
     bool isProfileAvailable(const QString &cardName, const QString &profileName);
 
+    // A2DP codec selection through the "/card/<name>/bluez" message handler (PulseAudio 15+ / pipewire-pulse)
+    QList<QPair<QString, QString>> listBluetoothCodecs(const QString &cardName);
+    QString getBluetoothCodec(const QString &cardName);
+    bool setBluetoothCodec(const QString &cardName, const QString &codec);
+
 private:
     pa_threaded_mainloop *m_mainloop;
     pa_context *m_context;
@@ -32,6 +47,10 @@ private:
     static void serverInfoCallback(pa_context *c, const pa_server_info *info, void *userdata);
 
     bool waitForOperation(pa_operation *op);
+    bool sendCardMessage(const QString &cardName, const QString &message, const QString &params, QString *response = nullptr);
+
+    // pipewire-pulse answers in JSON, PulseAudio in its {}-delimited message parameter format
+    bool m_jsonMessages = false;
 };
 
 #endif // PULSEAUDIOCONTROLLER_H
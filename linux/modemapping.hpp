@@ -0,0 +1,91 @@
+#pragma once
+
+#include <QFile>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QJsonObject>
+
+#include <array>
+#include <optional>
+
+#include "BasicControlCommand.hpp"
+#include "enums.h"
+#include "logger.h"
+#include "storage.hpp"
+
+// Byte values of the listening modes in the 0x0D control command. Off/ANC/Transparency/Adaptive
+// are 0x01-0x04 on every firmware seen so far; <config dir>/mode-mappings.json can override them
+// for a model and firmware prefix, the first matching entry wins:
+//   [{"model": "A2931", "firmware": "7A", "modes": {"off": 1, "anc": 2, "transparency": 3, "adaptive": 4}}]
+class ModeMapping
+{
+public:
+    using NoiseControlMode = AirpodsTrayApp::Enums::NoiseControlMode;
+
+    static QString filePath() { return Storage::configDir() + "/mode-mappings.json"; }
+
+    static ModeMapping load(const QString &model, const QString &firmware)
+    {
+        QFile file(filePath());
+        if (!file.open(QIODevice::ReadOnly))
+            return ModeMapping();
+
+        QJsonParseError error;
+        QJsonDocument document = QJsonDocument::fromJson(file.readAll(), &error);
+        if (error.error != QJsonParseError::NoError || !document.isArray())
+        {
+            LOG_WARN("Ignoring invalid " << file.fileName() << ": " << error.errorString());
+            return ModeMapping();
+        }
+        return select(document.array(), model, firmware);
+    }
+
+    // Entries without a model or firmware match any
+    static ModeMapping select(const QJsonArray &entries, const QString &model, const QString &firmware)
+    {
+        static const QStringList keys = {"off", "anc", "transparency", "adaptive"};
+        for (const QJsonValue &value : entries)
+        {
+            QJsonObject entry = value.toObject();
+            QString entryModel = entry.value("model").toString();
+            QString entryFirmware = entry.value("firmware").toString();
+            if ((!entryModel.isEmpty() && entryModel != model) || (!entryFirmware.isEmpty() && !firmware.startsWith(entryFirmware)))
+                continue;
+
+            ModeMapping mapping;
+            QJsonObject modes = entry.value("modes").toObject();
+            for (int i = 0; i < keys.size(); ++i)
+                mapping.m_values[i] = static_cast<quint8>(modes.value(keys[i]).toInt(mapping.m_values[i]));
+            mapping.m_overridden = true;
+            return mapping;
+        }
+        return ModeMapping();
+    }
+
+    bool isOverridden() const { return m_overridden; }
+
+    quint8 valueFor(NoiseControlMode mode) const { return m_values[static_cast<int>(mode)]; }
+
+    QByteArray packetFor(NoiseControlMode mode) const
+    {
+        return ControlCommand::createCommand(0x0D, valueFor(mode));
+    }
+
+    std::optional<NoiseControlMode> modeFor(quint8 value) const
+    {
+        for (int i = 0; i < static_cast<int>(m_values.size()); ++i)
+            if (m_values[i] == value)
+                return static_cast<NoiseControlMode>(i);
+        return std::nullopt;
+    }
+
+    std::optional<NoiseControlMode> parse(const QByteArray &packet) const
+    {
+        auto value = ControlCommand::parseActive(packet);
+        return value ? modeFor(static_cast<quint8>(value.value())) : std::nullopt;
+    }
+
+private:
+    std::array<quint8, 4> m_values{0x01, 0x02, 0x03, 0x04};
+    bool m_overridden = false;
+};
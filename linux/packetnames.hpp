@@ -0,0 +1,114 @@
+#pragma once
+
+#include <QByteArray>
+#include <QMetaEnum>
+#include <QString>
+#include <QStringList>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+#include "enums.h"
+
+// Readable names for AACP packets, so logs and the developer tools say "ListeningMode=Adaptive"
+// instead of showing opcodes only
+namespace PacketNames
+{
+    inline QString controlCommandName(quint8 identifier)
+    {
+        switch (identifier)
+        {
+        case 0x0D: return "ListeningMode";
+        case AirPodsPackets::OneBudANCMode::Type::ID: return "OneBudANCMode";
+        case AirPodsPackets::ChimeVolume::ID: return "ChimeVolume";
+        case AirPodsPackets::CallManagement::ID: return "CallManagement";
+        case AirPodsPackets::VolumeSwipe::Type::ID: return "VolumeSwipe";
+        case AirPodsPackets::AdaptiveVolume::Type::ID: return "AdaptiveVolume";
+        case AirPodsPackets::ConversationalAwareness::Type::ID: return "ConversationalAwareness";
+        case 0x2C: return "HearingAid";
+        case 0x2E: return "AdaptiveNoiseLevel";
+        case AirPodsPackets::HearingAssist::Type::ID: return "HearingAssist";
+        case AirPodsPackets::AllowOffOption::Type::ID: return "AllowOffOption";
+        default: return QString("0x%1").arg(identifier, 2, 16, QChar('0'));
+        }
+    }
+
+    inline QString batteryComponentName(quint8 code)
+    {
+        const char *name = QMetaEnum::fromType<Battery::Component>().valueToKey(code);
+        return name ? QString::fromLatin1(name) : QString("0x%1").arg(code, 2, 16, QChar('0'));
+    }
+
+    inline QString controlCommandValue(quint8 identifier, const QByteArray &packet)
+    {
+        quint8 value = static_cast<quint8>(ControlCommand::parseActive(packet).value_or(0));
+        switch (identifier)
+        {
+        case 0x0D:
+            if (auto mode = AirPodsPackets::NoiseControl::parseMode(packet))
+                return QString::fromLatin1(QMetaEnum::fromType<AirpodsTrayApp::Enums::NoiseControlMode>().valueToKey(static_cast<int>(mode.value())));
+            break;
+        case AirPodsPackets::ChimeVolume::ID:
+        case 0x2E:
+            return QString::number(value);
+        case AirPodsPackets::CallManagement::ID:
+            if (auto flipped = AirPodsPackets::CallManagement::parseFlipped(packet))
+                return flipped.value() ? "Flipped" : "Default";
+            break;
+        case 0x2C:
+            if (auto enabled = AirPodsPackets::HearingAid::parseState(packet))
+                return enabled.value() ? "On" : "Off";
+            break;
+        default:
+            if (value == 0x01)
+                return "On";
+            if (value == 0x02)
+                return "Off";
+            break;
+        }
+        return QString("0x%1").arg(value, 2, 16, QChar('0'));
+    }
+
+    // Empty for packets without a known layout, callers still log the hex dump
+    inline QString describe(const QByteArray &packet)
+    {
+        using namespace AirPodsPackets;
+
+        if (packet == Connection::HANDSHAKE)
+            return "Handshake";
+        if (packet == Connection::SET_SPECIFIC_FEATURES)
+            return "SetSpecificFeatures";
+        if (packet == Connection::REQUEST_NOTIFICATIONS)
+            return "RequestNotifications";
+        if (packet.startsWith(Parse::HANDSHAKE_ACK))
+            return "HandshakeAck";
+        if (packet.startsWith(Parse::FEATURES_ACK))
+            return "FeaturesAck";
+        if (packet.startsWith(Parse::METADATA))
+            return "Metadata";
+        if (packet.size() == 8 && packet.startsWith(Parse::EAR_DETECTION))
+            return QString("EarDetection primary=0x%1 secondary=0x%2")
+                .arg(static_cast<quint8>(packet.at(6)), 2, 16, QChar('0'))
+                .arg(static_cast<quint8>(packet.at(7)), 2, 16, QChar('0'));
+
+        if (packet.startsWith(Parse::BATTERY_STATUS) && packet.size() >= 7)
+        {
+            QStringList components;
+            for (int offset = 7; offset + 5 <= packet.size(); offset += 5)
+            {
+                auto status = static_cast<Battery::BatteryStatus>(packet.at(offset + 3));
+                const char *statusName = QMetaEnum::fromType<Battery::BatteryStatus>().valueToKey(static_cast<int>(status));
+                components << QString("%1=%2% %3").arg(batteryComponentName(static_cast<quint8>(packet.at(offset))))
+                                  .arg(static_cast<quint8>(packet.at(offset + 2)))
+                                  .arg(statusName ? statusName : "Unknown");
+            }
+            return "Battery " + components.join(", ");
+        }
+
+        if (packet.startsWith(ControlCommand::HEADER) && packet.size() > ControlCommand::HEADER.size())
+        {
+            quint8 identifier = static_cast<quint8>(packet.at(ControlCommand::HEADER.size()));
+            return controlCommandName(identifier) + "=" + controlCommandValue(identifier, packet);
+        }
+        return QString();
+    }
+}
@@ -0,0 +1,122 @@
+#pragma once
+
+#include <QDir>
+#include <QFileInfo>
+#include <QJsonDocument>
+#include <QJsonObject>
+#include <QObject>
+#include <QProcess>
+
+#include "logger.h"
+#include "storage.hpp"
+
+// Runs every executable in <config dir>/plugins as a child process. Plugins
+// receive device events as one JSON object per line on stdin, e.g.
+//   {"event":"earDetection","primary":"InEar","secondary":"NotInEar"}
+// and can send commands back the same way on stdout, e.g.
+//   {"command":"setNoiseControlMode","mode":1}
+// Anything a plugin writes to stderr ends up in our log.
+class PluginManager : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit PluginManager(QObject *parent = nullptr) : QObject(parent) {}
+
+    ~PluginManager()
+    {
+        for (QProcess *plugin : std::as_const(m_plugins))
+        {
+            plugin->disconnect(this);
+            plugin->terminate();
+            if (!plugin->waitForFinished(1000))
+            {
+                plugin->kill();
+            }
+        }
+    }
+
+    static QString pluginDir() { return Storage::configDir() + "/plugins"; }
+
+    void loadPlugins()
+    {
+        const QFileInfoList entries = QDir(pluginDir()).entryInfoList(QDir::Files | QDir::Executable, QDir::Name);
+        for (const QFileInfo &entry : entries)
+        {
+            startPlugin(entry);
+        }
+        if (!m_plugins.isEmpty())
+        {
+            LOG_INFO("Started " << m_plugins.size() << " plugin(s) from " << pluginDir());
+        }
+    }
+
+    void sendEvent(const QString &event, QJsonObject data = QJsonObject())
+    {
+        if (m_plugins.isEmpty())
+        {
+            return;
+        }
+
+        data.insert("event", event);
+        QByteArray line = QJsonDocument(data).toJson(QJsonDocument::Compact) + '\n';
+        for (QProcess *plugin : std::as_const(m_plugins))
+        {
+            if (plugin->state() == QProcess::Running)
+            {
+                plugin->write(line);
+            }
+        }
+    }
+
+signals:
+    void commandReceived(const QString &plugin, const QJsonObject &command);
+
+private:
+    void startPlugin(const QFileInfo &entry)
+    {
+        QString name = entry.fileName();
+        QProcess *plugin = new QProcess(this);
+        plugin->setProgram(entry.absoluteFilePath());
+        plugin->setWorkingDirectory(entry.absolutePath());
+
+        connect(plugin, &QProcess::readyReadStandardOutput, this, [this, plugin, name]()
+        {
+            while (plugin->canReadLine())
+            {
+                QByteArray line = plugin->readLine().trimmed();
+                if (line.isEmpty())
+                    continue;
+
+                QJsonParseError error;
+                QJsonDocument document = QJsonDocument::fromJson(line, &error);
+                if (!document.isObject())
+                {
+                    LOG_WARN("Plugin " << name << " sent invalid JSON: " << error.errorString());
+                    continue;
+                }
+                emit commandReceived(name, document.object());
+            }
+        });
+        connect(plugin, &QProcess::readyReadStandardError, this, [plugin, name]()
+        {
+            LOG_INFO("[plugin " << name << "] " << plugin->readAllStandardError().trimmed());
+        });
+        connect(plugin, &QProcess::errorOccurred, this, [plugin, name](QProcess::ProcessError)
+        {
+            LOG_WARN("Plugin " << name << " failed: " << plugin->errorString());
+        });
+        connect(plugin, &QProcess::finished, this, [this, plugin, name](int exitCode)
+        {
+            LOG_INFO("Plugin " << name << " exited with code " << exitCode);
+            m_plugins.removeOne(plugin);
+            plugin->deleteLater();
+        });
+
+        LOG_DEBUG("Starting plugin " << name);
+        plugin->start();
+        m_plugins.append(plugin);
+    }
+
+    QList<QProcess *> m_plugins;
+};
@@ -0,0 +1,46 @@
+#pragma once
+
+#include <QtGlobal>
+
+#include <array>
+#include <numeric>
+
+// Events in the last minute, counted in one-second buckets so high-frequency streams like
+// head tracking don't need a timestamp per event
+class RateCounter
+{
+public:
+    void add(qint64 nowMs)
+    {
+        advance(nowMs);
+        m_buckets[bucket(m_second)]++;
+    }
+
+    int perMinute(qint64 nowMs)
+    {
+        advance(nowMs);
+        return std::accumulate(m_buckets.cbegin(), m_buckets.cend(), 0);
+    }
+
+private:
+    static constexpr int Seconds = 60;
+
+    static int bucket(qint64 second) { return static_cast<int>(second % Seconds); }
+
+    // Clears the buckets of the seconds that passed without events
+    void advance(qint64 nowMs)
+    {
+        qint64 second = nowMs / 1000;
+        if (second <= m_second)
+            return;
+        if (second - m_second >= Seconds)
+            m_buckets.fill(0);
+        else
+            for (qint64 s = m_second + 1; s <= second; ++s)
+                m_buckets[bucket(s)] = 0;
+        m_second = second;
+    }
+
+    std::array<int, Seconds> m_buckets{};
+    qint64 m_second = 0;
+};
@@ -0,0 +1,161 @@
+#pragma once
+
+#include <QDir>
+#include <QFile>
+#include <QHash>
+#include <QJSEngine>
+#include <QJSValue>
+#include <QJsonObject>
+#include <QObject>
+#include <QProcess>
+#include <QTimer>
+
+#include "logger.h"
+#include "storage.hpp"
+
+// The `librepods` object available to scripts
+class ScriptApi : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit ScriptApi(QJSEngine *engine, QObject *parent = nullptr) : QObject(parent), m_engine(engine) {}
+
+    // Registers a callback for an event, it is called with the event fields as an object
+    Q_INVOKABLE void on(const QString &event, const QJSValue &callback)
+    {
+        if (!callback.isCallable())
+        {
+            LOG_WARN("librepods.on(\"" << event << "\") needs a function");
+            return;
+        }
+        m_handlers[event].append(callback);
+    }
+
+    // Last seen fields of an event, e.g. state("battery").left
+    Q_INVOKABLE QJSValue state(const QString &event) const
+    {
+        return m_engine->toScriptValue(m_state.value(event).toVariantMap());
+    }
+
+    Q_INVOKABLE int after(int ms, const QJSValue &callback)
+    {
+        int id = ++m_lastTimerId;
+        QTimer *timer = new QTimer(this);
+        timer->setSingleShot(true);
+        connect(timer, &QTimer::timeout, this, [this, id, timer, callback]()
+        {
+            m_timers.remove(id);
+            timer->deleteLater();
+            report(QJSValue(callback).call());
+        });
+        m_timers.insert(id, timer);
+        timer->start(ms);
+        return id;
+    }
+
+    Q_INVOKABLE void cancel(int id)
+    {
+        if (QTimer *timer = m_timers.take(id))
+        {
+            timer->stop();
+            timer->deleteLater();
+        }
+    }
+
+    Q_INVOKABLE void setNoiseControlMode(int mode) { command("setNoiseControlMode", {{"mode", mode}}); }
+    Q_INVOKABLE void setConversationalAwareness(bool enabled) { command("setConversationalAwareness", {{"enabled", enabled}}); }
+    Q_INVOKABLE void setAdaptiveNoiseLevel(int level) { command("setAdaptiveNoiseLevel", {{"level", level}}); }
+    Q_INVOKABLE void notify(const QString &title, const QString &message) { command("notify", {{"title", title}, {"message", message}}); }
+
+    Q_INVOKABLE bool run(const QString &program, const QStringList &arguments = QStringList())
+    {
+        return QProcess::startDetached(program, arguments);
+    }
+
+    Q_INVOKABLE void log(const QString &message) { LOG_INFO("[script] " << message); }
+
+    void dispatch(const QString &event, const QJsonObject &data)
+    {
+        m_state.insert(event, data);
+        const QList<QJSValue> handlers = m_handlers.value(event);
+        for (QJSValue handler : handlers)
+        {
+            report(handler.call({m_engine->toScriptValue(data.toVariantMap())}));
+        }
+    }
+
+    void report(const QJSValue &result) const
+    {
+        if (result.isError())
+        {
+            LOG_WARN("Script error at line " << result.property("lineNumber").toInt() << ": " << result.toString());
+        }
+    }
+
+signals:
+    void commandReceived(const QString &source, const QJsonObject &command);
+
+private:
+    void command(const QString &name, QJsonObject fields)
+    {
+        fields.insert("command", name);
+        emit commandReceived("script", fields);
+    }
+
+    QJSEngine *m_engine;
+    QHash<QString, QList<QJSValue>> m_handlers;
+    QHash<QString, QJsonObject> m_state;
+    QHash<int, QTimer *> m_timers;
+    int m_lastTimerId = 0;
+};
+
+// Loads the *.js files in <config dir>/scripts into a shared JavaScript engine.
+// Scripts see the same events and commands as external plugins, see pluginmanager.hpp.
+class ScriptHost : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit ScriptHost(QObject *parent = nullptr) : QObject(parent), m_api(new ScriptApi(&m_engine, this))
+    {
+        m_engine.installExtensions(QJSEngine::ConsoleExtension);
+        m_engine.globalObject().setProperty("librepods", m_engine.newQObject(m_api));
+        connect(m_api, &ScriptApi::commandReceived, this, &ScriptHost::commandReceived);
+    }
+
+    static QString scriptDir() { return Storage::configDir() + "/scripts"; }
+
+    void loadScripts()
+    {
+        QDir dir(scriptDir());
+        const QStringList files = dir.entryList({"*.js"}, QDir::Files, QDir::Name);
+        for (const QString &fileName : files)
+        {
+            QFile file(dir.filePath(fileName));
+            if (!file.open(QIODevice::ReadOnly | QIODevice::Text))
+            {
+                LOG_WARN("Failed to read script " << file.fileName() << ": " << file.errorString());
+                continue;
+            }
+
+            QJSValue result = m_engine.evaluate(QString::fromUtf8(file.readAll()), file.fileName());
+            if (result.isError())
+            {
+                LOG_WARN("Failed to load script " << fileName << " at line " << result.property("lineNumber").toInt()
+                         << ": " << result.toString());
+                continue;
+            }
+            LOG_INFO("Loaded script " << fileName);
+        }
+    }
+
+    void dispatch(const QString &event, const QJsonObject &data) { m_api->dispatch(event, data); }
+
+signals:
+    void commandReceived(const QString &source, const QJsonObject &command);
+
+private:
+    QJSEngine m_engine;
+    ScriptApi *m_api;
+};
@@ -0,0 +1,43 @@
+#pragma once
+
+#include <QDateTime>
+#include <QObject>
+#include <QVariantList>
+
+#include "logger.h"
+
+// Session log of every setting written to (or reported by) the AirPods, so an
+// unexpected change can be traced back to the window, tray, a plugin or the buds
+class SettingsAuditLog : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(QVariantList entries READ entries NOTIFY entriesChanged)
+
+public:
+    explicit SettingsAuditLog(QObject *parent = nullptr) : QObject(parent) {}
+
+    QVariantList entries() const { return m_entries; }
+
+    void record(const QString &source, const QString &setting, const QVariant &oldValue, const QVariant &newValue)
+    {
+        LOG_DEBUG("Setting changed by " << source << ": " << setting << " " << oldValue.toString() << " -> " << newValue.toString());
+        m_entries.prepend(QVariantMap{{"time", QDateTime::currentDateTime().toString("HH:mm:ss")},
+                                      {"source", source},
+                                      {"setting", setting},
+                                      {"oldValue", oldValue.toString()},
+                                      {"newValue", newValue.toString()}});
+        if (m_entries.size() > MaxEntries)
+        {
+            m_entries.removeLast();
+        }
+        emit entriesChanged();
+    }
+
+signals:
+    void entriesChanged();
+
+private:
+    static constexpr int MaxEntries = 200;
+
+    QVariantList m_entries;
+};
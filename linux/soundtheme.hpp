@@ -0,0 +1,51 @@
+#pragma once
+
+#include <QFile>
+#include <QProcess>
+#include <QStandardPaths>
+#include <QString>
+
+#include "logger.h"
+
+// Plays event sounds from the freedesktop sound theme, e.g. "device-added" or "battery-low"
+namespace SoundTheme
+{
+    // Looks the sound up in the freedesktop theme when libcanberra's player isn't available
+    inline QString findSoundFile(const QString &name)
+    {
+        for (const QString &dataDir : QStandardPaths::standardLocations(QStandardPaths::GenericDataLocation))
+        {
+            for (const QString &extension : {QStringLiteral("oga"), QStringLiteral("ogg"), QStringLiteral("wav")})
+            {
+                QString path = QString("%1/sounds/freedesktop/stereo/%2.%3").arg(dataDir, name, extension);
+                if (QFile::exists(path))
+                    return path;
+            }
+        }
+        return QString();
+    }
+
+    inline bool play(const QString &name)
+    {
+        if (name.isEmpty())
+            return false;
+
+        // canberra-gtk-play follows the theme the desktop has selected
+        if (!QStandardPaths::findExecutable("canberra-gtk-play").isEmpty())
+            return QProcess::startDetached("canberra-gtk-play", {"-i", name, "-d", "LibrePods"});
+
+        QString path = findSoundFile(name);
+        if (path.isEmpty())
+        {
+            LOG_WARN("Sound " << name << " not found in the freedesktop sound theme");
+            return false;
+        }
+        for (const QString &player : {QStringLiteral("pw-play"), QStringLiteral("paplay")})
+        {
+            if (!QStandardPaths::findExecutable(player).isEmpty())
+                return QProcess::startDetached(player, {path});
+        }
+        LOG_WARN("No sound player found, cannot play " << name);
+        return false;
+    }
+}
@@ -0,0 +1,58 @@
+#pragma once
+
+#include <QDir>
+#include <QStandardPaths>
+#include <QString>
+
+// Central place for on-disk locations, following the XDG base directory spec:
+// settings live in the config dir, device state/history in the state dir and
+// anything that can be regenerated in the cache dir.
+namespace Storage
+{
+    inline QString &configDirOverride()
+    {
+        static QString dir;
+        return dir;
+    }
+
+    // Used by --config-dir for portable installs and tests. Everything is then
+    // kept below the given directory instead of the XDG locations.
+    inline void setConfigDirOverride(const QString &dir)
+    {
+        configDirOverride() = QDir(dir).absolutePath();
+    }
+
+    inline QString ensureDir(const QString &path)
+    {
+        QDir().mkpath(path);
+        return path;
+    }
+
+    inline QString configDir()
+    {
+        if (!configDirOverride().isEmpty())
+            return ensureDir(configDirOverride());
+        // Same directory QSettings("AirPodsTrayApp", "AirPodsTrayApp") used, so existing settings are kept
+        return ensureDir(QStandardPaths::writableLocation(QStandardPaths::GenericConfigLocation) + "/AirPodsTrayApp");
+    }
+
+    inline QString stateDir()
+    {
+        if (!configDirOverride().isEmpty())
+            return ensureDir(configDirOverride() + "/state");
+
+        QString stateHome = qEnvironmentVariable("XDG_STATE_HOME");
+        if (stateHome.isEmpty())
+            stateHome = QDir::homePath() + "/.local/state";
+        return ensureDir(stateHome + "/librepods");
+    }
+
+    inline QString cacheDir()
+    {
+        if (!configDirOverride().isEmpty())
+            return ensureDir(configDirOverride() + "/cache");
+        return ensureDir(QStandardPaths::writableLocation(QStandardPaths::GenericCacheLocation) + "/librepods");
+    }
+
+    inline QString settingsFilePath() { return configDir() + "/AirPodsTrayApp.conf"; }
+}
@@ -0,0 +1,160 @@
+#pragma once
+
+#include <QJsonArray>
+#include <QJsonObject>
+#include <QMetaEnum>
+#include <QObject>
+#include <QTimer>
+#include <QVariantList>
+
+#include <algorithm>
+
+#include "enums.h"
+
+// Fake devices for working on multi-device layouts without owning several AirPods. Only
+// created with --debug, nothing is sent over Bluetooth and nothing is saved.
+class SyntheticDevices : public QObject
+{
+    Q_OBJECT
+    Q_PROPERTY(QVariantList devices READ devices NOTIFY devicesChanged)
+
+public:
+    enum Behavior
+    {
+        Static,
+        Draining,
+        Charging,
+        CyclingModes
+    };
+    Q_ENUM(Behavior)
+
+    struct Device
+    {
+        QString address;
+        QString name;
+        AirpodsTrayApp::Enums::AirPodsModel model = AirpodsTrayApp::Enums::AirPodsModel::AirPodsPro2USBC;
+        Behavior behavior = Static;
+        int left = 80;
+        int right = 80;
+        int caseLevel = 60;
+        int mode = 0;
+    };
+
+    explicit SyntheticDevices(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_timer.setInterval(TickMs);
+        connect(&m_timer, &QTimer::timeout, this, &SyntheticDevices::tick);
+    }
+
+    const QList<Device> &list() const { return m_devices; }
+
+    QVariantList devices() const
+    {
+        static const QStringList modes = {"Off", "Noise Cancellation", "Transparency", "Adaptive"};
+        QVariantList list;
+        for (const Device &device : m_devices)
+            list.append(QVariantMap{{"address", device.address}, {"name", device.name}, {"model", QMetaEnum::fromType<AirpodsTrayApp::Enums::AirPodsModel>().valueToKey(static_cast<int>(device.model))},
+                                    {"behavior", QMetaEnum::fromType<Behavior>().valueToKey(device.behavior)},
+                                    {"left", device.left}, {"right", device.right}, {"case", device.caseLevel},
+                                    {"mode", modes.value(device.mode)}});
+        return list;
+    }
+
+    // Same shape as the real device in GET /devices, marked so clients can tell them apart
+    QJsonArray toJson() const
+    {
+        static const QStringList modes = {"off", "anc", "transparency", "adaptive"};
+        QJsonArray array;
+        for (const Device &device : m_devices)
+            array.append(QJsonObject{{"address", device.address}, {"deviceName", device.name}, {"model", static_cast<int>(device.model)},
+                                     {"connected", true}, {"synthetic", true}, {"listening_mode", modes.value(device.mode)},
+                                     {"battery", QJsonObject{{"left", device.left}, {"right", device.right}, {"case", device.caseLevel}}}});
+        return array;
+    }
+
+    // One tick of the scripted behavior, levels wrap around so the script keeps going
+    static void step(Device &device)
+    {
+        switch (device.behavior)
+        {
+        case Draining:
+            device.left = device.left > 0 ? device.left - 1 : 100;
+            device.right = device.right > 1 ? device.right - 2 : 100;
+            break;
+        case Charging:
+            device.left = device.left < 100 ? device.left + 1 : 10;
+            device.right = device.right < 100 ? device.right + 1 : 10;
+            device.caseLevel = std::max(0, device.caseLevel - 1);
+            break;
+        case CyclingModes:
+            device.mode = (device.mode + 1) % 4;
+            break;
+        case Static:
+            break;
+        }
+    }
+
+public slots:
+    // Addresses are locally administered, so they can't collide with a real device. model is an
+    // AirPodsModel value, Unknown picks the AirPods Pro 2
+    QString add(const QString &name, int model, int behavior)
+    {
+        Device device;
+        device.address = QString("02:00:00:00:00:%1").arg(++m_counter & 0xFF, 2, 16, QChar('0')).toUpper();
+        device.name = name.isEmpty() ? QString("Synthetic AirPods %1").arg(m_counter) : name;
+        if (model > static_cast<int>(AirpodsTrayApp::Enums::AirPodsModel::Unknown)
+            && model <= static_cast<int>(AirpodsTrayApp::Enums::AirPodsModel::AirPods4ANC))
+            device.model = static_cast<AirpodsTrayApp::Enums::AirPodsModel>(model);
+        device.behavior = static_cast<Behavior>(std::clamp(behavior, int(Static), int(CyclingModes)));
+        m_devices.append(device);
+        m_timer.start();
+        emit devicesChanged();
+        return device.address;
+    }
+
+    // False when address isn't a synthetic device
+    bool setMode(const QString &address, int mode)
+    {
+        for (Device &device : m_devices)
+        {
+            if (device.address != address)
+                continue;
+            device.mode = std::clamp(mode, 0, 3);
+            emit devicesChanged();
+            return true;
+        }
+        return false;
+    }
+
+    void remove(const QString &address)
+    {
+        m_devices.removeIf([&address](const Device &device) { return device.address == address; });
+        if (m_devices.isEmpty())
+            m_timer.stop();
+        emit devicesChanged();
+    }
+
+    void clear()
+    {
+        m_devices.clear();
+        m_timer.stop();
+        emit devicesChanged();
+    }
+
+signals:
+    void devicesChanged();
+
+private:
+    static constexpr int TickMs = 2000;
+
+    void tick()
+    {
+        for (Device &device : m_devices)
+            step(device);
+        emit devicesChanged();
+    }
+
+    QTimer m_timer;
+    QList<Device> m_devices;
+    int m_counter = 0;
+};
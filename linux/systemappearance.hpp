@@ -0,0 +1,125 @@
+#pragma once
+
+#include <QColor>
+#include <QDBusArgument>
+#include <QDBusConnection>
+#include <QDBusMessage>
+#include <QDBusVariant>
+#include <QObject>
+#include <QPalette>
+
+#include "logger.h"
+
+// Color scheme and accent color from the XDG settings portal, followed live through its
+// SettingChanged signal. Desktops without the portal simply report no preference.
+class SystemAppearance : public QObject
+{
+    Q_OBJECT
+
+public:
+    // Values of org.freedesktop.appearance color-scheme
+    enum ColorScheme
+    {
+        NoPreference,
+        PreferDark,
+        PreferLight
+    };
+
+    explicit SystemAppearance(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_colorScheme = static_cast<ColorScheme>(read("color-scheme").toUInt());
+        m_accentColor = parseAccent(read("accent-color"));
+        QDBusConnection::sessionBus().connect(PortalService, PortalPath, SettingsInterface, "SettingChanged", this,
+                                              SLOT(onSettingChanged(QString, QString, QDBusVariant)));
+    }
+
+    ColorScheme colorScheme() const { return m_colorScheme; }
+    // Invalid when the desktop doesn't set one
+    QColor accentColor() const { return m_accentColor; }
+
+    // Fusion, the default Quick Controls style on Linux, draws everything from the palette
+    static QPalette darkPalette()
+    {
+        QPalette palette;
+        palette.setColor(QPalette::Window, QColor(0x2b, 0x2b, 0x2b));
+        palette.setColor(QPalette::WindowText, Qt::white);
+        palette.setColor(QPalette::Base, QColor(0x1e, 0x1e, 0x1e));
+        palette.setColor(QPalette::AlternateBase, QColor(0x35, 0x35, 0x35));
+        palette.setColor(QPalette::ToolTipBase, QColor(0x35, 0x35, 0x35));
+        palette.setColor(QPalette::ToolTipText, Qt::white);
+        palette.setColor(QPalette::Text, Qt::white);
+        palette.setColor(QPalette::PlaceholderText, QColor(0x9a, 0x9a, 0x9a));
+        palette.setColor(QPalette::Button, QColor(0x35, 0x35, 0x35));
+        palette.setColor(QPalette::ButtonText, Qt::white);
+        palette.setColor(QPalette::BrightText, Qt::red);
+        palette.setColor(QPalette::Mid, QColor(0x50, 0x50, 0x50));
+        palette.setColor(QPalette::Link, QColor(0x4d, 0xa3, 0xff));
+        palette.setColor(QPalette::Highlight, QColor(0x0a, 0x84, 0xff));
+        palette.setColor(QPalette::HighlightedText, Qt::white);
+        palette.setColor(QPalette::Disabled, QPalette::WindowText, QColor(0x80, 0x80, 0x80));
+        palette.setColor(QPalette::Disabled, QPalette::Text, QColor(0x80, 0x80, 0x80));
+        palette.setColor(QPalette::Disabled, QPalette::ButtonText, QColor(0x80, 0x80, 0x80));
+        return palette;
+    }
+
+signals:
+    void changed();
+
+private slots:
+    void onSettingChanged(const QString &group, const QString &key, const QDBusVariant &value)
+    {
+        if (group != AppearanceGroup)
+            return;
+        if (key == "color-scheme")
+            m_colorScheme = static_cast<ColorScheme>(value.variant().toUInt());
+        else if (key == "accent-color")
+            m_accentColor = parseAccent(value.variant());
+        else
+            return;
+        LOG_DEBUG("System appearance changed: " << key);
+        emit changed();
+    }
+
+private:
+    static constexpr const char *PortalService = "org.freedesktop.portal.Desktop";
+    static constexpr const char *PortalPath = "/org/freedesktop/portal/desktop";
+    static constexpr const char *SettingsInterface = "org.freedesktop.portal.Settings";
+    static constexpr const char *AppearanceGroup = "org.freedesktop.appearance";
+
+    // ReadOne where available, the deprecated Read wraps the value in a second variant
+    static QVariant read(const QString &key)
+    {
+        for (const char *method : {"ReadOne", "Read"})
+        {
+            QDBusMessage message = QDBusMessage::createMethodCall(PortalService, PortalPath, SettingsInterface, method);
+            message << QString(AppearanceGroup) << key;
+            QDBusMessage reply = QDBusConnection::sessionBus().call(message, QDBus::Block, 1000);
+            if (reply.type() != QDBusMessage::ReplyMessage || reply.arguments().isEmpty())
+                continue;
+
+            QVariant value = reply.arguments().first();
+            while (value.userType() == qMetaTypeId<QDBusVariant>())
+                value = value.value<QDBusVariant>().variant();
+            return value;
+        }
+        return QVariant();
+    }
+
+    // (ddd) in the 0-1 range, anything outside means no accent color is set
+    static QColor parseAccent(const QVariant &value)
+    {
+        if (value.userType() != qMetaTypeId<QDBusArgument>())
+            return QColor();
+
+        double red = -1, green = -1, blue = -1;
+        const QDBusArgument argument = value.value<QDBusArgument>();
+        argument.beginStructure();
+        argument >> red >> green >> blue;
+        argument.endStructure();
+        auto valid = [](double channel) { return channel >= 0 && channel <= 1; };
+        return valid(red) && valid(green) && valid(blue) ? QColor::fromRgbF(red, green, blue) : QColor();
+    }
+
+    ColorScheme m_colorScheme = NoPreference;
+    QColor m_accentColor;
+};
@@ -0,0 +1,118 @@
+#pragma once
+
+#include <QByteArray>
+#include <QCoreApplication>
+#include <QDir>
+#include <QFile>
+#include <QProcess>
+#include <QStandardPaths>
+#include <QTextStream>
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include <cstddef>
+#include <cstring>
+
+// Minimal sd_notify(3) implementation so we don't need to link libsystemd
+namespace Systemd
+{
+    inline bool notify(const QByteArray &state)
+    {
+        QByteArray socketPath = qgetenv("NOTIFY_SOCKET");
+        if (socketPath.isEmpty() || socketPath.size() >= static_cast<int>(sizeof(sockaddr_un::sun_path)))
+        {
+            return false;
+        }
+
+        sockaddr_un address = {};
+        address.sun_family = AF_UNIX;
+        memcpy(address.sun_path, socketPath.constData(), socketPath.size());
+        if (address.sun_path[0] == '@')
+        {
+            address.sun_path[0] = '\0'; // Abstract namespace socket
+        }
+
+        int fd = socket(AF_UNIX, SOCK_DGRAM | SOCK_CLOEXEC, 0);
+        if (fd < 0)
+        {
+            return false;
+        }
+
+        socklen_t length = offsetof(sockaddr_un, sun_path) + socketPath.size();
+        bool sent = sendto(fd, state.constData(), state.size(), MSG_NOSIGNAL,
+                           reinterpret_cast<sockaddr *>(&address), length) == state.size();
+        close(fd);
+        return sent;
+    }
+
+    // Returns the interval in ms at which WATCHDOG=1 has to be sent, or 0 if the watchdog is disabled
+    inline int watchdogInterval()
+    {
+        bool ok = false;
+        qint64 usec = qEnvironmentVariable("WATCHDOG_USEC").toLongLong(&ok);
+        if (!ok || usec <= 0)
+        {
+            return 0;
+        }
+
+        QString pid = qEnvironmentVariable("WATCHDOG_PID");
+        if (!pid.isEmpty() && pid.toLongLong() != QCoreApplication::applicationPid())
+        {
+            return 0;
+        }
+
+        // Ping at half the timeout as recommended by sd_watchdog_enabled(3)
+        return static_cast<int>(usec / 2000);
+    }
+
+    inline bool installUserService()
+    {
+        QTextStream out(stdout);
+        QTextStream err(stderr);
+
+        QString unitDir = QStandardPaths::writableLocation(QStandardPaths::GenericConfigLocation) + "/systemd/user";
+        QDir().mkpath(unitDir);
+
+        // Inside an AppImage applicationFilePath() points into the temporary mount
+        QString appPath = qEnvironmentVariable("APPIMAGE", QCoreApplication::applicationFilePath());
+
+        QFile unitFile(unitDir + "/librepods.service");
+        if (!unitFile.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text))
+        {
+            err << "Failed to write " << unitFile.fileName() << ": " << unitFile.errorString() << "\n";
+            return false;
+        }
+
+        unitFile.write(QStringLiteral(
+                           "[Unit]\n"
+                           "Description=LibrePods - AirPods liberated from Apple's ecosystem\n"
+                           "PartOf=graphical-session.target\n"
+                           "After=graphical-session.target\n"
+                           "\n"
+                           "[Service]\n"
+                           "Type=notify\n"
+                           "ExecStart=\"%1\" --hide\n"
+                           "Restart=on-failure\n"
+                           "WatchdogSec=30\n"
+                           "\n"
+                           "[Install]\n"
+                           "WantedBy=graphical-session.target\n")
+                           .arg(appPath)
+                           .toUtf8());
+        unitFile.close();
+        out << "Wrote " << unitFile.fileName() << "\n";
+
+        if (QProcess::execute("systemctl", QStringList() << "--user" << "daemon-reload") != 0 ||
+            QProcess::execute("systemctl", QStringList() << "--user" << "enable" << "librepods.service") != 0)
+        {
+            err << "Failed to enable librepods.service, is systemd --user running?\n";
+            return false;
+        }
+
+        out << "Enabled librepods.service, it will start with your next graphical session.\n"
+            << "Run 'systemctl --user start librepods' to start it now.\n";
+        return true;
+    }
+}
@@ -0,0 +1,88 @@
+#include <QtTest>
+#include <QFile>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+#include "eardetection.hpp"
+#include "packetnames.hpp"
+#include "framebuilder.h"
+
+Q_LOGGING_CATEGORY(librepods, "librepods")
+
+using namespace AirpodsTrayApp::Enums;
+
+// Timings for the inbound parsers that run for every packet. Under ctest each benchmark runs
+// once as a smoke test; run the binary directly (e.g. ./bench_parsers -iterations 100000) to
+// compare before and after an optimization.
+class BenchParsers : public QObject
+{
+    Q_OBJECT
+
+private slots:
+    void battery()
+    {
+        QByteArray frame = FrameBuilder::battery({{Battery::Component::Left, 42, Battery::BatteryStatus::Discharging},
+                                                  {Battery::Component::Right, 17, Battery::BatteryStatus::Discharging},
+                                                  {Battery::Component::Case, 80, Battery::BatteryStatus::Charging}});
+        Battery battery;
+        QBENCHMARK
+        {
+            battery.parsePacket(frame);
+        }
+        QCOMPARE(battery.getLeftPodLevel(), quint8(42));
+    }
+
+    void earDetection()
+    {
+        QByteArray inEar = FrameBuilder::earDetection(0x00, 0x00);
+        QByteArray outOfEar = FrameBuilder::earDetection(0x01, 0x00);
+        EarDetection earDetection;
+        QBENCHMARK
+        {
+            earDetection.parseData(inEar);
+            earDetection.parseData(outOfEar);
+        }
+        QVERIFY(earDetection.oneOrMorePodsInEar());
+    }
+
+    void noiseControl()
+    {
+        QByteArray frame = FrameBuilder::controlCommand(0x0D, 0x04);
+        std::optional<NoiseControlMode> mode;
+        QBENCHMARK
+        {
+            mode = AirPodsPackets::NoiseControl::parseMode(frame);
+        }
+        QCOMPARE(mode, std::optional<NoiseControlMode>(NoiseControlMode::Adaptive));
+    }
+
+    // Every packet goes through PacketNames::describe for the debug log and the developer tools
+    void describe_data()
+    {
+        QTest::addColumn<QByteArray>("frame");
+
+        QFile corpus(FRAMES_FILE);
+        QVERIFY2(corpus.open(QIODevice::ReadOnly | QIODevice::Text), qPrintable(corpus.errorString()));
+        while (!corpus.atEnd())
+        {
+            QByteArray line = corpus.readLine().trimmed();
+            if (line.isEmpty() || line.startsWith('#'))
+                continue;
+
+            QList<QByteArray> fields = line.split(' ');
+            QTest::newRow(fields.first().constData()) << QByteArray::fromHex(fields.last());
+        }
+    }
+
+    void describe()
+    {
+        QFETCH(QByteArray, frame);
+        QBENCHMARK
+        {
+            PacketNames::describe(frame);
+        }
+    }
+};
+
+QTEST_GUILESS_MAIN(BenchParsers)
+#include "bench_parsers.moc"
@@ -0,0 +1,73 @@
+#pragma once
+
+#include <QByteArray>
+#include <QString>
+#include <initializer_list>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+
+// Builds inbound AACP frames in the format the AirPods send them, so parsers
+// for new opcodes can be regression-tested without a captured trace
+namespace FrameBuilder
+{
+    struct BatteryEntry
+    {
+        Battery::Component component;
+        quint8 level;
+        Battery::BatteryStatus status;
+    };
+
+    inline QByteArray battery(std::initializer_list<BatteryEntry> entries)
+    {
+        QByteArray frame = AirPodsPackets::Parse::BATTERY_STATUS;
+        frame.append(static_cast<char>(entries.size()));
+        for (const BatteryEntry &entry : entries)
+        {
+            frame.append(static_cast<char>(entry.component));
+            frame.append(static_cast<char>(0x01)); // Spacer
+            frame.append(static_cast<char>(entry.level));
+            frame.append(static_cast<char>(entry.status));
+            frame.append(static_cast<char>(0x01)); // End
+        }
+        return frame;
+    }
+
+    inline QByteArray earDetection(quint8 primary, quint8 secondary)
+    {
+        QByteArray frame = AirPodsPackets::Parse::EAR_DETECTION;
+        frame.append(static_cast<char>(primary));
+        frame.append(static_cast<char>(secondary));
+        return frame;
+    }
+
+    inline QByteArray controlCommand(quint8 identifier, quint8 data1, quint8 data2 = 0x00)
+    {
+        return ControlCommand::createCommand(identifier, data1, data2);
+    }
+
+    inline QByteArray conversationalAwarenessData(quint8 level)
+    {
+        return AirPodsPackets::ConversationalAwareness::DATA_HEADER + static_cast<char>(level);
+    }
+
+    inline QByteArray magicCloudKeys(const QByteArray &irk, const QByteArray &encKey)
+    {
+        QByteArray frame = AirPodsPackets::MagicPairing::MAGIC_CLOUD_KEYS_HEADER;
+        frame.append(QByteArray::fromHex("01001000") + irk);
+        frame.append(QByteArray::fromHex("04001000") + encKey);
+        return frame;
+    }
+
+    inline QByteArray metadata(const QString &name, const QString &modelNumber, const QString &manufacturer)
+    {
+        QByteArray frame = AirPodsPackets::Parse::METADATA;
+        frame.append(QByteArray(6, '\0'));
+        for (const QString &field : {name, modelNumber, manufacturer})
+        {
+            frame.append(field.toUtf8());
+            frame.append('\0');
+        }
+        return frame;
+    }
+}
@@ -0,0 +1,546 @@
+#include <QtTest>
+#include <QFile>
+#include <QMetaEnum>
+#include <QTemporaryDir>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+#include "batteryhistory.hpp"
+#include "devicecapabilities.hpp"
+#include "devicestore.hpp"
+#include "drainmonitor.hpp"
+#include "eardetection.hpp"
+#include "headsetpower.hpp"
+#include "headtracking.hpp"
+#include "latencytest.hpp"
+#include "localsocketauth.hpp"
+#include "modemapping.hpp"
+#include "packetnames.hpp"
+#include "ratecounter.hpp"
+#include "syntheticdevices.hpp"
+#include "wearreminder.hpp"
+#include "framebuilder.h"
+
+Q_LOGGING_CATEGORY(librepods, "librepods")
+
+using namespace AirpodsTrayApp::Enums;
+
+namespace
+{
+    template <typename T>
+    QString enumName(T value)
+    {
+        return QString::fromLatin1(QMetaEnum::fromType<T>().valueToKey(static_cast<int>(value)));
+    }
+
+    QString enabledName(std::optional<bool> state)
+    {
+        if (!state)
+            return "invalid";
+        return state.value() ? "enabled" : "disabled";
+    }
+
+    // Human readable dump of everything the app extracts from a frame,
+    // dispatched through the same AirPodsPackets::classify as AirPodsTrayApp::parseData
+    QString describe(const QByteArray &frame)
+    {
+        using AirPodsPackets::Frame;
+        QStringList lines;
+
+        switch (AirPodsPackets::classify(frame))
+        {
+        case Frame::HandshakeAck:
+            lines << "handshakeAck";
+            break;
+        case Frame::FeaturesAck:
+            lines << "featuresAck";
+            break;
+        case Frame::HeadTracking:
+        {
+            auto sample = AirPodsPackets::HeadTracking::parse(frame).value();
+            lines << QString("headTracking.orientation: %1 %2 %3").arg(sample.o1).arg(sample.o2).arg(sample.o3);
+            lines << QString("headTracking.acceleration: %1 %2").arg(sample.horizontalAcceleration).arg(sample.verticalAcceleration);
+            break;
+        }
+        case Frame::ConnectedDevices:
+            for (const auto &host : AirPodsPackets::ConnectedDevices::parse(frame).value())
+            {
+                lines << QString("connectedDevice: %1 (0x%2 0x%3)").arg(host.address)
+                                                                   .arg(host.info1, 2, 16, QChar('0'))
+                                                                   .arg(host.info2, 2, 16, QChar('0'));
+            }
+            break;
+        case Frame::HostType:
+        {
+            auto hostType = AirPodsPackets::ConnectedDevices::parseDeviceType(frame).value();
+            lines << "hostType: " + hostType.first + " " + hostType.second;
+            break;
+        }
+        case Frame::MagicCloudKeys:
+        {
+            auto keys = AirPodsPackets::MagicPairing::parseMagicCloudKeysPacket(frame);
+            lines << "magicAccIRK: " + QString::fromLatin1(keys.magicAccIRK.toHex());
+            lines << "magicAccEncKey: " + QString::fromLatin1(keys.magicAccEncKey.toHex());
+            break;
+        }
+        case Frame::ConversationalAwareness:
+            lines << "conversationalAwareness: " + enabledName(AirPodsPackets::ConversationalAwareness::parseState(frame));
+            break;
+        case Frame::HearingAid:
+            lines << "hearingAid: " + enabledName(AirPodsPackets::HearingAid::parseState(frame));
+            break;
+        case Frame::NoiseControl:
+        {
+            auto mode = AirPodsPackets::NoiseControl::parseMode(frame);
+            lines << "noiseControl: " + (mode ? enumName(mode.value()) : QString("invalid"));
+            break;
+        }
+        case Frame::EarDetection:
+        {
+            EarDetection earDetection;
+            earDetection.parseData(frame);
+            lines << "earDetection.primary: " + enumName(earDetection.getprimaryStatus());
+            lines << "earDetection.secondary: " + enumName(earDetection.getsecondaryStatus());
+            break;
+        }
+        case Frame::Battery:
+        {
+            Battery battery;
+            if (!battery.parsePacket(frame))
+            {
+                return "battery: invalid\n";
+            }
+            for (auto component : {Battery::Component::Left, Battery::Component::Right,
+                                   Battery::Component::Case, Battery::Component::Headset})
+            {
+                lines << "battery." + enumName(component) + ": " + battery.getComponentStatus(component);
+            }
+            lines << "battery.primary: " + enumName(battery.getPrimaryPod());
+            if (battery.getPrimaryPod() != Battery::Component::Headset)
+            {
+                lines << "battery.secondary: " + enumName(battery.getSecondaryPod());
+            }
+            break;
+        }
+        case Frame::ConversationalAwarenessData:
+            lines << QString("conversationalAwarenessData: 0x%1").arg(static_cast<quint8>(frame.at(9)), 2, 16, QChar('0'));
+            break;
+        case Frame::Metadata:
+        {
+            auto metadata = AirPodsPackets::Metadata::parse(frame);
+            if (!metadata)
+            {
+                return "metadata: invalid\n";
+            }
+            lines << "metadata.name: " + metadata->name;
+            lines << "metadata.modelNumber: " + metadata->modelNumber;
+            lines << "metadata.manufacturer: " + metadata->manufacturer;
+            lines << "metadata.model: " + enumName(parseModelNumber(metadata->modelNumber));
+            if (!metadata->firmwareVersion.isEmpty())
+            {
+                lines << "metadata.serialNumber: " + metadata->serialNumber;
+                lines << "metadata.firmwareVersion: " + metadata->firmwareVersion;
+            }
+            break;
+        }
+        case Frame::AllowOffOption:
+            lines << "allowOffOption: " + enabledName(AirPodsPackets::AllowOffOption::parseState(frame));
+            break;
+        case Frame::CallManagement:
+        {
+            auto flipped = AirPodsPackets::CallManagement::parseFlipped(frame);
+            lines << "callControls: " + (flipped ? QString(flipped.value() ? "flipped" : "default") : QString("invalid"));
+            break;
+        }
+        case Frame::ChimeVolume:
+        {
+            auto volume = AirPodsPackets::ChimeVolume::parseVolume(frame);
+            lines << "chimeVolume: " + (volume ? QString::number(volume.value()) : QString("invalid"));
+            break;
+        }
+        case Frame::OneBudANCMode:
+            lines << "oneBudANCMode: " + enabledName(AirPodsPackets::OneBudANCMode::parseState(frame));
+            break;
+        case Frame::Unknown:
+            lines << "unrecognized";
+            break;
+        }
+
+        return lines.join('\n') + '\n';
+    }
+}
+
+class TestAacpParsing : public QObject
+{
+    Q_OBJECT
+
+private slots:
+    void snapshots_data()
+    {
+        QTest::addColumn<QByteArray>("frame");
+
+        QFile corpus(FRAMES_FILE);
+        QVERIFY2(corpus.open(QIODevice::ReadOnly | QIODevice::Text), qPrintable(corpus.errorString()));
+        while (!corpus.atEnd())
+        {
+            QByteArray line = corpus.readLine().trimmed();
+            if (line.isEmpty() || line.startsWith('#'))
+                continue;
+
+            QList<QByteArray> fields = line.split(' ');
+            QTest::newRow(fields.first().constData()) << QByteArray::fromHex(fields.last());
+        }
+    }
+
+    // Set UPDATE_SNAPSHOTS=1 to (re)generate the expected output after an intended change
+    void snapshots()
+    {
+        QFETCH(QByteArray, frame);
+
+        QString actual = describe(frame);
+        QFile snapshot(QString(SNAPSHOT_DIR) + "/" + QTest::currentDataTag() + ".txt");
+
+        if (qEnvironmentVariableIsSet("UPDATE_SNAPSHOTS"))
+        {
+            QVERIFY(snapshot.open(QIODevice::WriteOnly | QIODevice::Truncate | QIODevice::Text));
+            snapshot.write(actual.toUtf8());
+            return;
+        }
+
+        QVERIFY2(snapshot.open(QIODevice::ReadOnly | QIODevice::Text),
+                 qPrintable("Missing snapshot " + snapshot.fileName() + ", run with UPDATE_SNAPSHOTS=1"));
+        QCOMPARE(actual, QString::fromUtf8(snapshot.readAll()));
+    }
+
+    void batterySmoothing()
+    {
+        auto report = [](quint8 level, Battery::BatteryStatus status) {
+            return FrameBuilder::battery({{Battery::Component::Left, level, status}});
+        };
+
+        Battery battery;
+        QVERIFY(battery.parsePacket(report(60, Battery::BatteryStatus::Discharging)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(60));
+
+        // Changes show up right away, even against the discharge direction
+        QVERIFY(battery.parsePacket(report(65, Battery::BatteryStatus::Discharging)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(65));
+
+        // Jumping straight back is a flip-flop and has to be reported twice, the raw value is kept
+        QVERIFY(battery.parsePacket(report(60, Battery::BatteryStatus::Discharging)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(65));
+        QCOMPARE(battery.getRawState(Battery::Component::Left).level, quint8(60));
+        QVERIFY(battery.parsePacket(report(60, Battery::BatteryStatus::Discharging)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(60));
+        QVERIFY(battery.parsePacket(report(55, Battery::BatteryStatus::Discharging)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(55));
+
+        // Going on the charger applies at once, flipping straight back needs a second report
+        QVERIFY(battery.parsePacket(report(55, Battery::BatteryStatus::Charging)));
+        QVERIFY(battery.isLeftPodCharging());
+        QVERIFY(battery.parsePacket(report(55, Battery::BatteryStatus::Discharging)));
+        QVERIFY(battery.isLeftPodCharging());
+        QVERIFY(battery.parsePacket(report(55, Battery::BatteryStatus::Charging)));
+        QVERIFY(battery.parsePacket(report(60, Battery::BatteryStatus::Charging)));
+        QVERIFY(battery.isLeftPodCharging());
+        QCOMPARE(battery.getLeftPodLevel(), quint8(60));
+
+        // A disconnected bud keeps its last level, the raw state still follows
+        QVERIFY(battery.parsePacket(report(60, Battery::BatteryStatus::Disconnected)));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(60));
+        QCOMPARE(battery.getRawState(Battery::Component::Left).status, Battery::BatteryStatus::Disconnected);
+    }
+
+    void headTrackingCalibration()
+    {
+        HeadTracking tracking;
+        tracking.setActive(true);
+        for (int i = 0; i < 10; ++i)
+        {
+            tracking.process({19000, 1000, 1000, 0, 0});
+        }
+        QVERIFY(tracking.calibrated());
+        QCOMPARE(tracking.pitch(), 0.0);
+
+        // Both components moving the same way is pitch, opposite ways is yaw
+        tracking.process({19000, 4200, 4200, 0, 0});
+        QCOMPARE(tracking.pitch(), 18.0);
+        QCOMPARE(tracking.yaw(), 0.0);
+        tracking.process({19000, 4200, -2200, 0, 0});
+        QCOMPARE(tracking.pitch(), 0.0);
+        QCOMPARE(tracking.yaw(), 18.0);
+    }
+
+    void drainDivergence()
+    {
+        const qint64 minute = 60 * 1000;
+        DrainMonitor monitor;
+        QVERIFY(!monitor.addSample(0, 90, 90));
+        // Uneven, but not for long enough yet
+        QVERIFY(!monitor.addSample(20 * minute, 70, 85));
+
+        auto divergence = monitor.addSample(30 * minute, 66, 84);
+        QVERIFY(divergence);
+        QCOMPARE(divergence->faster, Battery::Component::Left);
+        QCOMPARE(divergence->ratio, 4.0);
+        QVERIFY(!monitor.addSample(40 * minute, 60, 82)); // Only reported once
+
+        // A charged bud restarts the measurement
+        monitor.reset();
+        QVERIFY(!monitor.addSample(0, 50, 50));
+        QVERIFY(!monitor.addSample(10 * minute, 80, 49));
+        QVERIFY(!monitor.addSample(35 * minute, 60, 40)); // Measured from 80/49 only 25 minutes ago
+        QVERIFY(monitor.addSample(45 * minute, 55, 39));
+    }
+
+    void localSocketAuth()
+    {
+        QCOMPARE(LocalSocketAuth::parseGroups({"1000", " 27 ", "wheel", ""}), QList<uint>({1000, 27}));
+
+        int fds[2];
+        QCOMPARE(socketpair(AF_UNIX, SOCK_STREAM, 0, fds), 0);
+        QVERIFY(LocalSocketAuth::isPeerAllowed(fds[0], {}));
+        close(fds[0]);
+        close(fds[1]);
+        QVERIFY(!LocalSocketAuth::isPeerAllowed(-1, {}));
+    }
+
+    void packetNames()
+    {
+        QCOMPARE(PacketNames::describe(FrameBuilder::controlCommand(0x0D, 0x04)), QString("ListeningMode=Adaptive"));
+        QCOMPARE(PacketNames::describe(AirPodsPackets::ConversationalAwareness::DISABLED), QString("ConversationalAwareness=Off"));
+        QCOMPARE(PacketNames::describe(AirPodsPackets::ChimeVolume::getPacket(40)), QString("ChimeVolume=40"));
+        QCOMPARE(PacketNames::describe(FrameBuilder::controlCommand(0x7F, 0x01)), QString("0x7f=On"));
+        QCOMPARE(PacketNames::describe(FrameBuilder::battery({{Battery::Component::Left, 42, Battery::BatteryStatus::Charging}})),
+                 QString("Battery Left=42% Charging"));
+        QCOMPARE(PacketNames::describe(AirPodsPackets::Connection::HANDSHAKE), QString("Handshake"));
+        QVERIFY(PacketNames::describe(QByteArray::fromHex("deadbeef")).isEmpty());
+    }
+
+    void wearReminderQuietHours()
+    {
+        QVERIFY(WearReminder::isQuiet(QTime(23, 0), QTime(22, 0), QTime(7, 0)));
+        QVERIFY(WearReminder::isQuiet(QTime(6, 59), QTime(22, 0), QTime(7, 0)));
+        QVERIFY(!WearReminder::isQuiet(QTime(7, 0), QTime(22, 0), QTime(7, 0)));
+        QVERIFY(WearReminder::isQuiet(QTime(13, 30), QTime(13, 0), QTime(14, 0)));
+        QVERIFY(!WearReminder::isQuiet(QTime(12, 0), QTime(13, 0), QTime(14, 0)));
+        QVERIFY(!WearReminder::isQuiet(QTime(23, 0), QTime(), QTime(7, 0)));
+    }
+
+    void modeMappingOverrides()
+    {
+        ModeMapping defaults;
+        QCOMPARE(defaults.packetFor(NoiseControlMode::Adaptive), AirPodsPackets::NoiseControl::ADAPTIVE);
+        QCOMPARE(defaults.parse(AirPodsPackets::NoiseControl::OFF), std::optional<NoiseControlMode>(NoiseControlMode::Off));
+
+        QJsonArray entries = QJsonDocument::fromJson(R"([
+            {"model": "A2084", "modes": {"off": 1}},
+            {"firmware": "7A", "modes": {"transparency": 5, "adaptive": 3}}
+        ])").array();
+        QVERIFY(!ModeMapping::select(entries, "A2931", "6F21").isOverridden());
+
+        ModeMapping mapping = ModeMapping::select(entries, "A2931", "7A294");
+        QVERIFY(mapping.isOverridden());
+        QCOMPARE(mapping.valueFor(NoiseControlMode::NoiseCancellation), quint8(0x02));
+        QCOMPARE(mapping.packetFor(NoiseControlMode::Transparency), ControlCommand::createCommand(0x0D, 0x05));
+        QCOMPARE(mapping.parse(ControlCommand::createCommand(0x0D, 0x03)), std::optional<NoiseControlMode>(NoiseControlMode::Adaptive));
+        QVERIFY(!mapping.parse(ControlCommand::createCommand(0x0D, 0x04)));
+    }
+
+    void rateCounterWindow()
+    {
+        RateCounter counter;
+        counter.add(1000);
+        counter.add(1500);
+        counter.add(30000);
+        QCOMPARE(counter.perMinute(30000), 3);
+        QCOMPARE(counter.perMinute(61000), 1);
+        QCOMPARE(counter.perMinute(89000), 1);
+        QCOMPARE(counter.perMinute(91000), 0);
+        counter.add(500000);
+        QCOMPARE(counter.perMinute(500000), 1);
+    }
+
+    void syntheticDeviceScripts()
+    {
+        SyntheticDevices::Device draining;
+        draining.behavior = SyntheticDevices::Draining;
+        draining.left = 1;
+        draining.right = 1;
+        SyntheticDevices::step(draining);
+        QCOMPARE(draining.left, 0);
+        QCOMPARE(draining.right, 100);
+
+        SyntheticDevices::Device charging;
+        charging.behavior = SyntheticDevices::Charging;
+        charging.left = 100;
+        charging.caseLevel = 0;
+        SyntheticDevices::step(charging);
+        QCOMPARE(charging.left, 10);
+        QCOMPARE(charging.caseLevel, 0);
+
+        SyntheticDevices::Device cycling;
+        cycling.behavior = SyntheticDevices::CyclingModes;
+        cycling.mode = 3;
+        SyntheticDevices::step(cycling);
+        QCOMPARE(cycling.mode, 0);
+    }
+
+    void latencyEstimate()
+    {
+        // Taps 160 or 200 ms after the clicks, one beat is missed and one tap is off
+        QList<qint64> taps;
+        for (int click = 0; click < LatencyTest::Clicks; ++click)
+        {
+            if (click != 5)
+                taps.append(LatencyTest::LeadInMs + click * LatencyTest::IntervalMs + 180 + (click % 2 ? 20 : -20));
+        }
+        taps.insert(3, LatencyTest::LeadInMs + 3 * LatencyTest::IntervalMs - 250);
+        QCOMPARE(LatencyTest::estimate(taps).value_or(-1), 200);
+
+        // Too few taps after the ones spent finding the beat
+        QVERIFY(!LatencyTest::estimate(taps.mid(0, 5)));
+    }
+
+    void batteryHistoryLevels()
+    {
+        Battery battery;
+        QVERIFY(battery.parsePacket(FrameBuilder::battery({{Battery::Component::Left, 42, Battery::BatteryStatus::Discharging},
+                                                           {Battery::Component::Right, 17, Battery::BatteryStatus::Discharging},
+                                                           {Battery::Component::Case, 80, Battery::BatteryStatus::Charging}})));
+        BatteryHistory::Levels levels = BatteryHistory::levels(battery, false);
+        QCOMPARE(levels.left, 42);
+        QCOMPARE(levels.right, 17);
+        QCOMPARE(levels.caseLevel, 80);
+
+        // An ignored case is left out of the history like an unreported one
+        levels = BatteryHistory::levels(battery, true);
+        QCOMPARE(levels.left, 42);
+        QCOMPARE(levels.caseLevel, -1);
+        QVERIFY(!levels.isEmpty());
+    }
+
+    void deviceCapabilities()
+    {
+        DeviceCapabilities capabilities;
+        QVERIFY(capabilities.add(DeviceCapabilities::forFrame(AirPodsPackets::Frame::ChimeVolume)));
+        QVERIFY(!capabilities.add(DeviceCapabilities::forFrame(AirPodsPackets::Frame::ChimeVolume)));
+        QVERIFY(!capabilities.add(DeviceCapabilities::forFrame(AirPodsPackets::Frame::Battery)));
+        QVERIFY(capabilities.add(DeviceCapabilities::CallControls));
+        QVERIFY(capabilities.setFeaturesAck(AirPodsPackets::Parse::FEATURES_ACK + QByteArray::fromHex("0102")));
+        QVERIFY(capabilities.has(DeviceCapabilities::ChimeVolume));
+        QVERIFY(!capabilities.has(DeviceCapabilities::HearingAid));
+
+        QStringList stored = capabilities.toStringList();
+        QCOMPARE(stored, QStringList({"CallControls", "ChimeVolume", "features:0102"}));
+        QCOMPARE(DeviceCapabilities::fromStringList(stored), capabilities);
+        // Names written by a newer version are ignored
+        QCOMPARE(DeviceCapabilities::fromStringList(stored << "SpatialAudio"), capabilities);
+    }
+
+    void deviceStoreRoundTrip()
+    {
+        QTemporaryDir dir;
+        QVERIFY(dir.isValid());
+        const QString address = "AA:BB:CC:DD:EE:FF";
+        QSettings settings(dir.filePath("source.conf"), QSettings::IniFormat);
+        settings.setValue(DeviceStore::key("ignoreCase", address), true);
+        settings.setValue(DeviceStore::key("volume", address), 60);
+        settings.setValue(DeviceStore::key("capabilities", address), QStringList({"CallControls", "ChimeVolume"}));
+        settings.setValue("autoConnect/priority", QStringList({address}));
+        QCOMPARE(DeviceStore::addresses(settings), QStringList({address}));
+
+        QJsonObject exported = DeviceStore::settingsFor(settings, address);
+        QCOMPARE(exported.keys(), QStringList({"capabilities", "ignoreCase", "volume"}));
+
+        QSettings target(dir.filePath("target.conf"), QSettings::IniFormat);
+        DeviceStore::applySettings(target, address, exported);
+        QCOMPARE(target.value(DeviceStore::key("ignoreCase", address)).toBool(), true);
+        QCOMPARE(target.value(DeviceStore::key("volume", address)).toInt(), 60);
+        QCOMPARE(target.value(DeviceStore::key("capabilities", address)).toStringList(), QStringList({"CallControls", "ChimeVolume"}));
+        QVERIFY(!target.contains(DeviceStore::key("lostMode", address)));
+
+        QVERIFY(!DeviceStore::isAddress("AA:BB:CC:DD:EE"));
+        QVERIFY(!DeviceStore::isAddress(""));
+    }
+
+    void deviceStoreImport()
+    {
+        QTemporaryDir dir;
+        QVERIFY(dir.isValid());
+        const QString current = "AA:BB:CC:DD:EE:FF";
+        const QString paired = "11:22:33:44:55:66";
+        QSettings settings(dir.filePath("source.conf"), QSettings::IniFormat);
+        settings.setValue(DeviceStore::key("volume", current), 60);
+        settings.setValue(DeviceStore::key("autoConnect", paired), true);
+        settings.setValue("autoConnect/priority", QStringList({paired, current}));
+
+        QJsonObject root = DeviceStore::exportJson(settings, {{paired, "Work AirPods"}}, current,
+                                                   QJsonObject{{"deviceName", "AirPods Pro"}, {"model", 4}});
+        QJsonArray devices = root.value("devices").toArray();
+        QCOMPARE(devices.size(), 2);
+        QCOMPARE(devices.at(0).toObject().value("deviceName").toString(), QString("AirPods Pro"));
+        QCOMPARE(devices.at(1).toObject().value("deviceName").toString(), QString("Work AirPods"));
+        QVERIFY(!devices.at(0).toObject().contains("serialNumber"));
+
+        devices.append(QJsonObject{{"deviceName", "Broken"}, {"address", "not an address"}});
+        devices.append(QJsonObject{});
+        root["devices"] = devices;
+
+        // A new install has no current device, the entry with a model takes its place
+        QSettings target(dir.filePath("target.conf"), QSettings::IniFormat);
+        DeviceStore::ImportResult result = DeviceStore::importJson(target, root, QString());
+        QCOMPARE(result.imported, 2);
+        QCOMPARE(result.skipped, QStringList({"Broken", "entry 4"}));
+        QCOMPARE(result.infoAddress, current);
+        QCOMPARE(target.value(DeviceStore::key("volume", current)).toInt(), 60);
+        QCOMPARE(target.value(DeviceStore::key("autoConnect", paired)).toBool(), true);
+        QCOMPARE(target.value("autoConnect/priority").toStringList(), QStringList({paired, current}));
+    }
+
+    void headsetPowerState()
+    {
+        QCOMPARE(HeadsetPower::stateFor(false, true, true, false), HeadsetPower::Unknown);
+        QCOMPARE(HeadsetPower::stateFor(true, false, false, true), HeadsetPower::Unknown);
+        QCOMPARE(HeadsetPower::stateFor(true, true, true, true), HeadsetPower::Awake);
+        QCOMPARE(HeadsetPower::stateFor(true, true, false, false), HeadsetPower::OffHead);
+        QCOMPARE(HeadsetPower::stateFor(true, true, false, true), HeadsetPower::LowPower);
+
+        HeadsetPower power;
+        power.update(true, true, false);
+        QCOMPARE(power.state(), HeadsetPower::OffHead);
+        power.update(true, true, true);
+        QCOMPARE(power.state(), HeadsetPower::Awake);
+        QVERIFY(power.label().size() > 0);
+    }
+
+    void frameBuilder()
+    {
+        Battery battery;
+        QVERIFY(battery.parsePacket(FrameBuilder::battery({{Battery::Component::Left, 42, Battery::BatteryStatus::Charging},
+                                                           {Battery::Component::Right, 17, Battery::BatteryStatus::Discharging}})));
+        QCOMPARE(battery.getLeftPodLevel(), quint8(42));
+        QVERIFY(battery.isLeftPodCharging());
+        QCOMPARE(battery.getRightPodLevel(), quint8(17));
+        QCOMPARE(battery.getPrimaryPod(), Battery::Component::Left);
+
+        EarDetection earDetection;
+        QVERIFY(earDetection.parseData(FrameBuilder::earDetection(0x00, 0x02)));
+        QVERIFY(earDetection.isPrimaryInEar());
+        QCOMPARE(earDetection.getsecondaryStatus(), EarDetection::EarDetectionStatus::InCase);
+
+        auto mode = AirPodsPackets::NoiseControl::parseMode(FrameBuilder::controlCommand(0x0D, 0x03));
+        QVERIFY(mode);
+        QCOMPARE(mode.value(), NoiseControlMode::Transparency);
+
+        QCOMPARE(describe(FrameBuilder::conversationalAwarenessData(0x02)), QString("conversationalAwarenessData: 0x02\n"));
+
+        auto metadata = AirPodsPackets::Metadata::parse(FrameBuilder::metadata("My AirPods", "A2084", "Apple Inc."));
+        QVERIFY(metadata);
+        QCOMPARE(metadata->name, QString("My AirPods"));
+        QCOMPARE(parseModelNumber(metadata->modelNumber), AirPodsModel::AirPodsPro);
+    }
+};
+
+QTEST_GUILESS_MAIN(TestAacpParsing)
+#include "tst_aacpparsing.moc"
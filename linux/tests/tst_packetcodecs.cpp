@@ -0,0 +1,155 @@
+#include <QtTest>
+#include <QRandomGenerator>
+
+#include "airpods_packets.h"
+#include "battery.hpp"
+#include "framebuilder.h"
+
+Q_LOGGING_CATEGORY(librepods, "librepods")
+
+using namespace AirpodsTrayApp::Enums;
+
+namespace
+{
+    // Fixed seed so failures are reproducible, override with PACKET_TEST_SEED
+    QRandomGenerator generator()
+    {
+        bool ok = false;
+        quint32 seed = qEnvironmentVariable("PACKET_TEST_SEED").toUInt(&ok);
+        return QRandomGenerator(ok ? seed : 0x4c50);
+    }
+
+    constexpr int Iterations = 500;
+
+    template <typename Command>
+    void verifyToggleRoundTrip()
+    {
+        QCOMPARE(Command::parseState(Command::ENABLED), std::optional<bool>(true));
+        QCOMPARE(Command::parseState(Command::DISABLED), std::optional<bool>(false));
+        QVERIFY(Command::ENABLED.startsWith(Command::HEADER));
+        QVERIFY(Command::DISABLED.startsWith(Command::HEADER));
+    }
+}
+
+// Encode -> decode round trips for the packets we send, and for the frame
+// builder against the inbound parsers
+class TestPacketCodecs : public QObject
+{
+    Q_OBJECT
+
+private slots:
+    void noiseControlRoundTrip()
+    {
+        for (int value = static_cast<int>(NoiseControlMode::MinValue); value <= static_cast<int>(NoiseControlMode::MaxValue); ++value)
+        {
+            auto mode = static_cast<NoiseControlMode>(value);
+            auto parsed = AirPodsPackets::NoiseControl::parseMode(AirPodsPackets::NoiseControl::getPacketForMode(mode));
+            QVERIFY(parsed);
+            QCOMPARE(parsed.value(), mode);
+        }
+    }
+
+    void toggleCommandsRoundTrip()
+    {
+        verifyToggleRoundTrip<AirPodsPackets::OneBudANCMode::Type>();
+        verifyToggleRoundTrip<AirPodsPackets::VolumeSwipe::Type>();
+        verifyToggleRoundTrip<AirPodsPackets::AdaptiveVolume::Type>();
+        verifyToggleRoundTrip<AirPodsPackets::ConversationalAwareness::Type>();
+        verifyToggleRoundTrip<AirPodsPackets::HearingAssist::Type>();
+        verifyToggleRoundTrip<AirPodsPackets::AllowOffOption::Type>();
+
+        QCOMPARE(AirPodsPackets::HearingAid::parseState(AirPodsPackets::HearingAid::ENABLED), std::optional<bool>(true));
+        QCOMPARE(AirPodsPackets::HearingAid::parseState(AirPodsPackets::HearingAid::DISABLED), std::optional<bool>(false));
+    }
+
+    void adaptiveNoiseLevelRoundTrip()
+    {
+        for (int level = 0; level <= 100; ++level)
+        {
+            QByteArray packet = AirPodsPackets::AdaptiveNoise::getPacket(level);
+            QVERIFY(packet.startsWith(AirPodsPackets::AdaptiveNoise::HEADER));
+            QCOMPARE(static_cast<quint8>(ControlCommand::parseActive(packet).value()), static_cast<quint8>(level));
+        }
+    }
+
+    void chimeVolumeRoundTrip()
+    {
+        for (int volume = 0; volume <= 100; ++volume)
+        {
+            QCOMPARE(AirPodsPackets::ChimeVolume::parseVolume(AirPodsPackets::ChimeVolume::getPacket(volume)), std::optional<int>(volume));
+        }
+        QCOMPARE(AirPodsPackets::ChimeVolume::parseVolume(AirPodsPackets::ChimeVolume::getPacket(101)), std::nullopt);
+    }
+
+    void callManagementRoundTrip()
+    {
+        QCOMPARE(AirPodsPackets::CallManagement::parseFlipped(AirPodsPackets::CallManagement::FLIPPED), std::optional<bool>(true));
+        QCOMPARE(AirPodsPackets::CallManagement::parseFlipped(AirPodsPackets::CallManagement::DEFAULT), std::optional<bool>(false));
+        QCOMPARE(AirPodsPackets::CallManagement::parseFlipped(AirPodsPackets::CallManagement::HEADER), std::nullopt);
+    }
+
+    void renameRoundTrip()
+    {
+        QRandomGenerator random = generator();
+        for (int i = 0; i < Iterations; ++i)
+        {
+            QString name;
+            int length = random.bounded(1, 33);
+            for (int c = 0; c < length; ++c)
+            {
+                // Mix of ASCII and a few multi-byte characters
+                name += random.bounded(4) == 0 ? QChar(0x00C0 + random.bounded(0x40)) : QChar(0x20 + random.bounded(0x5F));
+            }
+
+            QByteArray packet = AirPodsPackets::Rename::getPacket(name);
+            quint8 size = static_cast<quint8>(packet.at(7));
+            QCOMPARE(static_cast<int>(size), name.toUtf8().size());
+            QCOMPARE(QString::fromUtf8(packet.mid(9, size)), name);
+        }
+    }
+
+    void magicCloudKeysRoundTrip()
+    {
+        QRandomGenerator random = generator();
+        for (int i = 0; i < Iterations; ++i)
+        {
+            QByteArray irk(16, Qt::Uninitialized);
+            QByteArray encKey(16, Qt::Uninitialized);
+            random.fillRange(reinterpret_cast<quint32 *>(irk.data()), 4);
+            random.fillRange(reinterpret_cast<quint32 *>(encKey.data()), 4);
+
+            auto keys = AirPodsPackets::MagicPairing::parseMagicCloudKeysPacket(FrameBuilder::magicCloudKeys(irk, encKey));
+            QCOMPARE(keys.magicAccIRK, irk);
+            QCOMPARE(keys.magicAccEncKey, encKey);
+        }
+    }
+
+    void batteryRoundTrip()
+    {
+        QRandomGenerator random = generator();
+        for (int i = 0; i < Iterations; ++i)
+        {
+            auto randomEntry = [&random](Battery::Component component) {
+                return FrameBuilder::BatteryEntry{component, static_cast<quint8>(random.bounded(1, 101)),
+                                                  random.bounded(2) ? Battery::BatteryStatus::Charging
+                                                                    : Battery::BatteryStatus::Discharging};
+            };
+            auto left = randomEntry(Battery::Component::Left);
+            auto right = randomEntry(Battery::Component::Right);
+            auto caseEntry = randomEntry(Battery::Component::Case);
+
+            Battery battery;
+            QVERIFY(battery.parsePacket(FrameBuilder::battery({left, right, caseEntry})));
+            for (const auto &entry : {left, right, caseEntry})
+            {
+                QCOMPARE(battery.getState(entry.component).level, entry.level);
+                QCOMPARE(battery.getState(entry.component).status, entry.status);
+            }
+            QCOMPARE(battery.getPrimaryPod(), Battery::Component::Left);
+            QCOMPARE(battery.getSecondaryPod(), Battery::Component::Right);
+        }
+    }
+};
+
+QTEST_GUILESS_MAIN(TestPacketCodecs)
+#include "tst_packetcodecs.moc"
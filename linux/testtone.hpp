@@ -0,0 +1,116 @@
+#pragma once
+
+#include <QDataStream>
+#include <QFile>
+#include <QProcess>
+#include <QStandardPaths>
+#include <QtMath>
+
+#include <climits>
+
+#include "logger.h"
+#include "storage.hpp"
+
+// Short stereo test tones for checking channel placement, played through
+// pw-play or paplay so they go to the default sink like everything else
+namespace TestTone
+{
+    constexpr int SampleRate = 44100;
+
+    inline void writeHeader(QDataStream &out, quint32 frames)
+    {
+        const quint32 dataSize = frames * 2 * sizeof(qint16);
+        out.writeRawData("RIFF", 4);
+        out << quint32(36 + dataSize);
+        out.writeRawData("WAVEfmt ", 8);
+        out << quint32(16) << quint16(1) << quint16(2) << quint32(SampleRate)
+            << quint32(SampleRate * 2 * sizeof(qint16)) << quint16(2 * sizeof(qint16)) << quint16(16);
+        out.writeRawData("data", 4);
+        out << dataSize;
+    }
+
+    // pan: -1 is fully left, 0 centre and 1 fully right
+    inline QString writeWave(double pan, double frequency = 440.0, int durationMs = 600)
+    {
+        const int frames = SampleRate * durationMs / 1000;
+
+        QString path = QString("%1/test-tone-%2-%3-%4.wav").arg(Storage::cacheDir()).arg(qRound(pan * 100)).arg(qRound(frequency)).arg(durationMs);
+        QFile file(path);
+        if (file.exists())
+            return path;
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate))
+        {
+            LOG_ERROR("Failed to write test tone " << path << ": " << file.errorString());
+            return QString();
+        }
+
+        QDataStream out(&file);
+        out.setByteOrder(QDataStream::LittleEndian);
+        writeHeader(out, frames);
+
+        // Constant power panning, with a short fade to avoid clicks
+        const double angle = (pan + 1) * M_PI / 4;
+        const double leftGain = qCos(angle);
+        const double rightGain = qSin(angle);
+        const int fade = SampleRate / 100;
+        for (int i = 0; i < frames; ++i)
+        {
+            double envelope = qMin(1.0, qMin(i, frames - i) / double(fade));
+            double sample = qSin(2 * M_PI * frequency * i / SampleRate) * envelope * 0.5 * SHRT_MAX;
+            out << qint16(sample * leftGain) << qint16(sample * rightGain);
+        }
+        return path;
+    }
+
+    // Silence for leadInMs, then a 10 ms click every intervalMs, for the latency test
+    inline QString writeClickTrack(int clicks, int intervalMs, int leadInMs)
+    {
+        QString path = QString("%1/click-track-%2-%3-%4.wav").arg(Storage::cacheDir()).arg(clicks).arg(intervalMs).arg(leadInMs);
+        QFile file(path);
+        if (file.exists())
+            return path;
+        if (!file.open(QIODevice::WriteOnly | QIODevice::Truncate))
+        {
+            LOG_ERROR("Failed to write click track " << path << ": " << file.errorString());
+            return QString();
+        }
+
+        QDataStream out(&file);
+        out.setByteOrder(QDataStream::LittleEndian);
+        const int frames = SampleRate * (leadInMs + clicks * intervalMs) / 1000;
+        const int clickFrames = SampleRate / 100;
+        writeHeader(out, frames);
+        for (int i = 0; i < frames; ++i)
+        {
+            int sinceClick = (i - SampleRate * leadInMs / 1000) % (SampleRate * intervalMs / 1000);
+            bool inClick = i >= SampleRate * leadInMs / 1000 && sinceClick < clickFrames;
+            qint16 sample = inClick ? qint16(qSin(2 * M_PI * 1000.0 * sinceClick / SampleRate) * 0.6 * SHRT_MAX) : 0;
+            out << sample << sample;
+        }
+        return path;
+    }
+
+    inline bool playFile(const QString &path)
+    {
+        if (path.isEmpty())
+            return false;
+
+        for (const QString &player : {QStringLiteral("pw-play"), QStringLiteral("paplay")})
+        {
+            if (!QStandardPaths::findExecutable(player).isEmpty())
+                return QProcess::startDetached(player, {path});
+        }
+        LOG_WARN("Neither pw-play nor paplay is installed, cannot play test tone");
+        return false;
+    }
+
+    inline bool play(double pan) { return playFile(writeWave(pan)); }
+
+    // A loose ear tip lets the low end leak out, so the fit check plays a longer bass tone
+    inline bool playFitCheck(double pan) { return playFile(writeWave(pan, 100.0, 3000)); }
+
+    inline bool playClickTrack(int clicks, int intervalMs, int leadInMs)
+    {
+        return playFile(writeClickTrack(clicks, intervalMs, leadInMs));
+    }
+}
@@ -1,4 +1,5 @@
 #include "trayiconmanager.h"
+#include "logger.h"
 
 #include <QSystemTrayIcon>
 #include <QMenu>
@@ -6,8 +7,19 @@
 #include <QApplication>
 #include <QPainter>
 #include <QFont>
+#include <QFontDatabase>
 #include <QColor>
 #include <QActionGroup>
+#include <QTimer>
+#include <QRegularExpression>
+#include <QMap>
+#include <QDBusConnection>
+#include <QDBusMessage>
+#include <QDBusPendingCallWatcher>
+#include <QDBusPendingReply>
+#include <QTime>
+
+#include <algorithm>
 
 using namespace AirpodsTrayApp::Enums;
 
@@ -17,14 +29,40 @@ TrayIconManager::TrayIconManager(QObject *parent) : QObject(parent)
     trayIcon = new QSystemTrayIcon(QIcon(":/icons/assets/airpods.png"), this);
     trayMenu = new QMenu();
 
+    setIconFontFamily(QString());
+
     // Setup basic menu actions
     setupMenuActions();
 
+    caseRotationTimer = new QTimer(this);
+    caseRotationTimer->setInterval(3000);
+    connect(caseRotationTimer, &QTimer::timeout, this, [this]()
+            {
+                m_showingCase = !m_showingCase;
+                if (!currentBatteryStatus.isEmpty())
+                    updateIconFromBattery(currentBatteryStatus); });
+
+    singleClickTimer = new QTimer(this);
+    singleClickTimer->setSingleShot(true);
+    singleClickTimer->setInterval(QApplication::doubleClickInterval());
+    connect(singleClickTimer, &QTimer::timeout, this, &TrayIconManager::trayClicked);
+
     // Connect signals
     trayIcon->setContextMenu(trayMenu);
     connect(trayIcon, &QSystemTrayIcon::activated, this, &TrayIconManager::onTrayIconActivated);
 
     trayIcon->show();
+
+    if (!QSystemTrayIcon::isSystemTrayAvailable())
+    {
+        LOG_INFO("No system tray available, using a notification for the controls");
+        m_notificationFallback = true;
+        QDBusConnection bus = QDBusConnection::sessionBus();
+        bus.connect("org.freedesktop.Notifications", "/org/freedesktop/Notifications", "org.freedesktop.Notifications",
+                    "ActionInvoked", this, SLOT(onNotificationActionInvoked(uint, QString)));
+        bus.connect("org.freedesktop.Notifications", "/org/freedesktop/Notifications", "org.freedesktop.Notifications",
+                    "NotificationClosed", this, SLOT(onNotificationClosed(uint, uint)));
+    }
 }
 
 void TrayIconManager::showNotification(const QString &title, const QString &message)
@@ -34,10 +72,127 @@ void TrayIconManager::showNotification(const QString &title, const QString &mess
     trayIcon->showMessage(title, message, QSystemTrayIcon::Information, 3000);
 }
 
+void TrayIconManager::showPersistentAlert(const QString &title, const QString &message, bool playSound)
+{
+    // QSystemTrayIcon can't set urgency or sound hints, so talk to the notification daemon directly
+    QVariantMap hints{{"urgency", QVariant::fromValue<uchar>(2)}};
+    if (playSound)
+        hints.insert("sound-name", "dialog-warning");
+
+    QDBusMessage notify = QDBusMessage::createMethodCall("org.freedesktop.Notifications", "/org/freedesktop/Notifications",
+                                                         "org.freedesktop.Notifications", "Notify");
+    notify << QApplication::applicationDisplayName() << 0u << "dialog-warning" << title << message
+           << QStringList() << hints << 0; // 0 = never expire
+    auto *watcher = new QDBusPendingCallWatcher(QDBusConnection::sessionBus().asyncCall(notify), this);
+    connect(watcher, &QDBusPendingCallWatcher::finished, this, [this, title, message, playSound](QDBusPendingCallWatcher *call)
+            {
+                call->deleteLater();
+                QDBusPendingReply<uint> reply = *call;
+                if (!reply.isError())
+                    return;
+                LOG_WARN("Notification daemon unavailable, falling back to tray message: " << reply.error().message());
+                trayIcon->showMessage(title, message, QSystemTrayIcon::Warning, 0);
+                if (playSound)
+                    QApplication::beep(); });
+}
+
+void TrayIconManager::setWarningBadge(bool visible)
+{
+    if (m_warningBadge == visible)
+        return;
+    m_warningBadge = visible;
+    if (!currentBatteryStatus.isEmpty())
+        updateIconFromBattery(currentBatteryStatus);
+}
+
+void TrayIconManager::setReadOnly(bool readOnly)
+{
+    m_readOnly = readOnly;
+    caToggleAction->setEnabled(!readOnly);
+    allowOffAction->setEnabled(!readOnly);
+    for (QAction *action : noiseControlGroup->actions())
+        action->setEnabled(!readOnly);
+    recentMenu->setEnabled(!readOnly && !recentMenu->actions().isEmpty());
+}
+
+void TrayIconManager::setShowCaseBattery(bool enabled)
+{
+    if (m_showCaseBattery == enabled)
+        return;
+
+    m_showCaseBattery = enabled;
+    m_showingCase = false;
+    if (enabled)
+        caseRotationTimer->start();
+    else
+        caseRotationTimer->stop();
+    if (!currentBatteryStatus.isEmpty())
+        updateIconFromBattery(currentBatteryStatus);
+    emit showCaseBatteryChanged(enabled);
+}
+
 void TrayIconManager::TrayIconManager::updateBatteryStatus(const QString &status)
 {
-    trayIcon->setToolTip(tr("Battery Status: ") + status);
-    updateIconFromBattery(status);
+    // DeviceInfo already coalesces bursts of battery packets
+    currentBatteryStatus = status;
+    trayIcon->setToolTip(tr("Battery Status: ") + currentBatteryStatus);
+    QString compact = compactBatteryStatus(currentBatteryStatus);
+    batteryHeaderAction->setText(compact);
+    batteryHeaderAction->setVisible(!compact.isEmpty());
+    updateIconFromBattery(currentBatteryStatus);
+    updateFallbackNotification();
+}
+
+QString TrayIconManager::compactBatteryStatus(const QString &status)
+{
+    static const QRegularExpression entryPattern("^(\\w+): (\\d+)%");
+    static const QMap<QString, QString> shortNames{{"Left", "L"}, {"Right", "R"}};
+
+    QStringList parts;
+    for (const QString &part : status.split(", "))
+    {
+        QRegularExpressionMatch match = entryPattern.match(part);
+        if (match.hasMatch() && match.captured(2).toInt() > 0)
+            parts << shortNames.value(match.captured(1), match.captured(1)) + " " + match.captured(2) + "%";
+    }
+    return parts.join(" · ");
+}
+
+void TrayIconManager::setIconFontFamily(const QString &family)
+{
+    QStringList chain;
+    if (!family.isEmpty())
+    {
+        chain << family;
+    }
+    chain << "Arial" << "DejaVu Sans";
+
+    QStringList missing;
+    for (const QString &candidate : chain)
+    {
+        if (QFontDatabase::hasFamily(candidate))
+        {
+            iconFont = QFont(candidate, 12, QFont::Bold);
+            m_iconFontStatus = missing.isEmpty() ? candidate
+                                                 : tr("%1 (fallback, not found: %2)").arg(candidate, missing.join(", "));
+            break;
+        }
+        missing << candidate;
+    }
+
+    if (missing.size() == chain.size())
+    {
+        iconFont = QFontDatabase::systemFont(QFontDatabase::GeneralFont);
+        iconFont.setPointSize(12);
+        iconFont.setBold(true);
+        m_iconFontStatus = tr("%1 (system default, not found: %2)").arg(iconFont.family(), missing.join(", "));
+    }
+
+    if (!missing.isEmpty())
+    {
+        LOG_WARN("Tray icon font fallback: " << m_iconFontStatus);
+    }
+    currentIconKey.clear();
 }
 
 void TrayIconManager::updateNoiseControlState(NoiseControlMode mode)
@@ -47,6 +202,8 @@ void TrayIconManager::updateNoiseControlState(NoiseControlMode mode)
     {
         action->setChecked(action->data().toInt() == (int)mode);
     }
+    m_currentMode = mode;
+    updateFallbackNotification();
 }
 
 void TrayIconManager::updateConversationalAwareness(bool enabled)
@@ -54,8 +211,27 @@ void TrayIconManager::updateConversationalAwareness(bool enabled)
     caToggleAction->setChecked(enabled);
 }
 
+void TrayIconManager::updateAllowOffOption(bool allowed)
+{
+    allowOffAction->setChecked(allowed);
+    m_offAllowed = allowed;
+    for (QAction *action : noiseControlGroup->actions())
+    {
+        if (action->data().toInt() == (int)NoiseControlMode::Off)
+        {
+            action->setVisible(allowed);
+        }
+    }
+}
+
 void TrayIconManager::setupMenuActions()
 {
+    // Live battery levels, filled in once the AirPods report them
+    batteryHeaderAction = new QAction(trayMenu);
+    batteryHeaderAction->setEnabled(false);
+    batteryHeaderAction->setVisible(false);
+    trayMenu->addAction(batteryHeaderAction);
+
     // Open action
     QAction *openAction = new QAction(tr("Open"), trayMenu);
     trayMenu->addAction(openAction);
@@ -76,27 +252,34 @@ void TrayIconManager::setupMenuActions()
     connect(caToggleAction, &QAction::triggered, this, [this](bool checked)
             { emit conversationalAwarenessToggled(checked); });
 
+    allowOffAction = new QAction(tr("Allow Off Mode"), trayMenu);
+    allowOffAction->setCheckable(true);
+    allowOffAction->setChecked(true);
+    trayMenu->addAction(allowOffAction);
+    connect(allowOffAction, &QAction::triggered, this, [this](bool checked)
+            { emit allowOffOptionToggled(checked); });
+
     trayMenu->addSeparator();
 
     // Noise Control Options
     noiseControlGroup = new QActionGroup(trayMenu);
-    const QPair<QString, NoiseControlMode> noiseOptions[] = {
-        {tr("Adaptive"), NoiseControlMode::Adaptive},
-        {tr("Transparency"), NoiseControlMode::Transparency},
-        {tr("Noise Cancellation"), NoiseControlMode::NoiseCancellation},
-        {tr("Off"), NoiseControlMode::Off}};
+    const NoiseControlMode noiseOptions[] = {NoiseControlMode::Adaptive, NoiseControlMode::Transparency,
+                                             NoiseControlMode::NoiseCancellation, NoiseControlMode::Off};
 
-    for (auto option : noiseOptions)
+    for (NoiseControlMode mode : noiseOptions)
     {
-        QAction *action = new QAction(option.first, trayMenu);
+        QAction *action = new QAction(noiseControlModeLabel(mode), trayMenu);
         action->setCheckable(true);
-        action->setData((int)option.second);
+        action->setData((int)mode);
         noiseControlGroup->addAction(action);
         trayMenu->addAction(action);
-        connect(action, &QAction::triggered, this, [this, mode = option.second]()
+        connect(action, &QAction::triggered, this, [this, mode]()
                 { emit noiseControlChanged(mode); });
     }
 
+    recentMenu = trayMenu->addMenu(tr("Recent"));
+    recentMenu->setEnabled(false);
+
     trayMenu->addSeparator();
 
     // Quit action
@@ -105,42 +288,218 @@ void TrayIconManager::setupMenuActions()
     connect(quitAction, &QAction::triggered, qApp, &QApplication::quit);
 }
 
+QString TrayIconManager::noiseControlModeLabel(NoiseControlMode mode)
+{
+    switch (mode)
+    {
+    case NoiseControlMode::Adaptive:
+        return tr("Adaptive");
+    case NoiseControlMode::Transparency:
+        return tr("Transparency");
+    case NoiseControlMode::NoiseCancellation:
+        return tr("Noise Cancellation");
+    case NoiseControlMode::Off:
+        return tr("Off");
+    }
+    return QString();
+}
+
+void TrayIconManager::recordModeChange(NoiseControlMode mode, const QString &origin)
+{
+    static constexpr int MaxRecentModes = 5;
+
+    QAction *action = new QAction(tr("%1 (%2, %3)").arg(noiseControlModeLabel(mode), origin,
+                                                        QTime::currentTime().toString("HH:mm")),
+                                  recentMenu);
+    connect(action, &QAction::triggered, this, [this, mode]()
+            { emit noiseControlChanged(mode); });
+
+    QList<QAction *> actions = recentMenu->actions();
+    recentMenu->insertAction(actions.isEmpty() ? nullptr : actions.first(), action);
+    while (recentMenu->actions().size() > MaxRecentModes)
+        delete recentMenu->actions().last();
+    recentMenu->setEnabled(!m_readOnly);
+}
+
 void TrayIconManager::updateIconFromBattery(const QString &status)
 {
-    int leftLevel = 0;
-    int rightLevel = 0;
     int minLevel = 0;
+    int caseLevel = 0;
 
     if (!status.isEmpty())
     {
-        // Parse the battery status string
-        QStringList parts = status.split(", ");
-        if (parts.size() >= 2) {
-            leftLevel = parts[0].split(": ")[1].replace("%", "").toInt();
-            rightLevel = parts[1].split(": ")[1].replace("%", "").toInt();
-            minLevel = (leftLevel == 0) ? rightLevel : (rightLevel == 0) ? leftLevel
-                                                                    : qMin(leftLevel, rightLevel);
-        } else if (parts.size() == 1) {
-            minLevel = parts[0].split(": ")[1].replace("%", "").toInt();
+        // Parse the battery status string, "Left: 80%, Right: 75% (In case), Case: 50%" or "Headset: 80%"
+        static const QRegularExpression entryPattern("^(\\w+): (\\d+)%( \\(In case\\))?$");
+        QList<int> activeLevels;
+        QList<int> inCaseLevels;
+        for (const QString &part : status.split(", "))
+        {
+            QRegularExpressionMatch match = entryPattern.match(part);
+            if (!match.hasMatch())
+                continue;
+
+            int level = match.captured(2).toInt();
+            if (match.captured(1) == "Case")
+                caseLevel = level;
+            if (level == 0 || match.captured(1) == "Case")
+                continue;
+            (match.hasCaptured(3) ? inCaseLevels : activeLevels).append(level);
         }
+
+        // With one bud in the case the number follows the bud that's actually in use
+        if (activeLevels.isEmpty())
+            activeLevels = inCaseLevels;
+        if (!activeLevels.isEmpty())
+            minLevel = *std::min_element(activeLevels.cbegin(), activeLevels.cend());
     }
 
-    QPixmap pixmap(32, 32);
-    pixmap.fill(Qt::transparent);
-    QPainter painter(&pixmap);
-    painter.setPen(Qt::white);
-    painter.setFont(QFont("Arial", 12, QFont::Bold));
-    painter.drawText(pixmap.rect(), Qt::AlignCenter, QString::number(minLevel) + "%");
-    painter.end();
+    const QString text = m_showCaseBattery && m_showingCase && caseLevel > 0
+                             ? "C" + QString::number(caseLevel)
+                             : QString::number(minLevel) + "%";
+    const QFont &font = iconFont;
+    const QColor color = Qt::white;
 
-    trayIcon->setIcon(QIcon(pixmap));
+    // Identical battery values produce identical icons, don't re-render or re-send them
+    const QString key = text + '|' + font.toString() + '|' + color.name() + (m_warningBadge ? "|!" : "");
+    if (key == currentIconKey)
+    {
+        return;
+    }
+
+    QPixmap *pixmap = iconCache.object(key);
+    if (!pixmap)
+    {
+        pixmap = new QPixmap(32, 32);
+        pixmap->fill(Qt::transparent);
+        QPainter painter(pixmap);
+        painter.setPen(color);
+        painter.setFont(font);
+        painter.drawText(pixmap->rect(), Qt::AlignCenter, text);
+        if (m_warningBadge)
+        {
+            painter.setRenderHint(QPainter::Antialiasing);
+            painter.setPen(Qt::NoPen);
+            painter.setBrush(QColor(255, 149, 0));
+            painter.drawEllipse(QRect(22, 0, 10, 10));
+        }
+        painter.end();
+        iconCache.insert(key, pixmap);
+    }
+
+    currentIconKey = key;
+    trayIcon->setIcon(QIcon(*pixmap));
+}
+
+void TrayIconManager::updateFallbackNotification()
+{
+    if (!m_notificationFallback || m_fallbackDismissed || currentBatteryStatus.isEmpty())
+        return;
+    // Without the id of the first notification a second call would open another one
+    if (m_fallbackCallPending)
+    {
+        m_fallbackUpdateQueued = true;
+        return;
+    }
+
+    // Action keys are the mode values, "default" is sent when the notification body is clicked
+    QStringList actions{"default", tr("Open")};
+    for (NoiseControlMode mode : {NoiseControlMode::NoiseCancellation, NoiseControlMode::Transparency,
+                                  NoiseControlMode::Adaptive, NoiseControlMode::Off})
+    {
+        if (!m_readOnly && mode != m_currentMode && (mode != NoiseControlMode::Off || m_offAllowed))
+            actions << QString::number((int)mode) << noiseControlModeLabel(mode);
+    }
+
+    QVariantMap hints{{"resident", true}, {"urgency", QVariant::fromValue<uchar>(0)}};
+    QDBusMessage notify = QDBusMessage::createMethodCall("org.freedesktop.Notifications", "/org/freedesktop/Notifications",
+                                                         "org.freedesktop.Notifications", "Notify");
+    notify << QApplication::applicationDisplayName() << m_fallbackNotificationId << "audio-headphones"
+           << noiseControlModeLabel(m_currentMode) << currentBatteryStatus << actions << hints << 0;
+    m_fallbackCallPending = true;
+    auto *watcher = new QDBusPendingCallWatcher(QDBusConnection::sessionBus().asyncCall(notify), this);
+    connect(watcher, &QDBusPendingCallWatcher::finished, this, [this, generation = m_fallbackGeneration](QDBusPendingCallWatcher *call)
+            {
+                call->deleteLater();
+                m_fallbackCallPending = false;
+                QDBusPendingReply<uint> reply = *call;
+                if (reply.isError())
+                {
+                    LOG_WARN("Failed to show the control notification: " << reply.error().message());
+                    m_fallbackUpdateQueued = false;
+                    return;
+                }
+                // Closed while the call was on its way
+                if (generation != m_fallbackGeneration)
+                    closeNotification(reply.value());
+                else
+                    m_fallbackNotificationId = reply.value();
+                if (m_fallbackUpdateQueued)
+                {
+                    m_fallbackUpdateQueued = false;
+                    updateFallbackNotification();
+                } });
+}
+
+void TrayIconManager::closeNotification(uint id)
+{
+    QDBusMessage close = QDBusMessage::createMethodCall("org.freedesktop.Notifications", "/org/freedesktop/Notifications",
+                                                        "org.freedesktop.Notifications", "CloseNotification");
+    close << id;
+    QDBusConnection::sessionBus().asyncCall(close);
+}
+
+void TrayIconManager::closeFallbackNotification()
+{
+    m_fallbackDismissed = false;
+    m_fallbackUpdateQueued = false;
+    ++m_fallbackGeneration;
+    if (m_fallbackNotificationId == 0)
+        return;
+
+    closeNotification(m_fallbackNotificationId);
+    m_fallbackNotificationId = 0;
+}
+
+void TrayIconManager::onNotificationActionInvoked(uint id, const QString &actionKey)
+{
+    if (id != m_fallbackNotificationId)
+        return;
+
+    if (actionKey == "default")
+    {
+        emit openApp();
+        return;
+    }
+    bool ok = false;
+    int mode = actionKey.toInt(&ok);
+    if (ok)
+        emit noiseControlChanged(static_cast<NoiseControlMode>(mode));
+}
+
+void TrayIconManager::onNotificationClosed(uint id, uint reason)
+{
+    if (id != m_fallbackNotificationId)
+        return;
+
+    m_fallbackNotificationId = 0;
+    // 2 = dismissed by the user, keep it away until the next connection
+    m_fallbackDismissed = reason == 2;
 }
 
 void TrayIconManager::onTrayIconActivated(QSystemTrayIcon::ActivationReason reason)
 {
     if (reason == QSystemTrayIcon::Trigger)
     {
-        emit trayClicked();
+        // The first click of a double click also arrives as Trigger, hold it back until it's clear which one it is
+        if (m_doubleClickEnabled)
+            singleClickTimer->start();
+        else
+            emit trayClicked();
+    }
+    else if (reason == QSystemTrayIcon::DoubleClick && m_doubleClickEnabled)
+    {
+        singleClickTimer->stop();
+        emit trayDoubleClicked();
     }
 }
 
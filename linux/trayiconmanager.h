@@ -1,5 +1,9 @@
 #include <QObject>
 #include <QSystemTrayIcon>
+#include <QTimer>
+#include <QCache>
+#include <QPixmap>
+#include <QFont>
 
 #include "enums.h"
 
@@ -11,6 +15,7 @@ class TrayIconManager : public QObject
 {
     Q_OBJECT
     Q_PROPERTY(bool notificationsEnabled READ notificationsEnabled WRITE setNotificationsEnabled NOTIFY notificationsEnabledChanged)
+    Q_PROPERTY(bool showCaseBattery READ showCaseBattery WRITE setShowCaseBattery NOTIFY showCaseBatteryChanged)
 
 public:
     explicit TrayIconManager(QObject *parent = nullptr);
@@ -21,8 +26,27 @@ public:
 
     void updateConversationalAwareness(bool enabled);
 
+    // Adds a listening mode change to the Recent submenu, origin is e.g. "tray" or "AirPods"
+    void recordModeChange(AirpodsTrayApp::Enums::NoiseControlMode mode, const QString &origin);
+
+    // Hides Off from the listening modes when the AirPods don't allow it
+    void updateAllowOffOption(bool allowed);
+
     void showNotification(const QString &title, const QString &message);
 
+    // Notification that stays until dismissed, shown even when regular notifications are off
+    void showPersistentAlert(const QString &title, const QString &message, bool playSound);
+
+    // Small orange dot on the icon for problems that need attention
+    void setWarningBadge(bool visible);
+
+    // Disables every menu entry that would send a command to the AirPods
+    void setReadOnly(bool readOnly);
+
+    // Resolves the font used for the battery number once, falling back along a fixed chain
+    void setIconFontFamily(const QString &family);
+    QString iconFontStatus() const { return m_iconFontStatus; }
+
     bool notificationsEnabled() const { return m_notificationsEnabled; }
     void setNotificationsEnabled(bool enabled)
     {
@@ -33,33 +57,107 @@ public:
         }
     }
 
+    // With a double click action the single click waits for the double click interval first
+    void setDoubleClickEnabled(bool enabled) { m_doubleClickEnabled = enabled; }
+
+    bool showCaseBattery() const { return m_showCaseBattery; }
+    void setShowCaseBattery(bool enabled);
+
+    // Glyph of the device model (buds or headphones), for the battery header and the idle icon
+    void setModelIcon(const QString &icon)
+    {
+        m_modelIcon = icon.isEmpty() ? QString() : ":/icons/assets/" + icon;
+        batteryHeaderAction->setIcon(m_modelIcon.isEmpty() ? QIcon() : QIcon(m_modelIcon));
+        if (currentBatteryStatus.isEmpty())
+            trayIcon->setIcon(QIcon(idleIcon()));
+    }
+
     void resetTrayIcon()
     {
-        trayIcon->setIcon(QIcon(":/icons/assets/airpods.png"));
-        trayIcon->setToolTip("");
+        currentBatteryStatus.clear();
+        currentIconKey.clear();
+        m_warningBadge = false;
+        batteryHeaderAction->setVisible(false);
+        closeFallbackNotification();
+        trayIcon->setIcon(QIcon(idleIcon()));
+        trayIcon->setToolTip(phoneToolTip());
+    }
+
+    // Battery relayed from the phone, only shown while the AirPods aren't connected here
+    void setPhoneStatus(const QString &status)
+    {
+        m_phoneStatus = status;
+        if (currentBatteryStatus.isEmpty())
+            trayIcon->setToolTip(phoneToolTip());
     }
 
 signals:
     void notificationsEnabledChanged(bool enabled);
+    void showCaseBatteryChanged(bool enabled);
 
 private slots:
     void onTrayIconActivated(QSystemTrayIcon::ActivationReason reason);
+    void onNotificationActionInvoked(uint id, const QString &actionKey);
+    void onNotificationClosed(uint id, uint reason);
 
 private:
     QSystemTrayIcon *trayIcon;
     QMenu *trayMenu;
+    QAction *batteryHeaderAction;
     QAction *caToggleAction;
+    QAction *allowOffAction;
     QActionGroup *noiseControlGroup;
+    QMenu *recentMenu;
     bool m_notificationsEnabled = true;
+    bool m_readOnly = false;
+    bool m_doubleClickEnabled = false;
+    bool m_warningBadge = false;
+    QTimer *singleClickTimer;
+    // Alternates the icon between the buds and the case when showCaseBattery is on
+    QTimer *caseRotationTimer;
+    bool m_showCaseBattery = false;
+    bool m_showingCase = false;
+    QString currentBatteryStatus;
+    QString m_phoneStatus;
+    QString m_modelIcon;
+    // Recently rendered icons keyed by text, font and color
+    QCache<QString, QPixmap> iconCache{16};
+    QString currentIconKey;
+    QFont iconFont;
+    QString m_iconFontStatus;
+    // Without a StatusNotifier host the controls live in a resident notification instead
+    bool m_notificationFallback = false;
+    bool m_fallbackDismissed = false;
+    uint m_fallbackNotificationId = 0;
+    // Notify is called asynchronously, updates while it's on its way are sent once it returns
+    bool m_fallbackCallPending = false;
+    bool m_fallbackUpdateQueued = false;
+    uint m_fallbackGeneration = 0;
+    AirpodsTrayApp::Enums::NoiseControlMode m_currentMode = AirpodsTrayApp::Enums::NoiseControlMode::Off;
+    bool m_offAllowed = true;
 
     void setupMenuActions();
+    QString idleIcon() const { return m_modelIcon.isEmpty() ? ":/icons/assets/airpods.png" : m_modelIcon; }
+
+    static QString noiseControlModeLabel(AirpodsTrayApp::Enums::NoiseControlMode mode);
+
+
+    // "Left: 80%, Right: 75% (In case), Case: 50%" -> "L 80% · R 75% · Case 50%"
+    static QString compactBatteryStatus(const QString &status);
+    QString phoneToolTip() const { return m_phoneStatus.isEmpty() ? QString() : tr("On your phone: ") + m_phoneStatus; }
 
     void updateIconFromBattery(const QString &status);
 
+    void updateFallbackNotification();
+    void closeFallbackNotification();
+    void closeNotification(uint id);
+
 signals:
     void trayClicked();
+    void trayDoubleClicked();
     void noiseControlChanged(AirpodsTrayApp::Enums::NoiseControlMode);
     void conversationalAwarenessToggled(bool enabled);
+    void allowOffOptionToggled(bool allowed);
     void openApp();
     void openSettings();
 };
\ No newline at end of file
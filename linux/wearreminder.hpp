@@ -0,0 +1,97 @@
+#pragma once
+
+#include <QDateTime>
+#include <QObject>
+#include <QTimer>
+
+// Reminds to take a break after wearing the AirPods for a while. Taking them out briefly
+// doesn't count as a break, and nothing is shown during the quiet hours.
+class WearReminder : public QObject
+{
+    Q_OBJECT
+
+public:
+    explicit WearReminder(QObject *parent = nullptr) : QObject(parent)
+    {
+        m_timer.setInterval(60 * 1000);
+        connect(&m_timer, &QTimer::timeout, this, &WearReminder::check);
+    }
+
+    // 0 turns the reminder off
+    void setIntervalMinutes(int minutes)
+    {
+        m_intervalMinutes = minutes;
+        if (m_intervalMinutes > 0 && m_inEar)
+            m_timer.start();
+        else
+            m_timer.stop();
+    }
+
+    // Invalid times disable the quiet hours, the range may wrap around midnight
+    void setQuietHours(const QTime &start, const QTime &end)
+    {
+        m_quietStart = start;
+        m_quietEnd = end;
+    }
+
+    void setInEar(bool inEar)
+    {
+        if (inEar == m_inEar)
+            return;
+        m_inEar = inEar;
+
+        QDateTime now = QDateTime::currentDateTime();
+        if (!inEar)
+        {
+            m_removedAt = now;
+            m_timer.stop();
+            return;
+        }
+
+        if (!m_wearStart.isValid() || (m_removedAt.isValid() && m_removedAt.msecsTo(now) >= BreakMs))
+        {
+            m_wearStart = now;
+            m_reminded = 0;
+        }
+        m_removedAt = QDateTime();
+        if (m_intervalMinutes > 0)
+            m_timer.start();
+    }
+
+    static bool isQuiet(const QTime &time, const QTime &start, const QTime &end)
+    {
+        if (!start.isValid() || !end.isValid() || start == end)
+            return false;
+        if (start < end)
+            return time >= start && time < end;
+        return time >= start || time < end;
+    }
+
+signals:
+    void reminderDue(int minutesWorn);
+
+private:
+    static constexpr qint64 BreakMs = 5 * 60 * 1000;
+
+    void check()
+    {
+        if (m_intervalMinutes <= 0 || !m_wearStart.isValid())
+            return;
+
+        int minutes = static_cast<int>(m_wearStart.secsTo(QDateTime::currentDateTime()) / 60);
+        if (minutes < m_intervalMinutes * (m_reminded + 1))
+            return;
+        m_reminded = minutes / m_intervalMinutes;
+        if (!isQuiet(QTime::currentTime(), m_quietStart, m_quietEnd))
+            emit reminderDue(minutes);
+    }
+
+    QTimer m_timer;
+    int m_intervalMinutes = 0;
+    QTime m_quietStart;
+    QTime m_quietEnd;
+    bool m_inEar = false;
+    QDateTime m_wearStart;
+    QDateTime m_removedAt;
+    int m_reminded = 0;
+};